@@ -25,6 +25,7 @@ export const CloseEscrowAccountStruct = new beet.BeetArgsStruct<{
  * @property [] tokenAccount Token account
  * @property [] edition Edition account
  * @property [_writable_, **signer**] payer Wallet paying for the transaction and new account
+ * @property [_writable_] rentRecipient (optional) Account receiving the escrow rent: the recorded rent recipient or funder, or the escrow treasury
  * @category Instructions
  * @category CloseEscrowAccount
  * @category generated
@@ -37,6 +38,7 @@ export type CloseEscrowAccountInstructionAccounts = {
   edition: web3.PublicKey;
   payer: web3.PublicKey;
   systemProgram?: web3.PublicKey;
+  rentRecipient?: web3.PublicKey;
 };
 
 export const closeEscrowAccountInstructionDiscriminator = 39;
@@ -94,6 +96,14 @@ export function createCloseEscrowAccountInstruction(
     },
   ];
 
+  if (accounts.rentRecipient != null) {
+    keys.push({
+      pubkey: accounts.rentRecipient,
+      isWritable: true,
+      isSigner: false,
+    });
+  }
+
   const ix = new web3.TransactionInstruction({
     programId,
     keys,
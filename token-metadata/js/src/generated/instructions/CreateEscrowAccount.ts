@@ -7,15 +7,35 @@
 
 import * as beet from '@metaplex-foundation/beet';
 import * as web3 from '@solana/web3.js';
+import {
+  CreateEscrowAccountArgs,
+  createEscrowAccountArgsBeet,
+} from '../types/CreateEscrowAccountArgs';
 
 /**
  * @category Instructions
  * @category CreateEscrowAccount
  * @category generated
  */
-export const CreateEscrowAccountStruct = new beet.BeetArgsStruct<{
-  instructionDiscriminator: number;
-}>([['instructionDiscriminator', beet.u8]], 'CreateEscrowAccountInstructionArgs');
+export type CreateEscrowAccountInstructionArgs = {
+  createEscrowAccountArgs: CreateEscrowAccountArgs;
+};
+/**
+ * @category Instructions
+ * @category CreateEscrowAccount
+ * @category generated
+ */
+export const CreateEscrowAccountStruct = new beet.FixableBeetArgsStruct<
+  CreateEscrowAccountInstructionArgs & {
+    instructionDiscriminator: number;
+  }
+>(
+  [
+    ['instructionDiscriminator', beet.u8],
+    ['createEscrowAccountArgs', createEscrowAccountArgsBeet],
+  ],
+  'CreateEscrowAccountInstructionArgs',
+);
 /**
  * Accounts required by the _CreateEscrowAccount_ instruction
  *
@@ -25,7 +45,7 @@ export const CreateEscrowAccountStruct = new beet.BeetArgsStruct<{
  * @property [] tokenAccount Token account of the token
  * @property [] edition Edition account
  * @property [_writable_, **signer**] payer Wallet paying for the transaction and new account
- * @property [**signer**] authority (optional) Authority/creator of the escrow account
+ * @property [**signer**] authority (optional) Authority/creator of the escrow account, passed when has_authority is set
  * @category Instructions
  * @category CreateEscrowAccount
  * @category generated
@@ -47,16 +67,20 @@ export const createEscrowAccountInstructionDiscriminator = 38;
  * Creates a _CreateEscrowAccount_ instruction.
  *
  * @param accounts that will be accessed while the instruction is processed
+ * @param args to provide as instruction data to the program
+ *
  * @category Instructions
  * @category CreateEscrowAccount
  * @category generated
  */
 export function createCreateEscrowAccountInstruction(
   accounts: CreateEscrowAccountInstructionAccounts,
+  args: CreateEscrowAccountInstructionArgs,
   programId = new web3.PublicKey('metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s'),
 ) {
   const [data] = CreateEscrowAccountStruct.serialize({
     instructionDiscriminator: createEscrowAccountInstructionDiscriminator,
+    ...args,
   });
   const keys: web3.AccountMeta[] = [
     {
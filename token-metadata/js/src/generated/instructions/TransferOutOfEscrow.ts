@@ -26,7 +26,7 @@ export type TransferOutOfEscrowInstructionArgs = {
  * @category TransferOutOfEscrow
  * @category generated
  */
-export const TransferOutOfEscrowStruct = new beet.BeetArgsStruct<
+export const TransferOutOfEscrowStruct = new beet.FixableBeetArgsStruct<
   TransferOutOfEscrowInstructionArgs & {
     instructionDiscriminator: number;
   }
@@ -47,7 +47,8 @@ export const TransferOutOfEscrowStruct = new beet.BeetArgsStruct<
  * @property [_writable_] attributeDst Token account, owned by TM, destination for the new attribute
  * @property [] escrowMint Mint account that the escrow is attached
  * @property [] escrowAccount Token account that holds the token the escrow is attached to
- * @property [**signer**] authority (optional) Authority/creator of the escrow account
+ * @property [**signer**] authority (optional) Authority/creator of the escrow account, passed when has_authority is set
+ * @property [] metadata Metadata account of the token the escrow is attached to
  * @category Instructions
  * @category TransferOutOfEscrow
  * @category generated
@@ -65,6 +66,7 @@ export type TransferOutOfEscrowInstructionAccounts = {
   tokenProgram?: web3.PublicKey;
   rent?: web3.PublicKey;
   authority?: web3.PublicKey;
+  metadata: web3.PublicKey;
 };
 
 export const transferOutOfEscrowInstructionDiscriminator = 40;
@@ -153,6 +155,11 @@ export function createTransferOutOfEscrowInstruction(
       isSigner: true,
     });
   }
+  keys.push({
+    pubkey: accounts.metadata,
+    isWritable: false,
+    isSigner: false,
+  });
 
   const ix = new web3.TransactionInstruction({
     programId,
@@ -8,13 +8,17 @@
 import * as beet from '@metaplex-foundation/beet';
 export type TransferOutOfEscrowArgs = {
   amount: beet.bignum;
+  hasAuthority: beet.COption<boolean>;
 };
 
 /**
  * @category userTypes
  * @category generated
  */
-export const transferOutOfEscrowArgsBeet = new beet.BeetArgsStruct<TransferOutOfEscrowArgs>(
-  [['amount', beet.u64]],
+export const transferOutOfEscrowArgsBeet = new beet.FixableBeetArgsStruct<TransferOutOfEscrowArgs>(
+  [
+    ['amount', beet.u64],
+    ['hasAuthority', beet.coption(beet.bool)],
+  ],
   'TransferOutOfEscrowArgs',
 );
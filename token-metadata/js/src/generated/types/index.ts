@@ -1,6 +1,7 @@
 export * from './ApproveUseAuthorityArgs';
 export * from './Collection';
 export * from './CollectionDetails';
+export * from './CreateEscrowAccountArgs';
 export * from './CreateMasterEditionArgs';
 export * from './CreateMetadataAccountArgs';
 export * from './CreateMetadataAccountArgsV2';
@@ -9,6 +10,7 @@ export * from './Creator';
 export * from './Data';
 export * from './DataV2';
 export * from './EscrowAuthority';
+export * from './EscrowMultisigArgs';
 export * from './Key';
 export * from './MintNewEditionFromMasterEditionViaTokenArgs';
 export * from './MintPrintingTokensViaTokenArgs';
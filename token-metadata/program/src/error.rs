@@ -1,6 +1,8 @@
 //! Error types
 
 use num_derive::FromPrimitive;
+#[cfg(feature = "client")]
+use num_traits::FromPrimitive as _;
 use solana_program::{
     decode_error::DecodeError,
     msg,
@@ -516,6 +518,138 @@ pub enum MetadataError {
     /// 130 - Cannot create NFT with no Freeze Authority.
     #[error("Cannot create NFT with no Freeze Authority.")]
     NoFreezeAuthoritySet,
+
+    /// 131 - Invalid authority type
+    #[error("Invalid authority type")]
+    InvalidAuthorityType,
+
+    /// 132 - Escrow still holds tokens
+    #[error("Escrow still holds tokens")]
+    EscrowNotEmpty,
+
+    /// 133 - Too many escrows in a single batch
+    #[error("Too many escrows in a single batch")]
+    EscrowBatchTooLarge,
+
+    /// 134 - Failed to borrow account data
+    #[error("Failed to borrow account data")]
+    DataBorrowFailed,
+
+    /// 135 - Rent recipient does not match the escrow
+    #[error("Rent recipient does not match the escrow")]
+    InvalidRentRecipient,
+
+    /// 136 - Signer does not hold the token the escrow is attached to
+    #[error("Signer does not hold the token the escrow is attached to")]
+    NotEscrowTokenOwner,
+
+    /// 137 - Signer is not the creator of the escrow
+    #[error("Signer is not the creator of the escrow")]
+    NotEscrowCreator,
+
+    /// 138 - Mint must have a supply of one and no decimals to own an escrow
+    #[error("Mint must have a supply of one and no decimals to own an escrow")]
+    InvalidMintForEscrow,
+
+    /// 139 - Token is frozen or delegated
+    #[error("Token is frozen or delegated")]
+    LockedToken,
+
+    /// 140 - Metadata account is larger than the maximum size
+    #[error("Metadata account is larger than the maximum size")]
+    InvalidMetadataSize,
+
+    /// 141 - The base token can't be deposited into its own escrow
+    #[error("The base token can't be deposited into its own escrow")]
+    CannotDepositBaseToken,
+
+    /// 142 - Escrow authority must be a signer
+    #[error("Escrow authority must be a signer")]
+    InvalidAuthoritySigner,
+
+    /// 143 - Token account is not for the mint of the metadata
+    #[error("Token account is not for the mint of the metadata")]
+    InvalidTokenAccountMint,
+
+    /// 144 - Escrow is locked
+    #[error("Escrow is locked")]
+    EscrowLocked,
+
+    /// 145 - Escrow account is not the expected size
+    #[error("Escrow account is not the expected size")]
+    InvalidEscrowAccountSize,
+
+    /// 146 - Escrow memo is too long
+    #[error("Escrow memo is too long")]
+    EscrowMemoTooLong,
+
+    /// 147 - Fee account is not the escrow treasury
+    #[error("Fee account is not the escrow treasury")]
+    InvalidFeeAccount,
+
+    /// 148 - Base token of the escrow has not been burned
+    #[error("Base token of the escrow has not been burned")]
+    BaseTokenNotBurned,
+
+    /// 149 - Metadata is mutable
+    #[error("Metadata is mutable")]
+    MetadataIsMutable,
+
+    /// 150 - Token account is not the associated token account of its owner
+    #[error("Token account is not the associated token account of its owner")]
+    InvalidTokenAccount,
+
+    /// 151 - Transfer amount must be greater than zero
+    #[error("Transfer amount must be greater than zero")]
+    InvalidTransferAmount,
+
+    /// 152 - Escrow multisig members or threshold are invalid
+    #[error("Escrow multisig members or threshold are invalid")]
+    InvalidEscrowMultisig,
+
+    /// 153 - Not enough escrow multisig members signed
+    #[error("Not enough escrow multisig members signed")]
+    NotEnoughMultisigSigners,
+
+    /// 154 - Token standard of the base token is not the expected one
+    #[error("Token standard of the base token is not the expected one")]
+    InvalidTokenStandard,
+
+    /// 155 - Token is not on the escrow allowlist
+    #[error("Token is not on the escrow allowlist")]
+    TokenNotAllowed,
+
+    /// 156 - Escrow allowlist has too many mints
+    #[error("Escrow allowlist has too many mints")]
+    EscrowAllowlistTooLong,
+
+    /// 157 - Account is too small for the data written to it
+    #[error("Account is too small for the data written to it")]
+    InvalidAccountSize,
+
+    /// 158 - Not enough accounts were passed to the instruction
+    #[error("Not enough accounts were passed to the instruction")]
+    NotEnoughAccounts,
+
+    /// 159 - Non-fungible token accounts must hold exactly one token
+    #[error("Non-fungible token accounts must hold exactly one token")]
+    InvalidAmount,
+
+    /// 160 - Payer cannot cover the rent of the new account
+    #[error("Payer cannot cover the rent of the new account")]
+    InsufficientFunds,
+
+    /// 161 - Account must be the funder recorded on the escrow
+    #[error("Account must be the funder recorded on the escrow")]
+    InvalidFunder,
+
+    /// 162 - Signer is not the upgrade authority of the program
+    #[error("Signer is not the upgrade authority of the program")]
+    InvalidTreasuryAuthority,
+
+    /// 163 - Withdrawal exceeds the treasury balance or leaves it below rent exemption
+    #[error("Withdrawal exceeds the treasury balance or leaves it below rent exemption")]
+    InvalidTreasuryWithdrawal,
 }
 
 impl PrintProgramError for MetadataError {
@@ -535,3 +669,183 @@ impl<T> DecodeError<T> for MetadataError {
         "Metadata Error"
     }
 }
+
+#[cfg(feature = "client")]
+impl MetadataError {
+    /// Static message for this error, the same as its `Display` output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetadataError::InstructionUnpackError => "Failed to unpack instruction data",
+            MetadataError::InstructionPackError => "Failed to pack instruction data",
+            MetadataError::NotRentExempt => "Lamport balance below rent-exempt threshold",
+            MetadataError::AlreadyInitialized => "Already initialized",
+            MetadataError::Uninitialized => "Uninitialized",
+            MetadataError::InvalidMetadataKey => " Metadata's key must match seed of ['metadata', program id, mint] provided",
+            MetadataError::InvalidEditionKey => "Edition's key must match seed of ['metadata', program id, name, 'edition'] provided",
+            MetadataError::UpdateAuthorityIncorrect => "Update Authority given does not match",
+            MetadataError::UpdateAuthorityIsNotSigner => "Update Authority needs to be signer to update metadata",
+            MetadataError::NotMintAuthority => "You must be the mint authority and signer on this transaction",
+            MetadataError::InvalidMintAuthority => "Mint authority provided does not match the authority on the mint",
+            MetadataError::NameTooLong => "Name too long",
+            MetadataError::SymbolTooLong => "Symbol too long",
+            MetadataError::UriTooLong => "URI too long",
+            MetadataError::UpdateAuthorityMustBeEqualToMetadataAuthorityAndSigner => "Update authority must be equivalent to the metadata's authority and also signer of this transaction",
+            MetadataError::MintMismatch => "Mint given does not match mint on Metadata",
+            MetadataError::EditionsMustHaveExactlyOneToken => "Editions must have exactly one token",
+            MetadataError::MaxEditionsMintedAlready => "Maximum editions printed already",
+            MetadataError::TokenMintToFailed => "Token mint to failed",
+            MetadataError::MasterRecordMismatch => "The master edition record passed must match the master record on the edition given",
+            MetadataError::DestinationMintMismatch => "The destination account does not have the right mint",
+            MetadataError::EditionAlreadyMinted => "An edition can only mint one of its kind!",
+            MetadataError::PrintingMintDecimalsShouldBeZero => "Printing mint decimals should be zero",
+            MetadataError::OneTimePrintingAuthorizationMintDecimalsShouldBeZero => "OneTimePrintingAuthorization mint decimals should be zero",
+            MetadataError::EditionMintDecimalsShouldBeZero => "EditionMintDecimalsShouldBeZero",
+            MetadataError::TokenBurnFailed => "Token burn failed",
+            MetadataError::TokenAccountOneTimeAuthMintMismatch => "The One Time authorization mint does not match that on the token account!",
+            MetadataError::DerivedKeyInvalid => "Derived key invalid",
+            MetadataError::PrintingMintMismatch => "The Printing mint does not match that on the master edition!",
+            MetadataError::OneTimePrintingAuthMintMismatch => "The One Time Printing Auth mint does not match that on the master edition!",
+            MetadataError::TokenAccountMintMismatch => "The mint of the token account does not match the Printing mint!",
+            MetadataError::TokenAccountMintMismatchV2 => "The mint of the token account does not match the master metadata mint!",
+            MetadataError::NotEnoughTokens => "Not enough tokens to mint a limited edition",
+            MetadataError::PrintingMintAuthorizationAccountMismatch => "The mint on your authorization token holding account does not match your Printing mint!",
+            MetadataError::AuthorizationTokenAccountOwnerMismatch => "The authorization token account has a different owner than the update authority for the master edition!",
+            MetadataError::Disabled => "This feature is currently disabled.",
+            MetadataError::CreatorsTooLong => "Creators list too long",
+            MetadataError::CreatorsMustBeAtleastOne => "Creators must be at least one if set",
+            MetadataError::MustBeOneOfCreators => "If using a creators array, you must be one of the creators listed",
+            MetadataError::NoCreatorsPresentOnMetadata => "This metadata does not have creators",
+            MetadataError::CreatorNotFound => "This creator address was not found",
+            MetadataError::InvalidBasisPoints => "Basis points cannot be more than 10000",
+            MetadataError::PrimarySaleCanOnlyBeFlippedToTrue => "Primary sale can only be flipped to true and is immutable",
+            MetadataError::OwnerMismatch => "Owner does not match that on the account given",
+            MetadataError::NoBalanceInAccountForAuthorization => "This account has no tokens to be used for authorization",
+            MetadataError::ShareTotalMustBe100 => "Share total must equal 100 for creator array",
+            MetadataError::ReservationExists => "This reservation list already exists!",
+            MetadataError::ReservationDoesNotExist => "This reservation list does not exist!",
+            MetadataError::ReservationNotSet => "This reservation list exists but was never set with reservations",
+            MetadataError::ReservationAlreadyMade => "This reservation list has already been set!",
+            MetadataError::BeyondMaxAddressSize => "Provided more addresses than max allowed in single reservation",
+            MetadataError::NumericalOverflowError => "NumericalOverflowError",
+            MetadataError::ReservationBreachesMaximumSupply => "This reservation would go beyond the maximum supply of the master edition!",
+            MetadataError::AddressNotInReservation => "Address not in reservation!",
+            MetadataError::CannotVerifyAnotherCreator => "You cannot unilaterally verify another creator, they must sign",
+            MetadataError::CannotUnverifyAnotherCreator => "You cannot unilaterally unverify another creator",
+            MetadataError::SpotMismatch => "In initial reservation setting, spots remaining should equal total spots",
+            MetadataError::IncorrectOwner => "Incorrect account owner",
+            MetadataError::PrintingWouldBreachMaximumSupply => "printing these tokens would breach the maximum supply limit of the master edition",
+            MetadataError::DataIsImmutable => "Data is immutable",
+            MetadataError::DuplicateCreatorAddress => "No duplicate creator addresses",
+            MetadataError::ReservationSpotsRemainingShouldMatchTotalSpotsAtStart => "Reservation spots remaining should match total spots when first being created",
+            MetadataError::InvalidTokenProgram => "Invalid token program",
+            MetadataError::DataTypeMismatch => "Data type mismatch",
+            MetadataError::BeyondAlottedAddressSize => "Beyond alotted address size in reservation!",
+            MetadataError::ReservationNotComplete => "The reservation has only been partially alotted",
+            MetadataError::TriedToReplaceAnExistingReservation => "You cannot splice over an existing reservation!",
+            MetadataError::InvalidOperation => "Invalid operation",
+            MetadataError::InvalidOwner => "Invalid Owner",
+            MetadataError::PrintingMintSupplyMustBeZeroForConversion => "Printing mint supply must be zero for conversion",
+            MetadataError::OneTimeAuthMintSupplyMustBeZeroForConversion => "One Time Auth mint supply must be zero for conversion",
+            MetadataError::InvalidEditionIndex => "You tried to insert one edition too many into an edition mark pda",
+            MetadataError::ReservationArrayShouldBeSizeOne => "In the legacy system the reservation needs to be of size one for cpu limit reasons",
+            MetadataError::IsMutableCanOnlyBeFlippedToFalse => "Is Mutable can only be flipped to false",
+            MetadataError::CollectionCannotBeVerifiedInThisInstruction => "Cannont Verify Collection in this Instruction",
+            MetadataError::Removed => "This instruction was deprecated in a previous release and is now removed",
+            MetadataError::MustBeBurned => "This token use method is burn and there are no remaining uses, it must be burned",
+            MetadataError::InvalidUseMethod => "This use method is invalid",
+            MetadataError::CannotChangeUseMethodAfterFirstUse => "Cannot Change Use Method after the first use",
+            MetadataError::CannotChangeUsesAfterFirstUse => "Cannot Change Remaining or Available uses after the first use",
+            MetadataError::CollectionNotFound => "Collection Not Found on Metadata",
+            MetadataError::InvalidCollectionUpdateAuthority => "Collection Update Authority is invalid",
+            MetadataError::CollectionMustBeAUniqueMasterEdition => "Collection Must Be a Unique Master Edition v2",
+            MetadataError::UseAuthorityRecordAlreadyExists => "The Use Authority Record Already Exists, to modify it Revoke, then Approve",
+            MetadataError::UseAuthorityRecordAlreadyRevoked => "The Use Authority Record is empty or already revoked",
+            MetadataError::Unusable => "This token has no uses",
+            MetadataError::NotEnoughUses => "There are not enough Uses left on this token.",
+            MetadataError::CollectionAuthorityRecordAlreadyExists => "This Collection Authority Record Already Exists.",
+            MetadataError::CollectionAuthorityDoesNotExist => "This Collection Authority Record Does Not Exist.",
+            MetadataError::InvalidUseAuthorityRecord => "This Use Authority Record is invalid.",
+            MetadataError::InvalidCollectionAuthorityRecord => "This Collection Authority Record is invalid.",
+            MetadataError::InvalidFreezeAuthority => "Metadata does not match the freeze authority on the mint",
+            MetadataError::InvalidDelegate => "All tokens in this account have not been delegated to this user.",
+            MetadataError::CannotAdjustVerifiedCreator => "Creator can not be adjusted once they are verified.",
+            MetadataError::CannotRemoveVerifiedCreator => "Verified creators cannot be removed.",
+            MetadataError::CannotWipeVerifiedCreators => "Can not wipe verified creators.",
+            MetadataError::NotAllowedToChangeSellerFeeBasisPoints => "Not allowed to change seller fee basis points.",
+            MetadataError::EditionOverrideCannotBeZero => "Edition override cannot be zero",
+            MetadataError::InvalidUser => "Invalid User",
+            MetadataError::RevokeCollectionAuthoritySignerIncorrect => "Revoke Collection Authority signer is incorrect",
+            MetadataError::TokenCloseFailed => "Token close failed",
+            MetadataError::UnsizedCollection => "Can't use this function on unsized collection",
+            MetadataError::SizedCollection => "Can't use this function on a sized collection",
+            MetadataError::MissingCollectionMetadata => "Can't burn a verified member of a collection w/o providing collection metadata account",
+            MetadataError::NotAMemberOfCollection => "This NFT is not a member of the specified collection.",
+            MetadataError::NotVerifiedMemberOfCollection => "This NFT is not a verified member of the specified collection.",
+            MetadataError::NotACollectionParent => "This NFT is not a collection parent NFT.",
+            MetadataError::CouldNotDetermineTokenStandard => "Could not determine a TokenStandard type.",
+            MetadataError::MissingEditionAccount => "This mint account has an edition but none was provided.",
+            MetadataError::NotAMasterEdition => "This edition is not a Master Edition",
+            MetadataError::MasterEditionHasPrints => "This Master Edition has existing prints",
+            MetadataError::BorshDeserializationError => "Borsh Deserialization Error",
+            MetadataError::CannotUpdateVerifiedCollection => "Cannot update a verified colleciton in this command",
+            MetadataError::CollectionMasterEditionAccountInvalid => "Edition account doesnt match collection ",
+            MetadataError::AlreadyVerified => "Item is already verified.",
+            MetadataError::AlreadyUnverified => "Item is already unverified.",
+            MetadataError::NotAPrintEdition => "This edition is not a Print Edition",
+            MetadataError::InvalidMasterEdition => "Invalid Master Edition",
+            MetadataError::InvalidPrintEdition => "Invalid Print Edition",
+            MetadataError::InvalidEditionMarker => "Invalid Edition Marker",
+            MetadataError::ReservationListDeprecated => "Reservation List is Deprecated",
+            MetadataError::PrintEditionDoesNotMatchMasterEdition => "Print Edition does not match Master Edition",
+            MetadataError::EditionNumberGreaterThanMaxSupply => "Edition Number greater than max supply",
+            MetadataError::MustUnverify => "Must unverify before migrating collections.",
+            MetadataError::InvalidEscrowBumpSeed => "Invalid Escrow Account Bump Seed",
+            MetadataError::MustBeEscrowAuthority => "Must Escrow Authority",
+            MetadataError::InvalidSystemProgram => "Invalid System Program",
+            MetadataError::MustBeNonFungible => "Must be a Non Fungible Token",
+            MetadataError::InsufficientTokens => "Insufficient tokens for transfer",
+            MetadataError::BorshSerializationError => "Borsh Serialization Error",
+            MetadataError::NoFreezeAuthoritySet => "Cannot create NFT with no Freeze Authority.",
+            MetadataError::InvalidAuthorityType => "Invalid authority type",
+            MetadataError::EscrowNotEmpty => "Escrow still holds tokens",
+            MetadataError::EscrowBatchTooLarge => "Too many escrows in a single batch",
+            MetadataError::DataBorrowFailed => "Failed to borrow account data",
+            MetadataError::InvalidRentRecipient => "Rent recipient does not match the escrow",
+            MetadataError::NotEscrowTokenOwner => "Signer does not hold the token the escrow is attached to",
+            MetadataError::NotEscrowCreator => "Signer is not the creator of the escrow",
+            MetadataError::InvalidMintForEscrow => "Mint must have a supply of one and no decimals to own an escrow",
+            MetadataError::LockedToken => "Token is frozen or delegated",
+            MetadataError::InvalidMetadataSize => "Metadata account is larger than the maximum size",
+            MetadataError::CannotDepositBaseToken => "The base token can't be deposited into its own escrow",
+            MetadataError::InvalidAuthoritySigner => "Escrow authority must be a signer",
+            MetadataError::InvalidTokenAccountMint => "Token account is not for the mint of the metadata",
+            MetadataError::EscrowLocked => "Escrow is locked",
+            MetadataError::InvalidEscrowAccountSize => "Escrow account is not the expected size",
+            MetadataError::EscrowMemoTooLong => "Escrow memo is too long",
+            MetadataError::InvalidFeeAccount => "Fee account is not the escrow treasury",
+            MetadataError::BaseTokenNotBurned => "Base token of the escrow has not been burned",
+            MetadataError::MetadataIsMutable => "Metadata is mutable",
+            MetadataError::InvalidTokenAccount => "Token account is not the associated token account of its owner",
+            MetadataError::InvalidTransferAmount => "Transfer amount must be greater than zero",
+            MetadataError::InvalidEscrowMultisig => "Escrow multisig members or threshold are invalid",
+            MetadataError::NotEnoughMultisigSigners => "Not enough escrow multisig members signed",
+            MetadataError::InvalidTokenStandard => "Token standard of the base token is not the expected one",
+            MetadataError::TokenNotAllowed => "Token is not on the escrow allowlist",
+            MetadataError::EscrowAllowlistTooLong => "Escrow allowlist has too many mints",
+            MetadataError::InvalidAccountSize => "Account is too small for the data written to it",
+            MetadataError::NotEnoughAccounts => "Not enough accounts were passed to the instruction",
+            MetadataError::InvalidAmount => "Non-fungible token accounts must hold exactly one token",
+            MetadataError::InsufficientFunds => "Payer cannot cover the rent of the new account",
+            MetadataError::InvalidFunder => "Account must be the funder recorded on the escrow",
+            MetadataError::InvalidTreasuryAuthority => "Signer is not the upgrade authority of the program",
+            MetadataError::InvalidTreasuryWithdrawal => "Withdrawal exceeds the treasury balance or leaves it below rent exemption",
+        }
+    }
+}
+
+/// Maps a custom program error code returned by Token Metadata to its message, for
+/// off-chain tooling. Returns `None` for codes the program doesn't define.
+#[cfg(feature = "client")]
+pub fn error_to_str(code: u32) -> Option<&'static str> {
+    MetadataError::from_u32(code).map(|error| error.as_str())
+}
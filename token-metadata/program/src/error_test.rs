@@ -0,0 +1,24 @@
+#![cfg(all(test, feature = "client"))]
+
+mod error_to_str {
+    use num_traits::FromPrimitive;
+
+    use crate::error::{error_to_str, MetadataError};
+
+    #[test]
+    fn every_error_has_a_message() {
+        let mut code = 0;
+
+        while let Some(error) = MetadataError::from_u32(code) {
+            assert_eq!(error_to_str(code), Some(error.to_string().as_str()));
+            code += 1;
+        }
+
+        assert_eq!(code, MetadataError::InvalidTreasuryWithdrawal as u32 + 1);
+    }
+
+    #[test]
+    fn unknown_code_has_no_message() {
+        assert_eq!(error_to_str(u32::MAX), None);
+    }
+}
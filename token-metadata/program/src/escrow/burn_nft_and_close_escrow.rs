@@ -0,0 +1,168 @@
+use crate::{
+    error::MetadataError,
+    escrow::{
+        events::{emit_event, EscrowClosed, EscrowEvent},
+        next_recorded_rent_destination,
+        pda::assert_escrow_derivation_tagged,
+    },
+    instruction::MetadataInstruction,
+    processor::process_burn_nft,
+    state::{EscrowAuthority, TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{
+        assert_account_key, assert_escrow_empty, assert_owned_by, assert_signer, close_account_raw,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct BurnNftAndCloseEscrowArgs {
+    /// Number of escrow owned token accounts passed after the token program.
+    pub escrow_token_accounts: u8,
+}
+
+/// Burns a master edition NFT and closes its escrow. `escrow_token_accounts` are the token
+/// accounts owned by the escrow, which must all be empty, and `rent_destination` must be the
+/// rent recipient recorded on the escrow, or else its funder, if any.
+#[allow(clippy::too_many_arguments)]
+pub fn burn_nft_and_close_escrow(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+    token: Pubkey,
+    edition: Pubkey,
+    escrow_token_accounts: &[Pubkey],
+    rent_destination: Option<Pubkey>,
+    collection_metadata: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(escrow_account, false),
+        AccountMeta::new(metadata, false),
+        AccountMeta::new(owner, true),
+        AccountMeta::new(mint, false),
+        AccountMeta::new(token, false),
+        AccountMeta::new(edition, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    for escrow_token_account in escrow_token_accounts {
+        accounts.push(AccountMeta::new_readonly(*escrow_token_account, false));
+    }
+
+    if let Some(rent_destination) = rent_destination {
+        accounts.push(AccountMeta::new(rent_destination, false));
+    }
+
+    if let Some(collection_metadata) = collection_metadata {
+        accounts.push(AccountMeta::new(collection_metadata, false));
+    }
+
+    let data = MetadataInstruction::BurnNftAndCloseEscrow(BurnNftAndCloseEscrowArgs {
+        escrow_token_accounts: escrow_token_accounts.len() as u8,
+    })
+    .try_to_vec()
+    .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_burn_nft_and_close_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: BurnNftAndCloseEscrowArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let owner_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let token_info = next_account_info(account_info_iter)?;
+    let edition_info = next_account_info(account_info_iter)?;
+    let spl_token_program_info = next_account_info(account_info_iter)?;
+
+    assert_signer(owner_info)?;
+    assert_owned_by(escrow_account_info, program_id)?;
+    let toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+    // The escrow must be attached to the NFT being burned.
+    assert_account_key(mint_info, &toe.base_token, MetadataError::MintMismatch)?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        mint_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    // A creator owned escrow belongs to its creator, so a holder can't close it by burning.
+    // A multisig escrow is closed by its members through `CloseEscrowAccount` instead.
+    match toe.authority {
+        EscrowAuthority::TokenOwner => (),
+        EscrowAuthority::Creator(creator) => {
+            if creator != *owner_info.key {
+                return Err(MetadataError::MustBeEscrowAuthority.into());
+            }
+        }
+        EscrowAuthority::Multisig(_) => return Err(MetadataError::InvalidAuthorityType.into()),
+    }
+
+    // Any token accounts the escrow owns are passed in after the token program.
+    let remaining_accounts = account_info_iter.as_slice();
+    let token_account_count = usize::from(args.escrow_token_accounts);
+    let escrow_token_accounts = remaining_accounts
+        .get(..token_account_count)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    assert_escrow_empty(escrow_account_info.key, escrow_token_accounts)?;
+    *account_info_iter = remaining_accounts[token_account_count..].iter();
+
+    // Escrows that recorded a rent recipient or a funder refund it instead of the owner.
+    let rent_destination_info =
+        next_recorded_rent_destination(&toe, account_info_iter)?.unwrap_or(owner_info);
+
+    // The burn handler takes care of the metadata, edition, token and collection checks.
+    let mut burn_accounts = vec![
+        metadata_info.clone(),
+        owner_info.clone(),
+        mint_info.clone(),
+        token_info.clone(),
+        edition_info.clone(),
+        spl_token_program_info.clone(),
+    ];
+    if let Some(collection_metadata_info) = account_info_iter.next() {
+        burn_accounts.push(collection_metadata_info.clone());
+    }
+
+    process_burn_nft(program_id, &burn_accounts)?;
+
+    close_account_raw(rent_destination_info, escrow_account_info)?;
+
+    emit_event(&EscrowEvent::Closed(EscrowClosed {
+        escrow: *escrow_account_info.key,
+        base_token: toe.base_token,
+        rent_destination: *rent_destination_info.key,
+    }))?;
+
+    Ok(())
+}
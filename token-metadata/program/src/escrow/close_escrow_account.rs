@@ -1,24 +1,34 @@
 use crate::{
     error::MetadataError,
-    instruction::MetadataInstruction,
-    state::{
-        EscrowAuthority, Metadata, TokenMetadataAccount, TokenOwnedEscrow, TokenStandard,
-        ESCROW_POSTFIX, PREFIX,
+    escrow::{
+        assert_escrow_token_standard,
+        events::{emit_event, EscrowClosed, EscrowEvent},
+        pda::{
+            assert_escrow_derivation_tagged, find_escrow_attribute_ata,
+            find_escrow_treasury_account, find_escrow_treasury_seeds,
+        },
     },
+    instruction::MetadataInstruction,
+    state::{EscrowAuthority, Metadata, TokenMetadataAccount, TokenOwnedEscrow},
     utils::{
-        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
-        check_token_standard, close_account_raw,
+        assert_account_key, assert_derivation, assert_edition_valid, assert_escrow_empty,
+        assert_holds_base_token, assert_initialized, assert_multisig_signers, assert_owned_by,
+        assert_signer, close_account_raw, is_token_holder,
     },
 };
 use borsh::BorshSerialize;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
+    account_info::{next_account_info, next_account_infos, AccountInfo},
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
     pubkey::Pubkey,
     system_program,
 };
 
+/// Closes an escrow. `rent_recipient` must be the recipient recorded when the escrow was
+/// created, if any.
+#[allow(clippy::too_many_arguments)]
 pub fn close_escrow_account(
     program_id: Pubkey,
     escrow_account: Pubkey,
@@ -27,8 +37,9 @@ pub fn close_escrow_account(
     edition_account: Pubkey,
     payer_account: Pubkey,
     token_account: Pubkey,
+    rent_recipient: Option<Pubkey>,
 ) -> Instruction {
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(escrow_account, false),
         AccountMeta::new_readonly(metadata_account, false),
         AccountMeta::new_readonly(mint_account, false),
@@ -37,6 +48,11 @@ pub fn close_escrow_account(
         AccountMeta::new(payer_account, true),
         AccountMeta::new_readonly(system_program::id(), false),
     ];
+
+    if let Some(rent_recipient) = rent_recipient {
+        accounts.push(AccountMeta::new(rent_recipient, false));
+    }
+
     let data = MetadataInstruction::CloseEscrowAccount
         .try_to_vec()
         .unwrap();
@@ -48,6 +64,126 @@ pub fn close_escrow_account(
     }
 }
 
+/// Closes an escrow that has no rent recipient or funder, sweeping its rent into the escrow
+/// treasury instead of refunding the payer.
+#[allow(clippy::too_many_arguments)]
+pub fn close_escrow_account_to_treasury(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    token_account: Pubkey,
+) -> Instruction {
+    let mut instruction = close_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        edition_account,
+        payer_account,
+        token_account,
+        None,
+    );
+
+    let (treasury, _) = find_escrow_treasury_account();
+    instruction.accounts.push(AccountMeta::new(treasury, false));
+
+    instruction
+}
+
+/// Closes an escrow that has no rent recipient, refunding its rent to the `funder` recorded
+/// when a payer funded the escrow for a different authority. Such escrows can only be closed
+/// this way.
+#[allow(clippy::too_many_arguments)]
+pub fn close_escrow_account_to_funder(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    token_account: Pubkey,
+    funder: Pubkey,
+) -> Instruction {
+    let mut instruction = close_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        edition_account,
+        payer_account,
+        token_account,
+        None,
+    );
+
+    instruction.accounts.push(AccountMeta::new(funder, false));
+
+    instruction
+}
+
+/// Closes an escrow after checking that its associated token accounts for each of
+/// `attribute_mints` are empty, the usual cleanup once everything was withdrawn. The close
+/// fails with `EscrowNotEmpty` if any of them still holds tokens.
+#[allow(clippy::too_many_arguments)]
+pub fn close_escrow_if_empty(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    token_account: Pubkey,
+    rent_recipient: Option<Pubkey>,
+    attribute_mints: &[Pubkey],
+) -> Instruction {
+    let mut instruction = close_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        edition_account,
+        payer_account,
+        token_account,
+        rent_recipient,
+    );
+
+    instruction
+        .accounts
+        .extend(attribute_mints.iter().map(|attribute_mint| {
+            AccountMeta::new_readonly(
+                find_escrow_attribute_ata(&escrow_account, attribute_mint),
+                false,
+            )
+        }));
+
+    instruction
+}
+
+/// Next account of `account_info_iter` when the escrow recorded where its rent goes on
+/// close: its rent recipient, or else the payer that funded it for its authority. `None`
+/// when it recorded neither, leaving the destination to the closing instruction. Every
+/// instruction that closes an escrow goes through this, so none of them can skip a refund.
+pub(crate) fn next_recorded_rent_destination<'a, 'b, I>(
+    toe: &TokenOwnedEscrow,
+    account_info_iter: &mut I,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError>
+where
+    I: Iterator<Item = &'a AccountInfo<'b>>,
+{
+    let (recorded, error) = match (toe.rent_recipient, toe.funder) {
+        (Some(rent_recipient), _) => (rent_recipient, MetadataError::InvalidRentRecipient),
+        (None, Some(funder)) => (funder, MetadataError::InvalidFunder),
+        (None, None) => return Ok(None),
+    };
+
+    let rent_destination_info = next_account_info(account_info_iter)?;
+    assert_account_key(rent_destination_info, &recorded, error)?;
+
+    Ok(Some(rent_destination_info))
+}
+
 pub fn process_close_escrow_account(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -72,52 +208,95 @@ pub fn process_close_escrow_account(
     assert_signer(payer_account_info)?;
 
     let metadata: Metadata = Metadata::from_account_info(metadata_account_info)?;
+    metadata.validate_lengths()?;
 
     // Mint account passed in must be the mint of the metadata account passed in.
-    if &metadata.mint != mint_account_info.key {
-        return Err(MetadataError::MintMismatch.into());
-    }
+    assert_account_key(
+        mint_account_info,
+        &metadata.mint,
+        MetadataError::MintMismatch,
+    )?;
 
-    if check_token_standard(mint_account_info, Some(edition_account_info))?
-        != TokenStandard::NonFungible
-    {
-        return Err(MetadataError::MustBeNonFungible.into());
-    };
+    assert_edition_valid(program_id, mint_account_info.key, edition_account_info)?;
 
-    let bump_seed = assert_derivation(
-        program_id,
-        escrow_account_info,
-        &[
-            PREFIX.as_bytes(),
-            program_id.as_ref(),
-            mint_account_info.key.as_ref(),
-            ESCROW_POSTFIX.as_bytes(),
-        ],
-    )?;
+    // Fungible asset escrows can only exist if they were allowed at creation.
+    assert_escrow_token_standard(mint_account_info, edition_account_info, true, true)?;
 
     assert_owned_by(escrow_account_info, program_id)?;
-    let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
     let toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
 
+    // The escrow must be attached to the mint passed in.
+    assert_account_key(
+        mint_account_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        mint_account_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+
     if bump_seed != toe.bump {
         return Err(MetadataError::InvalidEscrowBumpSeed.into());
     }
 
+    let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+
+    assert_account_key(
+        mint_account_info,
+        &token_account.mint,
+        MetadataError::MintMismatch,
+    )?;
+
+    assert_holds_base_token(token_account.amount, toe.token_standard.clone())?;
+
     match toe.authority {
         EscrowAuthority::TokenOwner => {
-            if *payer_account_info.key != token_account.owner {
-                return Err(MetadataError::MustBeEscrowAuthority.into());
+            if !is_token_holder(&token_account, payer_account_info.key) {
+                return Err(MetadataError::InvalidAuthorityType.into());
             }
         }
         EscrowAuthority::Creator(authority) => {
             if *payer_account_info.key != authority {
-                return Err(MetadataError::MustBeEscrowAuthority.into());
+                return Err(MetadataError::InvalidAuthorityType.into());
             }
         }
+        // The members follow the system program.
+        EscrowAuthority::Multisig(ref multisig) => {
+            let member_infos = next_account_infos(account_info_iter, multisig.members.len())?;
+            assert_multisig_signers(multisig, member_infos)?;
+        }
     }
 
+    // Escrows that recorded a rent recipient or a funder refund it instead of the authority.
+    let rent_destination_info = match next_recorded_rent_destination(&toe, account_info_iter)? {
+        Some(rent_destination_info) => rent_destination_info,
+        // Escrow token accounts are always owned by the token program, so any other
+        // account following the required ones is the treasury.
+        None => match account_info_iter.as_slice().first() {
+            Some(info) if *info.owner != spl_token::id() => {
+                let treasury_info = next_account_info(account_info_iter)?;
+                assert_derivation(program_id, treasury_info, &find_escrow_treasury_seeds())?;
+                treasury_info
+            }
+            _ => payer_account_info,
+        },
+    };
+
+    // Any token accounts the escrow owns are passed in as remaining accounts.
+    assert_escrow_empty(escrow_account_info.key, account_info_iter.as_slice())?;
+
     // Close the account.
-    close_account_raw(payer_account_info, escrow_account_info)?;
+    close_account_raw(rent_destination_info, escrow_account_info)?;
+
+    emit_event(&EscrowEvent::Closed(EscrowClosed {
+        escrow: *escrow_account_info.key,
+        base_token: toe.base_token,
+        rent_destination: *rent_destination_info.key,
+    }))?;
 
     Ok(())
 }
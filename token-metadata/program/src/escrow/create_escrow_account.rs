@@ -1,24 +1,201 @@
 use crate::{
     error::MetadataError,
-    escrow::pda::find_escrow_seeds,
+    escrow::{
+        events::{emit_event, EscrowCreated, EscrowEvent, EscrowTransfer},
+        pda::{
+            assert_escrow_derivation_tagged, find_escrow_account, find_escrow_account_tagged,
+            find_escrow_allowlist_account, find_escrow_attribute_ata, find_escrow_seeds_tagged,
+            find_escrow_treasury_account,
+        },
+        transfer_into::assert_deposit_allowed,
+    },
     instruction::MetadataInstruction,
+    pda::derive_nft_accounts,
     state::{
-        EscrowAuthority, Key, Metadata, TokenMetadataAccount, TokenOwnedEscrow, TokenStandard,
+        EscrowAuthority, EscrowMultisig, Key, Metadata, TokenMetadataAccount, TokenOwnedEscrow,
+        TokenStandard, TOKEN_OWNED_ESCROW_VERSION,
     },
     utils::{
-        assert_derivation, assert_initialized, assert_owned_by, assert_signer,
-        check_token_standard, create_or_allocate_account_raw,
+        assert_account_key, assert_edition_valid, assert_holds_base_token, assert_multisig_signers,
+        assert_owned_by, assert_rent_exempt, assert_signer, assert_token_owned_by,
+        assert_token_program_matches_package, check_token_standard, checked_minimum_balance,
+        create_or_allocate_account_raw, effective_token_controller, get_mint_authority,
+        get_mint_decimals, get_mint_supply, is_missing_edition, is_token_holder, transfer_spl,
+        write_account_data, TokenTransferParams,
     },
 };
-use borsh::BorshSerialize;
+use borsh::{maybestd::io::Error as BorshError, BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
-    program_memory::sol_memcpy,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey,
     pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
 };
 
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct CreateEscrowAccountArgs {
+    /// Allow a `FungibleAsset` with a fixed supply of exactly one token to own an escrow.
+    pub allow_fungible_asset: bool,
+    /// Amount of an attribute token to transfer into the new escrow in the same instruction.
+    pub initial_deposit_amount: Option<u64>,
+    /// Account to refund the escrow rent to when it is closed, instead of the closing authority.
+    pub rent_recipient: Option<Pubkey>,
+    /// Allow a frozen or delegated token account to prove ownership of the base token.
+    pub allow_locked_token: bool,
+    /// Only let the token owner or the metadata update authority create the escrow, so
+    /// third parties can't claim creator escrows on someone else's NFT.
+    pub require_known_authority: bool,
+    /// Only allow NFTs that are verified members of the collection whose mint is passed as
+    /// the last account.
+    pub verify_collection: bool,
+    /// Succeed without changes if the escrow already exists for the same base token and
+    /// authority, so clients can safely retry a create whose confirmation was lost.
+    pub idempotent: bool,
+    /// Create a Creator Owned Escrow even when the authority holds the token, which would
+    /// otherwise get a Token Owned Escrow.
+    pub force_creator: bool,
+    /// Run every check a real create would, then stop before the escrow is allocated, so
+    /// wallets can validate a setup before committing rent.
+    pub simulate: bool,
+    /// Note logged through the SPL Memo program once the escrow is created, for audit
+    /// trails. The memo program is then passed as the last account.
+    pub memo: Option<Vec<u8>>,
+    /// Only allow NFTs whose metadata is immutable, so their traits can't change under the
+    /// escrow.
+    pub require_immutable: bool,
+    /// Only accept the associated token account of the holder as proof of ownership.
+    pub require_ata: bool,
+    /// Create a multisig escrow over these members instead. Every member is then passed
+    /// last, in sorted order, and at least `threshold` of them must sign.
+    pub multisig: Option<EscrowMultisigArgs>,
+    /// Allow a print edition to own an escrow. Only master editions can by default, as
+    /// prints share their master's traits.
+    pub allow_print_edition: bool,
+    /// Whether an authority other than the payer follows the system program. The builders
+    /// set this from the authority they are given.
+    pub has_authority: bool,
+    /// Tag of the escrow among the escrows of the same mint and authority, see
+    /// `find_escrow_account_tagged`. `ESCROW_DEFAULT_TAG` creates the untagged escrow.
+    pub tag: u8,
+}
+
+impl borsh::de::BorshDeserialize for CreateEscrowAccountArgs {
+    fn deserialize(buf: &mut &[u8]) -> ::core::result::Result<Self, BorshError> {
+        // Instructions from clients predating the args carry no data after the discriminant.
+        if buf.is_empty() {
+            return Ok(CreateEscrowAccountArgs::default());
+        }
+
+        Ok(CreateEscrowAccountArgs {
+            allow_fungible_asset: BorshDeserialize::deserialize(buf)?,
+            initial_deposit_amount: BorshDeserialize::deserialize(buf)?,
+            rent_recipient: BorshDeserialize::deserialize(buf)?,
+            allow_locked_token: BorshDeserialize::deserialize(buf)?,
+            require_known_authority: BorshDeserialize::deserialize(buf)?,
+            verify_collection: BorshDeserialize::deserialize(buf)?,
+            idempotent: BorshDeserialize::deserialize(buf)?,
+            force_creator: BorshDeserialize::deserialize(buf)?,
+            simulate: BorshDeserialize::deserialize(buf)?,
+            memo: BorshDeserialize::deserialize(buf)?,
+            require_immutable: BorshDeserialize::deserialize(buf)?,
+            require_ata: BorshDeserialize::deserialize(buf)?,
+            multisig: BorshDeserialize::deserialize(buf)?,
+            allow_print_edition: BorshDeserialize::deserialize(buf)?,
+            has_authority: BorshDeserialize::deserialize(buf)?,
+            tag: BorshDeserialize::deserialize(buf)?,
+        })
+    }
+}
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowMultisigArgs {
+    pub members: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+/// Number of accounts following the authority when an initial deposit is made:
+/// attribute mint, attribute source, escrow attribute account, token program,
+/// associated token program, rent and the escrow allowlist of the update authority.
+pub const INITIAL_DEPOSIT_ACCOUNTS: usize = 7;
+
+/// Longest memo that can be attached to an escrow creation.
+pub const MAX_ESCROW_MEMO_LENGTH: usize = 256;
+
+/// Protocol fee in lamports the payer pays into the escrow treasury for every escrow it
+/// creates. While it is set, the treasury follows the system program of every create.
+/// No fee is charged while it is zero.
+pub const ESCROW_CREATION_FEE: u64 = 0;
+
+/// The SPL Memo program.
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWX6WUF7Mug1kp");
+
+/// Accounts and arguments of a `CreateEscrowAccount` instruction, named so the pubkeys
+/// can't be passed in the wrong order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreateEscrowArgs {
+    pub escrow: Pubkey,
+    pub metadata: Pubkey,
+    pub mint: Pubkey,
+    pub token: Pubkey,
+    pub edition: Pubkey,
+    pub payer: Pubkey,
+    /// Creator of a Creator Owned Escrow, the payer when `None`.
+    pub authority: Option<Pubkey>,
+    pub args: CreateEscrowAccountArgs,
+}
+
+impl CreateEscrowArgs {
+    pub fn instruction(self, program_id: Pubkey) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(self.escrow, false),
+            AccountMeta::new_readonly(self.metadata, false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new_readonly(self.token, false),
+            AccountMeta::new_readonly(self.edition, false),
+            AccountMeta::new(self.payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ];
+
+        if ESCROW_CREATION_FEE != 0 {
+            let (treasury, _) = find_escrow_treasury_account();
+            accounts.push(AccountMeta::new(treasury, false));
+        }
+
+        if let Some(authority) = self.authority {
+            accounts.push(AccountMeta::new_readonly(authority, true));
+        }
+
+        let args = CreateEscrowAccountArgs {
+            has_authority: self.authority.is_some(),
+            ..self.args
+        };
+        let data = MetadataInstruction::CreateEscrowAccount(args)
+            .try_to_vec()
+            .unwrap();
+
+        Instruction {
+            program_id,
+            accounts,
+            data,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn create_escrow_account(
     program_id: Pubkey,
     escrow_account: Pubkey,
@@ -28,53 +205,637 @@ pub fn create_escrow_account(
     edition_account: Pubkey,
     payer_account: Pubkey,
     authority: Option<Pubkey>,
+    args: CreateEscrowAccountArgs,
 ) -> Instruction {
-    let mut accounts = vec![
-        AccountMeta::new(escrow_account, false),
-        AccountMeta::new_readonly(metadata_account, false),
-        AccountMeta::new_readonly(mint_account, false),
-        AccountMeta::new_readonly(token_account, false),
-        AccountMeta::new_readonly(edition_account, false),
-        AccountMeta::new(payer_account, true),
-        AccountMeta::new_readonly(solana_program::system_program::id(), false),
-    ];
-
-    if let Some(authority) = authority {
-        accounts.push(AccountMeta::new_readonly(authority, true));
+    CreateEscrowArgs {
+        escrow: escrow_account,
+        metadata: metadata_account,
+        mint: mint_account,
+        token: token_account,
+        edition: edition_account,
+        payer: payer_account,
+        authority,
+        args,
     }
+    .instruction(program_id)
+}
 
-    let data = MetadataInstruction::CreateEscrowAccount
-        .try_to_vec()
-        .unwrap();
+/// Creates the escrow of `escrow_authority` over the NFT `mint_account`, returning the
+/// instruction together with the derived escrow address for follow-up instructions.
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_account_with_address(
+    program_id: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Option<Pubkey>,
+    escrow_authority: &EscrowAuthority,
+    args: CreateEscrowAccountArgs,
+) -> (Instruction, Pubkey) {
+    let (escrow_account, _) = find_escrow_account_tagged(&mint_account, escrow_authority, args.tag);
+
+    let instruction = create_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        authority,
+        args,
+    );
+
+    (instruction, escrow_account)
+}
+
+/// Creates a token owner escrow for the NFT `mint` held by `wallet`, deriving the metadata,
+/// master edition, escrow and the wallet's associated token account.
+pub fn create_escrow_account_auto(
+    program_id: Pubkey,
+    mint_account: Pubkey,
+    wallet: Pubkey,
+    payer_account: Pubkey,
+) -> Instruction {
+    let (escrow_account, _) = find_escrow_account(&mint_account, &EscrowAuthority::TokenOwner);
+    let nft_accounts = derive_nft_accounts(&program_id, &mint_account);
+    let token_account =
+        spl_associated_token_account::get_associated_token_address(&wallet, &mint_account);
+
+    // The wallet only needs to sign separately when it isn't paying.
+    let authority = if wallet == payer_account {
+        None
+    } else {
+        Some(wallet)
+    };
+
+    create_escrow_account(
+        program_id,
+        escrow_account,
+        nft_accounts.metadata,
+        mint_account,
+        token_account,
+        nft_accounts.master_edition,
+        payer_account,
+        authority,
+        CreateEscrowAccountArgs::default(),
+    )
+}
+
+/// Creates an escrow account for an NFT that must be a verified member of the collection
+/// with mint `collection_mint`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_account_in_collection(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Option<Pubkey>,
+    collection_mint: Pubkey,
+) -> Instruction {
+    let mut instruction = create_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        authority,
+        CreateEscrowAccountArgs {
+            verify_collection: true,
+            ..Default::default()
+        },
+    );
+
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(collection_mint, false));
+
+    instruction
+}
+
+/// Creates a multisig escrow account over `members`, of which `threshold` must sign. Only
+/// the members in `signers` are marked as signing.
+#[allow(clippy::too_many_arguments)]
+pub fn create_multisig_escrow_account(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    mut members: Vec<Pubkey>,
+    threshold: u8,
+    signers: &[Pubkey],
+) -> Instruction {
+    members.sort();
+    members.dedup();
+
+    let mut instruction = create_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        None,
+        CreateEscrowAccountArgs {
+            multisig: Some(EscrowMultisigArgs {
+                members: members.clone(),
+                threshold,
+            }),
+            ..Default::default()
+        },
+    );
+
+    instruction.accounts.extend(
+        members
+            .iter()
+            .map(|member| AccountMeta::new_readonly(*member, signers.contains(member))),
+    );
+
+    instruction
+}
+
+/// Creates an escrow account and logs `memo` through the SPL Memo program.
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_account_with_memo(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Option<Pubkey>,
+    memo: Vec<u8>,
+) -> Instruction {
+    let mut instruction = create_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        authority,
+        CreateEscrowAccountArgs {
+            memo: Some(memo),
+            ..Default::default()
+        },
+    );
+
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(MEMO_PROGRAM_ID, false));
+
+    instruction
+}
 
-    Instruction {
+/// Creates an escrow account and transfers `amount` of the attribute token from
+/// `attribute_src` into a new escrow owned associated token account. Like a later
+/// transfer into the escrow, the deposit is checked against the escrow allowlist of
+/// `update_authority`, the update authority of the base token.
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_account_with_deposit(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Option<Pubkey>,
+    attribute_mint: Pubkey,
+    attribute_src: Pubkey,
+    update_authority: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut instruction = create_escrow_account(
         program_id,
-        accounts,
-        data,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        authority,
+        CreateEscrowAccountArgs {
+            initial_deposit_amount: Some(amount),
+            ..Default::default()
+        },
+    );
+
+    let attribute_dst = find_escrow_attribute_ata(&escrow_account, &attribute_mint);
+    let (allowlist, _) = find_escrow_allowlist_account(&update_authority);
+
+    instruction.accounts.extend([
+        AccountMeta::new_readonly(attribute_mint, false),
+        AccountMeta::new(attribute_src, false),
+        AccountMeta::new(attribute_dst, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+        AccountMeta::new_readonly(allowlist, false),
+    ]);
+
+    instruction
+}
+
+/// Accounts of a `CreateEscrowAccount` instruction, named in the order they are passed in.
+pub struct CreateEscrowAccounts<'a> {
+    pub escrow: &'a AccountInfo<'a>,
+    pub metadata: &'a AccountInfo<'a>,
+    pub mint: &'a AccountInfo<'a>,
+    pub token: &'a AccountInfo<'a>,
+    pub edition: &'a AccountInfo<'a>,
+    pub payer: &'a AccountInfo<'a>,
+    pub system_program: &'a AccountInfo<'a>,
+    /// Escrow treasury collecting `ESCROW_CREATION_FEE`, present while it is set.
+    pub fee_collector: Option<&'a AccountInfo<'a>>,
+    /// Creator of a Creator Owned Escrow, the payer when omitted.
+    pub authority: Option<&'a AccountInfo<'a>>,
+    /// Present when `initial_deposit_amount` is set.
+    pub deposit: Option<InitialDepositAccounts<'a>>,
+    /// Present when `verify_collection` is set.
+    pub collection_mint: Option<&'a AccountInfo<'a>>,
+    /// Present when `memo` is set, the last account unless multisig members follow.
+    pub memo_program: Option<&'a AccountInfo<'a>>,
+    /// Members of a multisig escrow, empty for any other escrow.
+    pub multisig_members: &'a [AccountInfo<'a>],
+}
+
+/// Accounts used to make the initial deposit into a new escrow.
+pub struct InitialDepositAccounts<'a> {
+    pub attribute_mint: &'a AccountInfo<'a>,
+    pub attribute_src: &'a AccountInfo<'a>,
+    pub attribute_dst: &'a AccountInfo<'a>,
+    pub token_program: &'a AccountInfo<'a>,
+    pub ata_program: &'a AccountInfo<'a>,
+    pub rent: &'a AccountInfo<'a>,
+    pub allowlist: &'a AccountInfo<'a>,
+}
+
+impl<'a> CreateEscrowAccounts<'a> {
+    /// Names the accounts of the instruction, working out from `args` which optional
+    /// accounts were passed, and checks the authority signer and the deposit programs.
+    pub fn from_account_infos(
+        accounts: &'a [AccountInfo<'a>],
+        args: &CreateEscrowAccountArgs,
+    ) -> Result<Self, ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+
+        let escrow = next_named_account(accounts, account_info_iter, "escrow")?;
+        let metadata = next_named_account(accounts, account_info_iter, "metadata")?;
+        let mint = next_named_account(accounts, account_info_iter, "mint")?;
+        let token = next_named_account(accounts, account_info_iter, "token_account")?;
+        let edition = next_named_account(accounts, account_info_iter, "edition")?;
+        let payer = next_named_account(accounts, account_info_iter, "payer")?;
+        let system_program = next_named_account(accounts, account_info_iter, "system_program")?;
+
+        // Accounts for the initial deposit, if any, follow the optional authority.
+        let deposit_accounts = if args.initial_deposit_amount.is_some() {
+            INITIAL_DEPOSIT_ACCOUNTS
+        } else {
+            0
+        };
+        let collection_accounts = usize::from(args.verify_collection);
+        let fee_accounts = usize::from(ESCROW_CREATION_FEE != 0);
+        let authority_accounts = usize::from(args.has_authority);
+        let memo_accounts = usize::from(args.memo.is_some());
+        let multisig_accounts = args
+            .multisig
+            .as_ref()
+            .map_or(0, |multisig| multisig.members.len());
+        let optional_accounts = authority_accounts
+            + deposit_accounts
+            + collection_accounts
+            + fee_accounts
+            + memo_accounts
+            + multisig_accounts;
+
+        if account_info_iter.len() < optional_accounts {
+            msg!(
+                "Missing account {}: {} accounts must follow the system program",
+                accounts.len(),
+                optional_accounts
+            );
+            return Err(MetadataError::NotEnoughAccounts.into());
+        }
+        // The args alone decide which accounts are read; any account past them is ignored.
+
+        // The treasury collecting the protocol fee, if any, follows the system program.
+        let fee_collector = if ESCROW_CREATION_FEE != 0 {
+            let fee_collector = next_named_account(accounts, account_info_iter, "fee_collector")?;
+            if *fee_collector.key != find_escrow_treasury_account().0 {
+                return Err(MetadataError::InvalidFeeAccount.into());
+            }
+
+            Some(fee_collector)
+        } else {
+            None
+        };
+
+        let authority = if args.has_authority {
+            let authority_info = next_named_account(accounts, account_info_iter, "authority")?;
+            // The authority decides the escrow type, so it must sign for itself.
+            if !authority_info.is_signer {
+                return Err(MetadataError::InvalidAuthoritySigner.into());
+            }
+            Some(authority_info)
+        } else {
+            None
+        };
+
+        let deposit = if args.initial_deposit_amount.is_some() {
+            let deposit = InitialDepositAccounts {
+                attribute_mint: next_named_account(accounts, account_info_iter, "attribute_mint")?,
+                attribute_src: next_named_account(accounts, account_info_iter, "attribute_src")?,
+                attribute_dst: next_named_account(accounts, account_info_iter, "attribute_dst")?,
+                token_program: next_named_account(accounts, account_info_iter, "token_program")?,
+                ata_program: next_named_account(accounts, account_info_iter, "ata_program")?,
+                rent: next_named_account(accounts, account_info_iter, "rent")?,
+                allowlist: next_named_account(accounts, account_info_iter, "allowlist")?,
+            };
+
+            assert_token_program_matches_package(deposit.token_program)?;
+            if *deposit.ata_program.key != spl_associated_token_account::id() {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+
+            Some(deposit)
+        } else {
+            None
+        };
+
+        let collection_mint = if args.verify_collection {
+            Some(next_named_account(
+                accounts,
+                account_info_iter,
+                "collection_mint",
+            )?)
+        } else {
+            None
+        };
+
+        let memo_program = if args.memo.is_some() {
+            let memo_program = next_named_account(accounts, account_info_iter, "memo_program")?;
+            if *memo_program.key != MEMO_PROGRAM_ID {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+
+            Some(memo_program)
+        } else {
+            None
+        };
+
+        let multisig_members = &account_info_iter.as_slice()[..multisig_accounts];
+
+        Ok(CreateEscrowAccounts {
+            escrow,
+            metadata,
+            mint,
+            token,
+            edition,
+            payer,
+            system_program,
+            fee_collector,
+            authority,
+            deposit,
+            collection_mint,
+            memo_program,
+            multisig_members,
+        })
     }
 }
 
-pub fn process_create_escrow_account(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
+/// Pay `ESCROW_CREATION_FEE` for each of `escrows` new escrows from `payer_info` into the
+/// escrow treasury `fee_collector_info`, checking the treasury received the whole fee.
+pub(crate) fn pay_escrow_creation_fee<'a>(
+    payer_info: &AccountInfo<'a>,
+    fee_collector_info: &AccountInfo<'a>,
+    system_account_info: &AccountInfo<'a>,
+    escrows: u64,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
+    let fee = ESCROW_CREATION_FEE
+        .checked_mul(escrows)
+        .ok_or(MetadataError::NumericalOverflowError)?;
+    let expected_lamports = fee_collector_info
+        .lamports()
+        .checked_add(fee)
+        .ok_or(MetadataError::NumericalOverflowError)?;
 
-    let escrow_account_info = next_account_info(account_info_iter)?;
-    let metadata_account_info = next_account_info(account_info_iter)?;
-    let mint_account_info = next_account_info(account_info_iter)?;
-    let token_account_info = next_account_info(account_info_iter)?;
-    let edition_account_info = next_account_info(account_info_iter)?;
-    let payer_account_info = next_account_info(account_info_iter)?;
-    let system_account_info = next_account_info(account_info_iter)?;
+    invoke(
+        &system_instruction::transfer(payer_info.key, fee_collector_info.key, fee),
+        &[
+            payer_info.clone(),
+            fee_collector_info.clone(),
+            system_account_info.clone(),
+        ],
+    )?;
 
-    let is_using_authority = account_info_iter.len() == 1;
+    if fee_collector_info.lamports() != expected_lamports {
+        return Err(MetadataError::InvalidFeeAccount.into());
+    }
 
-    let maybe_authority_info: Option<&AccountInfo> = if is_using_authority {
-        Some(next_account_info(account_info_iter)?)
-    } else {
-        None
-    };
+    Ok(())
+}
+
+/// Take the next account from `iter`, an iterator over `accounts`, logging the position and
+/// `name` of the account when the client passed too few.
+fn next_named_account<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+    iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    name: &str,
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let position = accounts.len() - iter.len();
+
+    next_account_info(iter).map_err(|_| {
+        msg!("Missing account {}: {}", position, name);
+        MetadataError::NotEnoughAccounts.into()
+    })
+}
+
+pub fn process_create_escrow_account<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: CreateEscrowAccountArgs,
+) -> ProgramResult {
+    process_create_escrow_account_expecting(program_id, accounts, args, None)
+}
+
+/// Create an escrow, failing unless the detected token standard of the base token is
+/// `expected_standard` when one is given.
+pub(crate) fn process_create_escrow_account_expecting<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: CreateEscrowAccountArgs,
+    expected_standard: Option<TokenStandard>,
+) -> ProgramResult {
+    #[cfg(feature = "bench")]
+    solana_program::log::sol_log_compute_units();
+
+    let accounts = CreateEscrowAccounts::from_account_infos(accounts, &args)?;
+    let authority_info = accounts.authority.unwrap_or(accounts.payer);
+
+    if let Some(memo) = &args.memo {
+        if memo.len() > MAX_ESCROW_MEMO_LENGTH {
+            return Err(MetadataError::EscrowMemoTooLong.into());
+        }
+    }
+
+    // The initial deposit follows the same rules as a later transfer into the escrow.
+    if let Some(deposit) = &accounts.deposit {
+        assert_deposit_allowed(
+            program_id,
+            accounts.escrow,
+            accounts.metadata,
+            accounts.mint,
+            deposit.attribute_mint,
+            deposit.attribute_dst,
+            deposit.allowlist,
+        )?;
+    }
+
+    let created = process_create_escrow_account_logic(
+        program_id,
+        CreateEscrowAccountLogicArgs {
+            escrow_account_info: accounts.escrow,
+            metadata_account_info: accounts.metadata,
+            mint_account_info: accounts.mint,
+            token_account_info: accounts.token,
+            edition_account_info: accounts.edition,
+            payer_account_info: accounts.payer,
+            system_account_info: accounts.system_program,
+            authority_info,
+            collection_mint_info: accounts.collection_mint,
+            multisig_member_infos: accounts.multisig_members,
+        },
+        &args,
+        expected_standard,
+    )?;
+
+    // A retried create leaves the existing escrow and its contents as they are, so the
+    // initial deposit isn't made twice. A simulation never creates the escrow to deposit into.
+    if !created {
+        return Ok(());
+    }
+
+    if let Some(fee_collector) = accounts.fee_collector {
+        pay_escrow_creation_fee(accounts.payer, fee_collector, accounts.system_program, 1)?;
+    }
+
+    // The deposit runs in the same instruction, so a failed transfer also rolls back
+    // the escrow creation.
+    if let (Some(amount), Some(deposit)) = (args.initial_deposit_amount, accounts.deposit) {
+        #[allow(deprecated)]
+        let create_escrow_ata_ix = spl_associated_token_account::create_associated_token_account(
+            accounts.payer.key,
+            accounts.escrow.key,
+            deposit.attribute_mint.key,
+        );
+
+        invoke(
+            &create_escrow_ata_ix,
+            &[
+                accounts.payer.clone(),
+                deposit.attribute_dst.clone(),
+                accounts.escrow.clone(),
+                deposit.attribute_mint.clone(),
+                accounts.system_program.clone(),
+                deposit.token_program.clone(),
+                deposit.ata_program.clone(),
+                deposit.rent.clone(),
+            ],
+        )?;
+
+        transfer_spl(TokenTransferParams {
+            source: deposit.attribute_src.clone(),
+            destination: deposit.attribute_dst.clone(),
+            amount,
+            authority: authority_info.clone(),
+            authority_signer_seeds: None,
+            token_program: deposit.token_program.clone(),
+        })?;
+
+        emit_event(&EscrowEvent::TransferIn(EscrowTransfer {
+            escrow: *accounts.escrow.key,
+            mint: *deposit.attribute_mint.key,
+            amount,
+        }))?;
+    }
+
+    if let (Some(memo), Some(memo_program)) = (args.memo, accounts.memo_program) {
+        let memo_ix = Instruction {
+            program_id: *memo_program.key,
+            accounts: vec![],
+            data: memo,
+        };
+
+        invoke(&memo_ix, std::slice::from_ref(memo_program))?;
+    }
+
+    #[cfg(feature = "bench")]
+    solana_program::log::sol_log_compute_units();
+
+    Ok(())
+}
+
+pub struct CreateEscrowAccountLogicArgs<'a> {
+    pub escrow_account_info: &'a AccountInfo<'a>,
+    pub metadata_account_info: &'a AccountInfo<'a>,
+    pub mint_account_info: &'a AccountInfo<'a>,
+    pub token_account_info: &'a AccountInfo<'a>,
+    pub edition_account_info: &'a AccountInfo<'a>,
+    pub payer_account_info: &'a AccountInfo<'a>,
+    pub system_account_info: &'a AccountInfo<'a>,
+    pub authority_info: &'a AccountInfo<'a>,
+    /// Collection mint the NFT must be a verified member of, if any.
+    pub collection_mint_info: Option<&'a AccountInfo<'a>>,
+    /// Members of a multisig escrow, in sorted order.
+    pub multisig_member_infos: &'a [AccountInfo<'a>],
+}
+
+/// Validate the base token and create a single escrow account. Shared by the
+/// single and batched create instructions. Returns `false` when an idempotent create
+/// found the escrow already in place, or a simulation passed, and the escrow was left
+/// untouched. With an `expected_standard`, the base token must have that token standard.
+pub fn process_create_escrow_account_logic(
+    program_id: &Pubkey,
+    accounts: CreateEscrowAccountLogicArgs,
+    args: &CreateEscrowAccountArgs,
+    expected_standard: Option<TokenStandard>,
+) -> Result<bool, ProgramError> {
+    let CreateEscrowAccountLogicArgs {
+        escrow_account_info,
+        metadata_account_info,
+        mint_account_info,
+        token_account_info,
+        edition_account_info,
+        payer_account_info,
+        system_account_info,
+        authority_info,
+        collection_mint_info,
+        multisig_member_infos,
+    } = accounts;
+
+    // Wrapped SOL is fungible whatever its supply looks like, so it never owns an escrow.
+    if *mint_account_info.key == spl_token::native_mint::id() {
+        return Err(MetadataError::InvalidMintForEscrow.into());
+    }
+
+    // The escrow is allocated through the system program, so a spoofed one must not be invoked.
+    if *system_account_info.key != solana_program::system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
 
     assert_owned_by(metadata_account_info, program_id)?;
     assert_owned_by(mint_account_info, &spl_token::id())?;
@@ -82,79 +843,334 @@ pub fn process_create_escrow_account(
     assert_signer(payer_account_info)?;
 
     let metadata: Metadata = Metadata::from_account_info(metadata_account_info)?;
+    metadata.validate_lengths()?;
 
-    // Mint account passed in must be the mint of the metadata account passed in.
-    if &metadata.mint != mint_account_info.key {
-        return Err(MetadataError::MintMismatch.into());
+    if args.require_immutable && metadata.is_mutable {
+        return Err(MetadataError::MetadataIsMutable.into());
     }
 
-    // Only non-fungible tokens (i.e. unique) can have escrow accounts.
-    if check_token_standard(mint_account_info, Some(edition_account_info))?
-        != TokenStandard::NonFungible
-    {
-        return Err(MetadataError::MustBeNonFungible.into());
-    };
-
-    let creator = maybe_authority_info.unwrap_or(payer_account_info);
+    // Mint account passed in must be the mint of the metadata account passed in.
+    assert_account_key(
+        mint_account_info,
+        &metadata.mint,
+        MetadataError::MintMismatch,
+    )?;
 
-    let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+    if let Some(collection_mint_info) = collection_mint_info {
+        match &metadata.collection {
+            None => return Err(MetadataError::CollectionNotFound.into()),
+            Some(collection) if collection.key != *collection_mint_info.key => {
+                return Err(MetadataError::NotAMemberOfCollection.into());
+            }
+            Some(collection) if !collection.verified => {
+                return Err(MetadataError::NotVerifiedMemberOfCollection.into());
+            }
+            Some(_) => (),
+        }
+    }
 
-    if token_account.mint != *mint_account_info.key {
-        return Err(MetadataError::MintMismatch.into());
+    // The edition must be derived from the mint, otherwise another NFT's edition
+    // could be used to pass the token standard check. Clients may pass the system program
+    // for a mint without an edition, which the token standard check then treats as fungible.
+    if *edition_account_info.key != solana_program::system_program::id() {
+        assert_edition_valid(program_id, mint_account_info.key, edition_account_info)?;
     }
 
-    if token_account.amount < 1 {
-        return Err(MetadataError::NotEnoughTokens.into());
+    let token_standard = assert_escrow_token_standard(
+        mint_account_info,
+        edition_account_info,
+        args.allow_fungible_asset,
+        args.allow_print_edition,
+    )?;
+
+    if let Some(expected_standard) = expected_standard {
+        if token_standard != expected_standard {
+            return Err(MetadataError::InvalidTokenStandard.into());
+        }
     }
 
-    if token_account.mint != metadata.mint {
-        return Err(MetadataError::MintMismatch.into());
+    // The mint already matches the metadata, so a mint mismatch here means the token
+    // account belongs to another NFT.
+    let token_account = assert_token_owned_by(token_account_info, mint_account_info.key, None, 1)?;
+    assert_holds_base_token(token_account.amount, Some(token_standard.clone()))?;
+
+    if args.require_ata
+        && *token_account_info.key
+            != spl_associated_token_account::get_associated_token_address(
+                &token_account.owner,
+                mint_account_info.key,
+            )
+    {
+        return Err(MetadataError::InvalidTokenAccount.into());
     }
 
-    let creator_type = if token_account.owner == *creator.key {
-        EscrowAuthority::TokenOwner
+    // A holder, even one that is also the update authority, gets a Token Owned Escrow unless
+    // it asks for a Creator Owned Escrow, which stays with it once the NFT is sold.
+    // The holder is whoever actually controls the token, which is the delegate when the
+    // owner approved it for the full balance.
+    let is_holder = is_token_holder(&token_account, authority_info.key);
+    let creator_type = if let Some(multisig_args) = &args.multisig {
+        assert_known_authority(&metadata, &token_account, authority_info, args)?;
+
+        let multisig = EscrowMultisig::new(multisig_args.members.clone(), multisig_args.threshold)?;
+        assert_multisig_signers(&multisig, multisig_member_infos)?;
+        EscrowAuthority::Multisig(multisig)
+    } else if is_holder && !args.force_creator {
+        create_with_token_owner(&token_account, authority_info, args)?
     } else {
-        EscrowAuthority::Creator(*creator.key)
+        create_with_creator(&metadata, &token_account, authority_info, args)?
     };
 
     // Derive the seeds for PDA signing.
-    let escrow_seeds = find_escrow_seeds(mint_account_info.key, &creator_type);
+    let escrow_seeds = find_escrow_seeds_tagged(mint_account_info.key, &creator_type, args.tag);
 
-    let bump_seed = &[assert_derivation(
-        &crate::id(),
+    let bump_seed = &[assert_escrow_derivation_tagged(
         escrow_account_info,
-        &escrow_seeds,
+        mint_account_info.key,
+        &creator_type,
+        args.tag,
     )?];
 
     let escrow_authority_seeds = [escrow_seeds, vec![bump_seed]].concat();
 
+    if args.idempotent
+        && !escrow_account_info.data_is_empty()
+        && escrow_account_info.data.borrow()[0] != Key::Uninitialized as u8
+    {
+        let existing = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+        if existing.base_token != *mint_account_info.key || existing.authority != creator_type {
+            return Err(MetadataError::AlreadyInitialized.into());
+        }
+
+        return Ok(false);
+    }
+
     // Initialize a default (empty) escrow structure.
     let toe = TokenOwnedEscrow {
-        key: Key::TokenOwnedEscrow,
+        key: Key::TokenOwnedEscrowV2,
         base_token: *mint_account_info.key,
-        authority: creator_type,
+        authority: creator_type.clone(),
         bump: bump_seed[0],
+        rent_recipient: args.rent_recipient,
+        version: TOKEN_OWNED_ESCROW_VERSION,
+        locked: false,
+        token_standard: Some(token_standard.clone()),
+        operator: None,
+        // Both the payer and the authority signed, so the payer consented to fund the escrow.
+        funder: (payer_account_info.key != authority_info.key).then_some(*payer_account_info.key),
+        tag: args.tag,
+        update_authority: Some(metadata.update_authority),
     };
 
+    let preallocated = !escrow_account_info.data_is_empty();
+
+    if preallocated {
+        // The account was allocated ahead of time, so it is only written to if it is still
+        // uninitialized and sized exactly for the escrow.
+        assert_owned_by(escrow_account_info, program_id)?;
+
+        if escrow_account_info.data.borrow()[0] != Key::Uninitialized as u8 {
+            return Err(MetadataError::AlreadyInitialized.into());
+        }
+
+        if escrow_account_info.data_len() != TokenOwnedEscrow::size_for(&toe.authority) {
+            return Err(MetadataError::InvalidEscrowAccountSize.into());
+        }
+    } else {
+        // An address without data may hold lamports, but only the system program can hand it
+        // over to Token Metadata.
+        assert_owned_by(escrow_account_info, &solana_program::system_program::id())?;
+    }
+
+    assert_base_token_unchanged(
+        metadata_account_info,
+        mint_account_info,
+        edition_account_info,
+        token_standard,
+        args.allow_fungible_asset,
+        args.allow_print_edition,
+    )?;
+
+    // A payer short of the rent would otherwise only fail inside the system program transfer.
+    if !preallocated {
+        let required_lamports =
+            checked_minimum_balance(&Rent::get()?, TokenOwnedEscrow::size_for(&toe.authority))?
+                .max(1)
+                .saturating_sub(escrow_account_info.lamports());
+
+        if payer_account_info.lamports() < required_lamports {
+            msg!(
+                "Payer has {} lamports, {} are needed for the escrow rent",
+                payer_account_info.lamports(),
+                required_lamports
+            );
+            return Err(MetadataError::InsufficientFunds.into());
+        }
+    }
+
+    if args.simulate {
+        msg!("simulation ok");
+        return Ok(false);
+    }
+
+    if !preallocated {
+        // Create the account.
+        create_or_allocate_account_raw(
+            *program_id,
+            escrow_account_info,
+            system_account_info,
+            payer_account_info,
+            TokenOwnedEscrow::size_for(&toe.authority),
+            &escrow_authority_seeds,
+        )?;
+    }
+
+    // A pre-allocated escrow may have been left short of rent by whoever allocated it.
+    assert_rent_exempt(&Rent::get()?, escrow_account_info)?;
+
     let serialized_data = toe
         .try_to_vec()
         .map_err(|_| MetadataError::BorshSerializationError)?;
 
-    // Create the account.
-    create_or_allocate_account_raw(
-        *program_id,
-        escrow_account_info,
-        system_account_info,
-        payer_account_info,
-        serialized_data.len(),
-        &escrow_authority_seeds,
-    )?;
+    write_account_data(escrow_account_info, &serialized_data)?;
 
-    sol_memcpy(
-        &mut **escrow_account_info.try_borrow_mut_data().unwrap(),
-        &serialized_data,
-        serialized_data.len(),
-    );
+    emit_event(&EscrowEvent::Created(EscrowCreated {
+        escrow: *escrow_account_info.key,
+        base_token: toe.base_token,
+        authority: toe.authority,
+        bump: toe.bump,
+    }))?;
+
+    Ok(true)
+}
+
+/// Authority of an escrow created by the holder of the base token, a Token Owned Escrow.
+/// A frozen or delegated token is refused unless `allow_locked_token` is set or the
+/// delegate controlling it is the one creating the escrow.
+pub(crate) fn create_with_token_owner(
+    token_account: &spl_token::state::Account,
+    authority_info: &AccountInfo,
+    args: &CreateEscrowAccountArgs,
+) -> Result<EscrowAuthority, ProgramError> {
+    assert_signer(authority_info)?;
+
+    if !is_token_holder(token_account, authority_info.key) {
+        return Err(MetadataError::NotEscrowTokenOwner.into());
+    }
+
+    // A frozen or delegated token may be controlled by someone other than its owner, unless
+    // that someone is the delegate creating the escrow.
+    let controller = effective_token_controller(token_account);
+    let created_by_delegate =
+        controller != token_account.owner && controller == *authority_info.key;
+    if !args.allow_locked_token
+        && !created_by_delegate
+        && (token_account.is_frozen() || token_account.delegate.is_some())
+    {
+        return Err(MetadataError::LockedToken.into());
+    }
+
+    Ok(EscrowAuthority::TokenOwner)
+}
+
+/// Authority of an escrow created by anyone other than the holder, or by the holder with
+/// `force_creator` set, a Creator Owned Escrow.
+pub(crate) fn create_with_creator(
+    metadata: &Metadata,
+    token_account: &spl_token::state::Account,
+    authority_info: &AccountInfo,
+    args: &CreateEscrowAccountArgs,
+) -> Result<EscrowAuthority, ProgramError> {
+    assert_signer(authority_info)?;
+    assert_known_authority(metadata, token_account, authority_info, args)?;
+
+    Ok(EscrowAuthority::Creator(*authority_info.key))
+}
+
+/// With `require_known_authority`, only the holder of the base token or its update
+/// authority may create an escrow on it.
+fn assert_known_authority(
+    metadata: &Metadata,
+    token_account: &spl_token::state::Account,
+    authority_info: &AccountInfo,
+    args: &CreateEscrowAccountArgs,
+) -> ProgramResult {
+    if args.require_known_authority
+        && !is_token_holder(token_account, authority_info.key)
+        && metadata.update_authority != *authority_info.key
+    {
+        return Err(MetadataError::UpdateAuthorityIncorrect.into());
+    }
+
+    Ok(())
+}
+
+/// Re-load the metadata mint and the token standard right before the escrow is written,
+/// and assert they still match what was validated earlier in the instruction.
+///
+/// Accounts only change within an instruction through a CPI, so this guards against a
+/// program invoked between validation and allocation mutating the base token. It does not
+/// cover other instructions in the same transaction, which are validated on their own.
+pub fn assert_base_token_unchanged(
+    metadata_account_info: &AccountInfo,
+    mint_account_info: &AccountInfo,
+    edition_account_info: &AccountInfo,
+    token_standard: TokenStandard,
+    allow_fungible_asset: bool,
+    allow_print_edition: bool,
+) -> ProgramResult {
+    if Metadata::mint_from_account_info(metadata_account_info)? != *mint_account_info.key {
+        return Err(MetadataError::MintMismatch.into());
+    }
+
+    if assert_escrow_token_standard(
+        mint_account_info,
+        edition_account_info,
+        allow_fungible_asset,
+        allow_print_edition,
+    )? != token_standard
+    {
+        return Err(MetadataError::InvalidTokenStandard.into());
+    }
 
     Ok(())
 }
+
+/// Assert the base token is unique, and so allowed to own an escrow, returning its token
+/// standard.
+pub fn assert_escrow_token_standard(
+    mint_account_info: &AccountInfo,
+    edition_account_info: &AccountInfo,
+    allow_fungible_asset: bool,
+    allow_print_edition: bool,
+) -> Result<TokenStandard, ProgramError> {
+    // Only a single, indivisible token is unique, whatever the edition accounts claim.
+    if get_mint_supply(mint_account_info)? != 1 || get_mint_decimals(mint_account_info)? != 0 {
+        return Err(MetadataError::InvalidMintForEscrow.into());
+    }
+
+    // Fungible assets have no edition, so the derived edition account is empty.
+    if allow_fungible_asset && is_missing_edition(edition_account_info) {
+        // A fungible asset is only unique while no more tokens can be minted.
+        if check_token_standard(mint_account_info, None)? != TokenStandard::FungibleAsset
+            || get_mint_authority(mint_account_info)?.is_some()
+        {
+            return Err(MetadataError::MustBeNonFungible.into());
+        }
+
+        return Ok(TokenStandard::FungibleAsset);
+    }
+
+    // Only non-fungible tokens (i.e. unique) can have escrow accounts. A missing edition
+    // makes the token fungible, which is reported as such rather than as an undetermined
+    // standard.
+    match check_token_standard(mint_account_info, Some(edition_account_info))? {
+        TokenStandard::NonFungible => Ok(TokenStandard::NonFungible),
+        TokenStandard::NonFungibleEdition if allow_print_edition => {
+            Ok(TokenStandard::NonFungibleEdition)
+        }
+        TokenStandard::NonFungibleEdition => Err(MetadataError::NotAMasterEdition.into()),
+        _ => Err(MetadataError::MustBeNonFungible.into()),
+    }
+}
@@ -0,0 +1,581 @@
+#![cfg(test)]
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, instruction::AccountMeta, program_error::ProgramError,
+    program_option::COption, program_pack::Pack, pubkey::Pubkey, system_program,
+};
+use spl_token::state::{Account, AccountState, Mint};
+
+use crate::{
+    error::MetadataError,
+    escrow::{
+        assert_base_token_unchanged, assert_escrow_token_standard, create_escrow_account,
+        create_escrow_account::{create_with_creator, create_with_token_owner},
+        create_escrow_account_with_address, find_escrow_account, find_escrow_account_tagged,
+        CreateEscrowAccountArgs, CreateEscrowAccounts, CreateEscrowArgs,
+    },
+    instruction::MetadataInstruction,
+    state::{
+        EscrowAuthority, Key, Metadata, TokenStandard, MAX_METADATA_LEN, METADATA_MINT_OFFSET,
+    },
+};
+
+#[test]
+fn typed_builder_matches_positional_builder() {
+    let escrow = Pubkey::new_unique();
+    let metadata = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token = Pubkey::new_unique();
+    let edition = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let args = CreateEscrowAccountArgs {
+        idempotent: true,
+        ..Default::default()
+    };
+
+    let typed = CreateEscrowArgs {
+        escrow,
+        metadata,
+        mint,
+        token,
+        edition,
+        payer,
+        authority: Some(authority),
+        args: args.clone(),
+    }
+    .instruction(crate::id());
+    let positional = create_escrow_account(
+        crate::id(),
+        escrow,
+        metadata,
+        mint,
+        token,
+        edition,
+        payer,
+        Some(authority),
+        args,
+    );
+
+    assert_eq!(typed, positional);
+    assert_eq!(typed.data, positional.data);
+    assert_eq!(
+        typed.accounts,
+        vec![
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(metadata, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(token, false),
+            AccountMeta::new_readonly(edition, false),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(authority, true),
+        ]
+    );
+}
+
+#[test]
+fn builder_returns_escrow_address() {
+    let metadata = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let token = Pubkey::new_unique();
+    let edition = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let creator = Pubkey::new_unique();
+    let escrow_authority = EscrowAuthority::Creator(creator);
+    let args = CreateEscrowAccountArgs {
+        force_creator: true,
+        ..Default::default()
+    };
+
+    let (instruction, escrow) = create_escrow_account_with_address(
+        crate::id(),
+        metadata,
+        mint,
+        token,
+        edition,
+        payer,
+        Some(creator),
+        &escrow_authority,
+        args.clone(),
+    );
+
+    let (expected_escrow, _) = find_escrow_account(&mint, &escrow_authority);
+    assert_eq!(escrow, expected_escrow);
+    assert_eq!(instruction.accounts[0], AccountMeta::new(escrow, false));
+    assert_eq!(
+        instruction,
+        create_escrow_account(
+            crate::id(),
+            escrow,
+            metadata,
+            mint,
+            token,
+            edition,
+            payer,
+            Some(creator),
+            args,
+        )
+    );
+}
+
+#[test]
+fn builder_derives_the_tagged_escrow() {
+    let mint = Pubkey::new_unique();
+    let args = CreateEscrowAccountArgs {
+        tag: 1,
+        ..Default::default()
+    };
+
+    let (instruction, escrow) = create_escrow_account_with_address(
+        crate::id(),
+        Pubkey::new_unique(),
+        mint,
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        None,
+        &EscrowAuthority::TokenOwner,
+        args,
+    );
+
+    let (expected_escrow, _) = find_escrow_account_tagged(&mint, &EscrowAuthority::TokenOwner, 1);
+    assert_eq!(escrow, expected_escrow);
+    match MetadataInstruction::try_from_slice(&instruction.data).unwrap() {
+        MetadataInstruction::CreateEscrowAccount(args) => assert_eq!(args.tag, 1),
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn empty_edition_must_be_non_fungible() {
+    let mint = Mint {
+        mint_authority: COption::None,
+        supply: 1,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint::pack(mint, &mut mint_data).unwrap();
+
+    let mint_key = Pubkey::new_unique();
+    let mut mint_lamports = 0;
+    let mint_info = AccountInfo::new(
+        &mint_key,
+        false,
+        false,
+        &mut mint_lamports,
+        &mut mint_data,
+        &spl_token::ID,
+        false,
+        0,
+    );
+
+    let edition_key = Pubkey::new_unique();
+    let mut edition_lamports = 0;
+    let mut edition_data = vec![];
+    let edition_info = AccountInfo::new(
+        &edition_key,
+        false,
+        false,
+        &mut edition_lamports,
+        &mut edition_data,
+        &system_program::ID,
+        false,
+        0,
+    );
+
+    let err = assert_escrow_token_standard(&mint_info, &edition_info, false, false).unwrap_err();
+    assert_eq!(err, MetadataError::MustBeNonFungible.into());
+}
+
+fn fungible_asset_mint_data(supply: u64) -> Vec<u8> {
+    let mint = Mint {
+        mint_authority: COption::None,
+        supply,
+        decimals: 0,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    };
+    let mut mint_data = vec![0; Mint::LEN];
+    Mint::pack(mint, &mut mint_data).unwrap();
+    mint_data
+}
+
+fn metadata_data(mint: &Pubkey) -> Vec<u8> {
+    let mut metadata_data = vec![0; MAX_METADATA_LEN];
+    metadata_data[0] = Key::MetadataV1 as u8;
+    metadata_data[METADATA_MINT_OFFSET..METADATA_MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+    metadata_data
+}
+
+/// Validate a fungible asset base token, let `mutate` change its accounts as a CPI made
+/// mid-instruction could, then re-check it the way the create does before allocating.
+fn recheck_after_mutation(
+    mutate: impl FnOnce(&AccountInfo, &AccountInfo),
+) -> solana_program::entrypoint::ProgramResult {
+    let mint_key = Pubkey::new_unique();
+    let mut mint_lamports = 0;
+    let mut mint_data = fungible_asset_mint_data(1);
+    let mint_info = AccountInfo::new(
+        &mint_key,
+        false,
+        false,
+        &mut mint_lamports,
+        &mut mint_data,
+        &spl_token::ID,
+        false,
+        0,
+    );
+
+    let metadata_key = Pubkey::new_unique();
+    let mut metadata_lamports = 0;
+    let mut metadata_data = metadata_data(&mint_key);
+    let metadata_info = AccountInfo::new(
+        &metadata_key,
+        false,
+        false,
+        &mut metadata_lamports,
+        &mut metadata_data,
+        &crate::ID,
+        false,
+        0,
+    );
+
+    let edition_key = Pubkey::new_unique();
+    let mut edition_lamports = 0;
+    let mut edition_data = vec![];
+    let edition_info = AccountInfo::new(
+        &edition_key,
+        false,
+        false,
+        &mut edition_lamports,
+        &mut edition_data,
+        &system_program::ID,
+        false,
+        0,
+    );
+
+    let token_standard =
+        assert_escrow_token_standard(&mint_info, &edition_info, true, false).unwrap();
+    assert_eq!(token_standard, TokenStandard::FungibleAsset);
+
+    mutate(&metadata_info, &mint_info);
+
+    assert_base_token_unchanged(
+        &metadata_info,
+        &mint_info,
+        &edition_info,
+        token_standard,
+        true,
+        false,
+    )
+}
+
+#[test]
+fn unchanged_base_token_passes_recheck() {
+    assert!(recheck_after_mutation(|_, _| ()).is_ok());
+}
+
+#[test]
+fn swapped_metadata_mint_fails_recheck() {
+    let err = recheck_after_mutation(|metadata_info, _| {
+        let other_mint = Pubkey::new_unique();
+        metadata_info.data.borrow_mut()[METADATA_MINT_OFFSET..METADATA_MINT_OFFSET + 32]
+            .copy_from_slice(other_mint.as_ref());
+    })
+    .unwrap_err();
+
+    assert_eq!(err, MetadataError::MintMismatch.into());
+}
+
+#[test]
+fn minted_supply_fails_recheck() {
+    let err = recheck_after_mutation(|_, mint_info| {
+        mint_info
+            .data
+            .borrow_mut()
+            .copy_from_slice(&fungible_asset_mint_data(2));
+    })
+    .unwrap_err();
+
+    assert_eq!(err, MetadataError::InvalidMintForEscrow.into());
+}
+
+fn token_account(owner: Pubkey) -> Account {
+    Account {
+        mint: Pubkey::new_unique(),
+        owner,
+        amount: 1,
+        state: AccountState::Initialized,
+        ..Default::default()
+    }
+}
+
+/// Run `f` with an authority account for `key`, signing when `is_signer` is set.
+fn with_authority<T>(key: Pubkey, is_signer: bool, f: impl FnOnce(&AccountInfo) -> T) -> T {
+    let mut lamports = 0;
+    let mut data = vec![];
+    let authority_info = AccountInfo::new(
+        &key,
+        is_signer,
+        false,
+        &mut lamports,
+        &mut data,
+        &system_program::ID,
+        false,
+        0,
+    );
+
+    f(&authority_info)
+}
+
+#[test]
+fn token_owner_creates_token_owned_escrow() {
+    let owner = Pubkey::new_unique();
+    let token_account = token_account(owner);
+
+    let authority = with_authority(owner, true, |authority_info| {
+        create_with_token_owner(&token_account, authority_info, &Default::default())
+    });
+
+    assert_eq!(authority.unwrap(), EscrowAuthority::TokenOwner);
+}
+
+#[test]
+fn token_owner_must_sign() {
+    let owner = Pubkey::new_unique();
+    let token_account = token_account(owner);
+
+    let err = with_authority(owner, false, |authority_info| {
+        create_with_token_owner(&token_account, authority_info, &Default::default())
+    })
+    .unwrap_err();
+
+    assert_eq!(err, ProgramError::MissingRequiredSignature);
+}
+
+#[test]
+fn token_owner_of_frozen_token_needs_allow_locked_token() {
+    let owner = Pubkey::new_unique();
+    let token_account = Account {
+        state: AccountState::Frozen,
+        ..token_account(owner)
+    };
+
+    let err = with_authority(owner, true, |authority_info| {
+        create_with_token_owner(&token_account, authority_info, &Default::default())
+    })
+    .unwrap_err();
+    assert_eq!(err, MetadataError::LockedToken.into());
+
+    let authority = with_authority(owner, true, |authority_info| {
+        create_with_token_owner(
+            &token_account,
+            authority_info,
+            &CreateEscrowAccountArgs {
+                allow_locked_token: true,
+                ..Default::default()
+            },
+        )
+    });
+    assert_eq!(authority.unwrap(), EscrowAuthority::TokenOwner);
+}
+
+#[test]
+fn token_owner_escrow_requires_the_holder() {
+    let token_account = token_account(Pubkey::new_unique());
+
+    let err = with_authority(Pubkey::new_unique(), true, |authority_info| {
+        create_with_token_owner(&token_account, authority_info, &Default::default())
+    })
+    .unwrap_err();
+
+    assert_eq!(err, MetadataError::NotEscrowTokenOwner.into());
+}
+
+#[test]
+fn creator_creates_creator_owned_escrow() {
+    let creator = Pubkey::new_unique();
+    let token_account = token_account(Pubkey::new_unique());
+
+    let authority = with_authority(creator, true, |authority_info| {
+        create_with_creator(
+            &Metadata::default(),
+            &token_account,
+            authority_info,
+            &Default::default(),
+        )
+    });
+
+    assert_eq!(authority.unwrap(), EscrowAuthority::Creator(creator));
+}
+
+#[test]
+fn creator_must_sign() {
+    let token_account = token_account(Pubkey::new_unique());
+
+    let err = with_authority(Pubkey::new_unique(), false, |authority_info| {
+        create_with_creator(
+            &Metadata::default(),
+            &token_account,
+            authority_info,
+            &Default::default(),
+        )
+    })
+    .unwrap_err();
+
+    assert_eq!(err, ProgramError::MissingRequiredSignature);
+}
+
+#[test]
+fn creator_must_be_known_when_required() {
+    let creator = Pubkey::new_unique();
+    let token_account = token_account(Pubkey::new_unique());
+    let args = CreateEscrowAccountArgs {
+        require_known_authority: true,
+        ..Default::default()
+    };
+
+    let err = with_authority(creator, true, |authority_info| {
+        create_with_creator(&Metadata::default(), &token_account, authority_info, &args)
+    })
+    .unwrap_err();
+    assert_eq!(err, MetadataError::UpdateAuthorityIncorrect.into());
+
+    let metadata = Metadata {
+        update_authority: creator,
+        ..Metadata::default()
+    };
+    let authority = with_authority(creator, true, |authority_info| {
+        create_with_creator(&metadata, &token_account, authority_info, &args)
+    });
+    assert_eq!(authority.unwrap(), EscrowAuthority::Creator(creator));
+}
+
+// Parses `count` placeholder accounts as the accounts of a create with `args`, returning
+// the authority read from them.
+fn parse_accounts(
+    count: usize,
+    args: &CreateEscrowAccountArgs,
+) -> Result<Option<Pubkey>, ProgramError> {
+    let keys: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+    let mut lamports = vec![0; count];
+    let mut data: Vec<Vec<u8>> = vec![vec![]; count];
+    let accounts: Vec<AccountInfo> = keys
+        .iter()
+        .zip(lamports.iter_mut())
+        .zip(data.iter_mut())
+        .map(|((key, lamports), data)| {
+            AccountInfo::new(
+                key,
+                true,
+                false,
+                lamports,
+                data,
+                &system_program::ID,
+                false,
+                0,
+            )
+        })
+        .collect();
+
+    CreateEscrowAccounts::from_account_infos(&accounts, args)
+        .map(|accounts| accounts.authority.map(|authority| *authority.key))
+}
+
+#[test]
+fn truncated_fixed_accounts_are_not_enough_accounts() {
+    let args = CreateEscrowAccountArgs::default();
+
+    for count in 0..7 {
+        assert_eq!(
+            parse_accounts(count, &args).unwrap_err(),
+            MetadataError::NotEnoughAccounts.into()
+        );
+    }
+    assert!(parse_accounts(7, &args).is_ok());
+}
+
+#[test]
+fn truncated_optional_accounts_are_not_enough_accounts() {
+    let args = CreateEscrowAccountArgs {
+        verify_collection: true,
+        ..Default::default()
+    };
+
+    // The collection mint is missing.
+    assert_eq!(
+        parse_accounts(7, &args).unwrap_err(),
+        MetadataError::NotEnoughAccounts.into()
+    );
+    assert!(parse_accounts(8, &args).is_ok());
+}
+
+#[test]
+fn authority_is_only_read_when_flagged() {
+    // Without the flag, an extra account is ignored rather than taken as the authority.
+    let args = CreateEscrowAccountArgs::default();
+    assert_eq!(parse_accounts(8, &args), Ok(None));
+
+    let args = CreateEscrowAccountArgs {
+        has_authority: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        parse_accounts(7, &args).unwrap_err(),
+        MetadataError::NotEnoughAccounts.into()
+    );
+    assert!(parse_accounts(8, &args).unwrap().is_some());
+}
+
+#[test]
+fn flagged_authority_is_not_confused_with_optional_accounts() {
+    // A missing collection mint isn't made up for by reading the authority as absent.
+    let args = CreateEscrowAccountArgs {
+        has_authority: true,
+        verify_collection: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        parse_accounts(8, &args).unwrap_err(),
+        MetadataError::NotEnoughAccounts.into()
+    );
+    assert!(parse_accounts(9, &args).is_ok());
+}
+
+#[test]
+fn builder_flags_the_authority() {
+    let parsed_args = |authority: Option<Pubkey>| {
+        let instruction = create_escrow_account(
+            crate::id(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            authority,
+            CreateEscrowAccountArgs::default(),
+        );
+        match MetadataInstruction::try_from_slice(&instruction.data).unwrap() {
+            MetadataInstruction::CreateEscrowAccount(args) => args,
+            _ => unreachable!(),
+        }
+    };
+
+    assert!(parsed_args(Some(Pubkey::new_unique())).has_authority);
+    assert!(!parsed_args(None).has_authority);
+}
+
+#[test]
+fn create_without_args_decodes_as_default() {
+    // Clients predating the args only send the discriminant.
+    match MetadataInstruction::try_from_slice(&[38]).unwrap() {
+        MetadataInstruction::CreateEscrowAccount(args) => {
+            assert_eq!(args, CreateEscrowAccountArgs::default())
+        }
+        _ => unreachable!(),
+    }
+}
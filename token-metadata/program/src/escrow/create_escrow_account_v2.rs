@@ -0,0 +1,73 @@
+use crate::{
+    escrow::create_escrow_account::{
+        create_escrow_account, process_create_escrow_account_expecting, CreateEscrowAccountArgs,
+    },
+    instruction::MetadataInstruction,
+    state::TokenStandard,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction, pubkey::Pubkey,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct CreateEscrowAccountV2Args {
+    pub args: CreateEscrowAccountArgs,
+    /// Token standard the base token must have, so clients fail fast if it changed.
+    pub expected_standard: TokenStandard,
+}
+
+/// Creates an escrow account like `create_escrow_account`, failing unless the base token is
+/// of `expected_standard`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_account_v2(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Option<Pubkey>,
+    args: CreateEscrowAccountArgs,
+    expected_standard: TokenStandard,
+) -> Instruction {
+    let mut instruction = create_escrow_account(
+        program_id,
+        escrow_account,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        authority,
+        args.clone(),
+    );
+
+    instruction.data = MetadataInstruction::CreateEscrowAccountV2(CreateEscrowAccountV2Args {
+        args,
+        expected_standard,
+    })
+    .try_to_vec()
+    .unwrap();
+
+    instruction
+}
+
+pub fn process_create_escrow_account_v2<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: CreateEscrowAccountV2Args,
+) -> ProgramResult {
+    process_create_escrow_account_expecting(
+        program_id,
+        accounts,
+        args.args,
+        Some(args.expected_standard),
+    )
+}
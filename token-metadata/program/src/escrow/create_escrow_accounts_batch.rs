@@ -0,0 +1,143 @@
+use crate::{
+    error::MetadataError,
+    escrow::{
+        find_escrow_account, find_escrow_treasury_account, pay_escrow_creation_fee,
+        process_create_escrow_account_logic, CreateEscrowAccountArgs, CreateEscrowAccountLogicArgs,
+        ESCROW_CREATION_FEE,
+    },
+    instruction::MetadataInstruction,
+    state::EscrowAuthority,
+    utils::assert_signer,
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Maximum number of escrows that can be created in a single batch, keeping the
+/// transaction under the account limit.
+pub const MAX_ESCROW_BATCH_SIZE: usize = 5;
+
+/// Number of accounts passed for each escrow in the batch:
+/// escrow, metadata, mint, token account and edition.
+pub const ESCROW_BATCH_ENTRY_ACCOUNTS: usize = 5;
+
+/// Creates a batch of creator owned escrows. Each entry is a
+/// `(mint, metadata, token_account, edition, holder)` tuple, where `holder` controls the
+/// token account. An entry held by `authority` gets a token owned escrow instead, the same
+/// as a single create.
+pub fn create_escrow_accounts_batch(
+    program_id: Pubkey,
+    payer_account: Pubkey,
+    authority: Pubkey,
+    entries: &[(Pubkey, Pubkey, Pubkey, Pubkey, Pubkey)],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(payer_account, true),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(authority, true),
+    ];
+
+    if ESCROW_CREATION_FEE != 0 {
+        let (treasury, _) = find_escrow_treasury_account();
+        accounts.push(AccountMeta::new(treasury, false));
+    }
+
+    for (mint, metadata, token_account, edition, holder) in entries {
+        let (escrow, _) = find_escrow_account(
+            mint,
+            &EscrowAuthority::from_creator_and_token(&authority, holder),
+        );
+
+        accounts.push(AccountMeta::new(escrow, false));
+        accounts.push(AccountMeta::new_readonly(*metadata, false));
+        accounts.push(AccountMeta::new_readonly(*mint, false));
+        accounts.push(AccountMeta::new_readonly(*token_account, false));
+        accounts.push(AccountMeta::new_readonly(*edition, false));
+    }
+
+    let data = MetadataInstruction::CreateEscrowAccountsBatch
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_create_escrow_accounts_batch<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    assert_signer(authority_info)?;
+
+    // The treasury collecting the protocol fee, if any, follows the authority.
+    let fee_collector_info = if ESCROW_CREATION_FEE != 0 {
+        let fee_collector_info = next_account_info(account_info_iter)?;
+        if *fee_collector_info.key != find_escrow_treasury_account().0 {
+            return Err(MetadataError::InvalidFeeAccount.into());
+        }
+        Some(fee_collector_info)
+    } else {
+        None
+    };
+
+    let entry_accounts = account_info_iter.as_slice();
+
+    if entry_accounts.is_empty()
+        || !entry_accounts
+            .len()
+            .is_multiple_of(ESCROW_BATCH_ENTRY_ACCOUNTS)
+    {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    if entry_accounts.len() / ESCROW_BATCH_ENTRY_ACCOUNTS > MAX_ESCROW_BATCH_SIZE {
+        return Err(MetadataError::EscrowBatchTooLarge.into());
+    }
+
+    // Any failing entry fails the whole transaction, so no escrow is created unless all are.
+    for entry in entry_accounts.chunks(ESCROW_BATCH_ENTRY_ACCOUNTS) {
+        process_create_escrow_account_logic(
+            program_id,
+            CreateEscrowAccountLogicArgs {
+                escrow_account_info: &entry[0],
+                metadata_account_info: &entry[1],
+                mint_account_info: &entry[2],
+                token_account_info: &entry[3],
+                edition_account_info: &entry[4],
+                payer_account_info,
+                system_account_info,
+                authority_info,
+                collection_mint_info: None,
+                multisig_member_infos: &[],
+            },
+            &CreateEscrowAccountArgs::default(),
+            None,
+        )?;
+    }
+
+    if let Some(fee_collector_info) = fee_collector_info {
+        let escrows = (entry_accounts.len() / ESCROW_BATCH_ENTRY_ACCOUNTS) as u64;
+        pay_escrow_creation_fee(
+            payer_account_info,
+            fee_collector_info,
+            system_account_info,
+            escrows,
+        )?;
+    }
+
+    Ok(())
+}
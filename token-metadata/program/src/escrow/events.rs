@@ -0,0 +1,82 @@
+use crate::{error::MetadataError, state::EscrowAuthority};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, log::sol_log_data, pubkey::Pubkey};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+/// Discriminator logged ahead of every [`EscrowEvent`]. Events are emitted with
+/// `sol_log_data`, so they show up in the logs as `Program data: <discriminator> <event>`
+/// where both fields are base64 encoded and the event is Borsh serialized.
+pub const ESCROW_EVENT: &[u8] = b"escrow_event";
+
+/// Outcome of a successful escrow instruction. The first byte of a serialized event is its
+/// Borsh variant index, which indexers match on, so variants must only ever be appended.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub enum EscrowEvent {
+    /// 0 - An escrow account was created.
+    Created(EscrowCreated),
+    /// 1 - An escrow account was closed.
+    Closed(EscrowClosed),
+    /// 2 - Tokens were transferred into an escrow.
+    TransferIn(EscrowTransfer),
+    /// 3 - Tokens were transferred out of an escrow.
+    TransferOut(EscrowTransfer),
+    /// 4 - A creator owned escrow was handed over to the holder of the base token.
+    AuthorityUpdated(EscrowAuthorityUpdated),
+}
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowCreated {
+    pub escrow: Pubkey,
+    pub base_token: Pubkey,
+    pub authority: EscrowAuthority,
+    pub bump: u8,
+}
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowClosed {
+    pub escrow: Pubkey,
+    pub base_token: Pubkey,
+    /// Account the escrow rent was returned to.
+    pub rent_destination: Pubkey,
+}
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowTransfer {
+    pub escrow: Pubkey,
+    /// Mint of the transferred token.
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowAuthorityUpdated {
+    /// The closed creator owned escrow.
+    pub escrow: Pubkey,
+    /// The escrow that now holds its tokens.
+    pub new_escrow: Pubkey,
+    pub base_token: Pubkey,
+    pub new_authority: EscrowAuthority,
+}
+
+/// Log `event` under [`ESCROW_EVENT`].
+pub fn emit_event(event: &EscrowEvent) -> ProgramResult {
+    let data = event
+        .try_to_vec()
+        .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    sol_log_data(&[ESCROW_EVENT, &data]);
+
+    Ok(())
+}
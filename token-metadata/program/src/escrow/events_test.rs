@@ -0,0 +1,66 @@
+#![cfg(test)]
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    escrow::{
+        EscrowAuthorityUpdated, EscrowClosed, EscrowCreated, EscrowEvent, EscrowTransfer,
+        ESCROW_EVENT,
+    },
+    state::EscrowAuthority,
+};
+
+fn events() -> Vec<EscrowEvent> {
+    let escrow = Pubkey::new_unique();
+    let base_token = Pubkey::new_unique();
+    let transfer = EscrowTransfer {
+        escrow,
+        mint: Pubkey::new_unique(),
+        amount: 5,
+    };
+
+    vec![
+        EscrowEvent::Created(EscrowCreated {
+            escrow,
+            base_token,
+            authority: EscrowAuthority::Creator(Pubkey::new_unique()),
+            bump: 254,
+        }),
+        EscrowEvent::Closed(EscrowClosed {
+            escrow,
+            base_token,
+            rent_destination: Pubkey::new_unique(),
+        }),
+        EscrowEvent::TransferIn(transfer.clone()),
+        EscrowEvent::TransferOut(transfer),
+        EscrowEvent::AuthorityUpdated(EscrowAuthorityUpdated {
+            escrow,
+            new_escrow: Pubkey::new_unique(),
+            base_token,
+            new_authority: EscrowAuthority::TokenOwner,
+        }),
+    ]
+}
+
+#[test]
+fn event_discriminants_are_stable() {
+    // Indexers match on these, so they must never change.
+    let discriminants: Vec<u8> = events()
+        .iter()
+        .map(|event| event.try_to_vec().unwrap()[0])
+        .collect();
+
+    assert_eq!(discriminants, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn decode_logged_events() {
+    for event in events() {
+        // The same fields `emit_event` passes to `sol_log_data`.
+        let data = event.try_to_vec().unwrap();
+        let fields: [&[u8]; 2] = [ESCROW_EVENT, &data];
+
+        assert_eq!(fields[0], b"escrow_event");
+        assert_eq!(EscrowEvent::try_from_slice(fields[1]).unwrap(), event);
+    }
+}
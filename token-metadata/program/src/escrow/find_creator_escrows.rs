@@ -0,0 +1,112 @@
+use crate::{
+    error::MetadataError,
+    escrow::pda::{assert_escrow_derivation, find_escrow_account},
+    instruction::MetadataInstruction,
+    state::{EscrowAuthority, TokenMetadataAccount, TokenOwnedEscrow},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+/// Discriminator logged ahead of a [`CreatorEscrows`] view, in the same format as
+/// [`crate::escrow::ESCROW_EVENT`].
+pub const CREATOR_ESCROWS_EVENT: &[u8] = b"creator_escrows";
+
+/// A creator owned escrow found for the mint.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct CreatorEscrow {
+    pub creator: Pubkey,
+    pub escrow: Pubkey,
+}
+
+/// View of the creator owned escrows that exist for a mint, out of the candidates checked.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct CreatorEscrows {
+    pub mint: Pubkey,
+    pub escrows: Vec<CreatorEscrow>,
+}
+
+/// Checks which of `creators` have an escrow on `mint`. Intended to be simulated, reading
+/// the [`CreatorEscrows`] view back from the logs.
+pub fn find_creator_escrows(program_id: Pubkey, mint: Pubkey, creators: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(mint, false)];
+
+    for creator in creators {
+        let (escrow, _) = find_escrow_account(&mint, &EscrowAuthority::Creator(*creator));
+        accounts.push(AccountMeta::new_readonly(*creator, false));
+        accounts.push(AccountMeta::new_readonly(escrow, false));
+    }
+
+    let data = MetadataInstruction::FindCreatorEscrows
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_find_creator_escrows(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_info = next_account_info(account_info_iter)?;
+
+    let candidate_accounts = account_info_iter.as_slice();
+    if !candidate_accounts.len().is_multiple_of(2) {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // Each candidate is derived on-chain, which is why this is meant for simulation only.
+    let mut escrows = vec![];
+    for pair in candidate_accounts.chunks(2) {
+        let creator_info = &pair[0];
+        let escrow_info = &pair[1];
+
+        let authority = EscrowAuthority::Creator(*creator_info.key);
+        assert_escrow_derivation(escrow_info, mint_info.key, &authority)?;
+
+        if escrow_info.owner != program_id {
+            continue;
+        }
+
+        let exists = matches!(
+            TokenOwnedEscrow::from_account_info(escrow_info),
+            Ok(toe) if toe.base_token == *mint_info.key && toe.authority == authority
+        );
+        if exists {
+            escrows.push(CreatorEscrow {
+                creator: *creator_info.key,
+                escrow: *escrow_info.key,
+            });
+        }
+    }
+
+    let view = CreatorEscrows {
+        mint: *mint_info.key,
+        escrows,
+    }
+    .try_to_vec()
+    .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    sol_log_data(&[CREATOR_ESCROWS_EVENT, &view]);
+
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use crate::{
+    error::MetadataError,
+    escrow::pda::assert_escrow_derivation_tagged,
+    instruction::MetadataInstruction,
+    state::{TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{assert_initialized, assert_owned_by},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_data,
+    pubkey::Pubkey,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+/// Discriminator logged ahead of an [`EscrowContents`] view, in the same format as
+/// [`crate::escrow::ESCROW_EVENT`].
+pub const ESCROW_CONTENTS_EVENT: &[u8] = b"escrow_contents";
+
+/// Balance of one token account owned by an escrow.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowTokenBalance {
+    pub token_account: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// View of the token accounts held by an escrow, logged when listing its contents.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowContents {
+    pub escrow: Pubkey,
+    pub base_token: Pubkey,
+    pub balances: Vec<EscrowTokenBalance>,
+}
+
+/// Lists the balances of `token_accounts` owned by the escrow. Intended to be simulated,
+/// reading the [`EscrowContents`] view back from the logs.
+pub fn list_escrow_contents(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    token_accounts: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(escrow_account, false)];
+
+    for token_account in token_accounts {
+        accounts.push(AccountMeta::new_readonly(*token_account, false));
+    }
+
+    let data = MetadataInstruction::ListEscrowContents
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_list_escrow_contents(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(escrow_account_info, program_id)?;
+    let toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        &toe.base_token,
+        &toe.authority,
+        toe.tag,
+    )?;
+
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    // Token accounts can't be enumerated on-chain, so the candidates are passed in and
+    // only ones actually owned by the escrow are reported.
+    let mut balances = vec![];
+    for token_account_info in account_info_iter {
+        assert_owned_by(token_account_info, &spl_token::id())?;
+        let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+
+        if token_account.owner != *escrow_account_info.key {
+            return Err(MetadataError::IncorrectOwner.into());
+        }
+
+        balances.push(EscrowTokenBalance {
+            token_account: *token_account_info.key,
+            mint: token_account.mint,
+            amount: token_account.amount,
+        });
+    }
+
+    let view = EscrowContents {
+        escrow: *escrow_account_info.key,
+        base_token: toe.base_token,
+        balances,
+    }
+    .try_to_vec()
+    .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    sol_log_data(&[ESCROW_CONTENTS_EVENT, &view]);
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use crate::{
+    error::MetadataError, escrow::pda::find_escrow_account, instruction::MetadataInstruction,
+    state::EscrowAuthority,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    log::sol_log_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+/// Discriminator logged ahead of an [`EscrowBump`] view, in the same format as
+/// [`crate::escrow::ESCROW_EVENT`].
+pub const ESCROW_BUMP_EVENT: &[u8] = b"escrow_bump";
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct LogEscrowBumpArgs {
+    pub authority: EscrowAuthority,
+}
+
+/// Canonical address and bump of the escrow of `mint` controlled by `authority`.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowBump {
+    pub mint: Pubkey,
+    pub authority: EscrowAuthority,
+    pub escrow: Pubkey,
+    pub bump: u8,
+}
+
+impl EscrowBump {
+    pub fn new(mint: Pubkey, authority: EscrowAuthority) -> Self {
+        let (escrow, bump) = find_escrow_account(&mint, &authority);
+
+        EscrowBump {
+            mint,
+            authority,
+            escrow,
+            bump,
+        }
+    }
+}
+
+/// Derives the escrow of `mint` and `authority` on-chain. Intended to be simulated, reading
+/// the [`EscrowBump`] view back from the logs, by tooling that would rather not derive it.
+pub fn log_escrow_bump(
+    program_id: Pubkey,
+    mint: Pubkey,
+    authority: EscrowAuthority,
+) -> Instruction {
+    let accounts = vec![AccountMeta::new_readonly(mint, false)];
+
+    let data = MetadataInstruction::LogEscrowBump(LogEscrowBumpArgs { authority })
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_log_escrow_bump(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: LogEscrowBumpArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let mint_info = next_account_info(account_info_iter)?;
+
+    // Nothing is ever written, so only the readonly mint is accepted.
+    if mint_info.is_writable || account_info_iter.len() > 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let view = EscrowBump::new(*mint_info.key, args.authority)
+        .try_to_vec()
+        .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    sol_log_data(&[ESCROW_BUMP_EVENT, &view]);
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+#![cfg(test)]
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    escrow::{find_escrow_account, find_escrow_seeds, EscrowBump, ESCROW_BUMP_EVENT},
+    state::EscrowAuthority,
+};
+
+#[test]
+fn decode_logged_escrow_bump() {
+    let mint = Pubkey::new_unique();
+
+    for authority in [
+        EscrowAuthority::TokenOwner,
+        EscrowAuthority::Creator(Pubkey::new_unique()),
+    ] {
+        // The same fields `process_log_escrow_bump` passes to `sol_log_data`.
+        let data = EscrowBump::new(mint, authority.clone())
+            .try_to_vec()
+            .unwrap();
+        let fields: [&[u8]; 2] = [ESCROW_BUMP_EVENT, &data];
+
+        assert_eq!(fields[0], b"escrow_bump");
+        let view = EscrowBump::try_from_slice(fields[1]).unwrap();
+
+        assert_eq!(
+            (view.escrow, view.bump),
+            find_escrow_account(&mint, &authority)
+        );
+
+        let mut seeds = find_escrow_seeds(&mint, &authority);
+        let bump = [view.bump];
+        seeds.push(&bump);
+        assert_eq!(
+            Pubkey::create_program_address(&seeds, &crate::id()).unwrap(),
+            view.escrow
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{
+    error::MetadataError,
+    escrow::assert_escrow_token_standard,
+    instruction::MetadataInstruction,
+    state::{TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{
+        assert_account_key, assert_edition_valid, assert_owned_by, assert_signer,
+        resize_or_reallocate_account_raw,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+pub fn migrate_escrow(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    payer_account: Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(escrow_account, false),
+        AccountMeta::new(payer_account, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = MetadataInstruction::MigrateEscrow.try_to_vec().unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Migrates an escrow and records the token standard of its base token, read from the base
+/// token's mint and master edition.
+pub fn migrate_escrow_with_token_standard(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    payer_account: Pubkey,
+    mint_account: Pubkey,
+    edition_account: Pubkey,
+) -> Instruction {
+    let mut instruction = migrate_escrow(program_id, escrow_account, payer_account);
+
+    instruction.accounts.extend([
+        AccountMeta::new_readonly(mint_account, false),
+        AccountMeta::new_readonly(edition_account, false),
+    ]);
+
+    instruction
+}
+
+pub fn process_migrate_escrow<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    if *system_account_info.key != system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
+
+    assert_signer(payer_account_info)?;
+    assert_owned_by(escrow_account_info, program_id)?;
+
+    let mut toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+    let migrated = toe.migrate();
+
+    // Escrows created before the token standard was recorded get it from the base token
+    // accounts, when they are passed in.
+    let mut recorded = false;
+    if toe.token_standard.is_none() && account_info_iter.len() >= 2 {
+        let mint_account_info = next_account_info(account_info_iter)?;
+        let edition_account_info = next_account_info(account_info_iter)?;
+
+        assert_account_key(
+            mint_account_info,
+            &toe.base_token,
+            MetadataError::MintMismatch,
+        )?;
+        assert_owned_by(mint_account_info, &spl_token::id())?;
+        assert_edition_valid(program_id, mint_account_info.key, edition_account_info)?;
+
+        toe.token_standard = Some(assert_escrow_token_standard(
+            mint_account_info,
+            edition_account_info,
+            true,
+            true,
+        )?);
+        recorded = true;
+    }
+
+    // Nothing to do for escrows already on the current layout.
+    if !migrated && !recorded {
+        return Ok(());
+    }
+
+    // Older layouts are shorter, so grow the account and have the payer cover the rent delta.
+    if escrow_account_info.data_len() < TokenOwnedEscrow::size_for(&toe.authority) {
+        resize_or_reallocate_account_raw(
+            escrow_account_info,
+            payer_account_info,
+            system_account_info,
+            TokenOwnedEscrow::size_for(&toe.authority),
+        )?;
+    }
+
+    let mut escrow_data = escrow_account_info
+        .try_borrow_mut_data()
+        .map_err(|_| MetadataError::DataBorrowFailed)?;
+
+    toe.serialize(&mut *escrow_data)
+        .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    Ok(())
+}
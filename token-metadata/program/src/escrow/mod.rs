@@ -1,9 +1,44 @@
+pub mod burn_nft_and_close_escrow;
 pub mod close_escrow_account;
 pub mod create_escrow_account;
+pub mod create_escrow_account_test;
+pub mod create_escrow_account_v2;
+pub mod create_escrow_accounts_batch;
+pub mod events;
+pub mod events_test;
+pub mod find_creator_escrows;
+pub mod list_escrow_contents;
+pub mod log_escrow_bump;
+pub mod log_escrow_bump_test;
+pub mod migrate_escrow;
 pub mod pda;
+pub mod reclaim_orphaned_escrow;
+pub mod set_escrow_allowlist;
+pub mod set_escrow_lock;
+pub mod set_escrow_operator;
+pub mod transfer_into;
 pub mod transfer_out;
+pub mod update_escrow_authority;
+pub mod verify_escrow_authority;
+pub mod withdraw_escrow_treasury;
 
+pub use burn_nft_and_close_escrow::*;
 pub use close_escrow_account::*;
 pub use create_escrow_account::*;
+pub use create_escrow_account_v2::*;
+pub use create_escrow_accounts_batch::*;
+pub use events::*;
+pub use find_creator_escrows::*;
+pub use list_escrow_contents::*;
+pub use log_escrow_bump::*;
+pub use migrate_escrow::*;
 pub use pda::*;
+pub use reclaim_orphaned_escrow::*;
+pub use set_escrow_allowlist::*;
+pub use set_escrow_lock::*;
+pub use set_escrow_operator::*;
+pub use transfer_into::*;
 pub use transfer_out::*;
+pub use update_escrow_authority::*;
+pub use verify_escrow_authority::*;
+pub use withdraw_escrow_treasury::*;
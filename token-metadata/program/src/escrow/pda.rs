@@ -1,7 +1,45 @@
-use crate::state::{EscrowAuthority, ESCROW_POSTFIX, PREFIX};
-use solana_program::pubkey::Pubkey;
+use crate::{
+    state::{
+        EscrowAuthority, ESCROW_ALLOWLIST, ESCROW_POSTFIX, ESCROW_TAG, ESCROW_TREASURY, PREFIX,
+    },
+    utils::assert_derivation,
+};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+    program::invoke_signed, program_error::ProgramError, pubkey::Pubkey,
+};
+
+/// Seed scheme used by every escrow created so far. Escrows on this version carry no
+/// version seed, so their addresses are unchanged.
+pub const ESCROW_SEEDS_V1: u8 = 1;
+
+/// Tag of the one escrow a mint and authority had before tags. Untagged escrows carry no
+/// tag seed, so their addresses are unchanged.
+pub const ESCROW_DEFAULT_TAG: u8 = 0;
+
+/// Backing storage for the version and tag seeds, so the returned seeds can borrow a
+/// `'static` byte.
+static ESCROW_SEED_BYTES: [u8; 256] = {
+    let mut bytes = [0; 256];
+    let mut i = 0;
+    while i < bytes.len() {
+        bytes[i] = i as u8;
+        i += 1;
+    }
+    bytes
+};
 
 pub fn find_escrow_seeds<'a>(mint: &'a Pubkey, authority: &'a EscrowAuthority) -> Vec<&'a [u8]> {
+    find_escrow_seeds_versioned(mint, authority, ESCROW_SEEDS_V1)
+}
+
+/// Escrow seeds for a given seed scheme version. Any version after `ESCROW_SEEDS_V1`
+/// appends the version byte, placing those escrows in a separate PDA namespace.
+pub fn find_escrow_seeds_versioned<'a>(
+    mint: &'a Pubkey,
+    authority: &'a EscrowAuthority,
+    version: u8,
+) -> Vec<&'a [u8]> {
     let mut seeds = vec![PREFIX.as_bytes(), crate::ID.as_ref(), mint.as_ref()];
 
     for seed in authority.to_seeds() {
@@ -10,10 +48,158 @@ pub fn find_escrow_seeds<'a>(mint: &'a Pubkey, authority: &'a EscrowAuthority) -
 
     seeds.push(ESCROW_POSTFIX.as_bytes());
 
+    if version > ESCROW_SEEDS_V1 {
+        let version = version as usize;
+        seeds.push(&ESCROW_SEED_BYTES[version..version + 1]);
+    }
+
     seeds
 }
 
+/// Escrow seeds for one of several escrows of the same mint and authority, e.g. one per
+/// kind of attribute. Any tag other than `ESCROW_DEFAULT_TAG` appends `ESCROW_TAG` and the
+/// tag byte, so a tag never derives the same address as a seed version.
+pub fn find_escrow_seeds_tagged<'a>(
+    mint: &'a Pubkey,
+    authority: &'a EscrowAuthority,
+    tag: u8,
+) -> Vec<&'a [u8]> {
+    let mut seeds = find_escrow_seeds(mint, authority);
+
+    if tag != ESCROW_DEFAULT_TAG {
+        let tag = tag as usize;
+        seeds.push(ESCROW_TAG.as_bytes());
+        seeds.push(&ESCROW_SEED_BYTES[tag..tag + 1]);
+    }
+
+    seeds
+}
+
+/// Derive the escrow address and bump for the given mint and authority, without
+/// touching chain state.
 pub fn find_escrow_account(mint: &Pubkey, authority: &EscrowAuthority) -> (Pubkey, u8) {
     let seeds = find_escrow_seeds(mint, authority);
     Pubkey::find_program_address(&seeds, &crate::id())
 }
+
+/// Derive the address and bump of the escrow of the given mint and authority with `tag`.
+pub fn find_escrow_account_tagged(
+    mint: &Pubkey,
+    authority: &EscrowAuthority,
+    tag: u8,
+) -> (Pubkey, u8) {
+    let seeds = find_escrow_seeds_tagged(mint, authority, tag);
+    Pubkey::find_program_address(&seeds, &crate::id())
+}
+
+/// Assert `escrow_info` is the escrow of `mint` and `authority`, returning its bump.
+pub fn assert_escrow_derivation(
+    escrow_info: &AccountInfo,
+    mint: &Pubkey,
+    authority: &EscrowAuthority,
+) -> Result<u8, ProgramError> {
+    assert_escrow_derivation_tagged(escrow_info, mint, authority, ESCROW_DEFAULT_TAG)
+}
+
+/// Assert `escrow_info` is the escrow of `mint` and `authority` with `tag`, returning its
+/// bump.
+pub fn assert_escrow_derivation_tagged(
+    escrow_info: &AccountInfo,
+    mint: &Pubkey,
+    authority: &EscrowAuthority,
+    tag: u8,
+) -> Result<u8, ProgramError> {
+    let seeds = find_escrow_seeds_tagged(mint, authority, tag);
+    assert_derivation(&crate::id(), escrow_info, &seeds)
+}
+
+/// Seeds of the Token Metadata treasury that closed escrows can sweep their rent into.
+pub fn find_escrow_treasury_seeds() -> Vec<&'static [u8]> {
+    vec![
+        PREFIX.as_bytes(),
+        crate::ID.as_ref(),
+        ESCROW_TREASURY.as_bytes(),
+    ]
+}
+
+pub fn find_escrow_treasury_account() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&find_escrow_treasury_seeds(), &crate::id())
+}
+
+/// Seeds of the escrow allowlist owned by the update authority `authority`.
+pub fn find_escrow_allowlist_seeds(authority: &Pubkey) -> Vec<&[u8]> {
+    vec![
+        PREFIX.as_bytes(),
+        crate::ID.as_ref(),
+        authority.as_ref(),
+        ESCROW_ALLOWLIST.as_bytes(),
+    ]
+}
+
+pub fn find_escrow_allowlist_account(authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&find_escrow_allowlist_seeds(authority), &crate::id())
+}
+
+/// Associated token account of `escrow` holding `attribute_mint`, where attribute tokens
+/// deposited into the escrow are kept.
+pub fn find_escrow_attribute_ata(escrow: &Pubkey, attribute_mint: &Pubkey) -> Pubkey {
+    spl_associated_token_account::get_associated_token_address(escrow, attribute_mint)
+}
+
+/// Escrow seeds followed by the bump, as needed to sign for the escrow.
+pub fn escrow_signer_seeds<'a>(
+    mint: &'a Pubkey,
+    authority: &'a EscrowAuthority,
+    bump_seed: &'a [u8],
+) -> Vec<&'a [u8]> {
+    escrow_signer_seeds_tagged(mint, authority, ESCROW_DEFAULT_TAG, bump_seed)
+}
+
+/// Escrow seeds with `tag` followed by the bump, as needed to sign for a tagged escrow.
+pub fn escrow_signer_seeds_tagged<'a>(
+    mint: &'a Pubkey,
+    authority: &'a EscrowAuthority,
+    tag: u8,
+    bump_seed: &'a [u8],
+) -> Vec<&'a [u8]> {
+    let mut seeds = find_escrow_seeds_tagged(mint, authority, tag);
+    seeds.push(bump_seed);
+    seeds
+}
+
+/// Invoke `instruction` with the escrow of `mint` and `authority` signing. `bump` is the
+/// bump stored in the escrow, which the caller must already have compared with the
+/// canonical bump returned by `assert_escrow_derivation_tagged`, so an escrow recorded with
+/// another valid bump can't sign for an address the escrow instructions never derive.
+pub fn invoke_escrow_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    mint: &Pubkey,
+    authority: &EscrowAuthority,
+    bump: u8,
+) -> ProgramResult {
+    invoke_escrow_signed_tagged(
+        instruction,
+        account_infos,
+        mint,
+        authority,
+        ESCROW_DEFAULT_TAG,
+        bump,
+    )
+}
+
+/// Same as `invoke_escrow_signed`, with the escrow of `mint` and `authority` with `tag`
+/// signing.
+pub fn invoke_escrow_signed_tagged(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    mint: &Pubkey,
+    authority: &EscrowAuthority,
+    tag: u8,
+    bump: u8,
+) -> ProgramResult {
+    let bump_seed = [bump];
+    let signer_seeds = escrow_signer_seeds_tagged(mint, authority, tag, &bump_seed);
+
+    invoke_signed(instruction, account_infos, &[&signer_seeds])
+}
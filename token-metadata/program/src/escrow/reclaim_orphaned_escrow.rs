@@ -0,0 +1,153 @@
+use crate::{
+    error::MetadataError,
+    escrow::{
+        events::{emit_event, EscrowClosed, EscrowEvent},
+        next_recorded_rent_destination,
+        pda::assert_escrow_derivation_tagged,
+    },
+    instruction::MetadataInstruction,
+    pda::find_metadata_account,
+    state::{EscrowAuthority, Metadata, TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{
+        assert_account_key, assert_escrow_empty, assert_multisig_signers, assert_owned_by,
+        assert_signer, close_account_raw, get_mint_supply,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, next_account_infos, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Closes the escrow of a burned base token, refunding its rent to `authority`, or to
+/// `rent_destination` when the escrow recorded a rent recipient or a funder. `escrow_token_accounts` are the token
+/// accounts owned by the escrow, which must all be empty.
+pub fn reclaim_orphaned_escrow(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    mint_account: Pubkey,
+    authority: Pubkey,
+    rent_destination: Option<Pubkey>,
+    escrow_token_accounts: &[Pubkey],
+) -> Instruction {
+    let (metadata_account, _) = find_metadata_account(&mint_account);
+
+    let mut accounts = vec![
+        AccountMeta::new(escrow_account, false),
+        AccountMeta::new_readonly(metadata_account, false),
+        AccountMeta::new_readonly(mint_account, false),
+        AccountMeta::new(authority, true),
+    ];
+
+    if let Some(rent_destination) = rent_destination {
+        accounts.push(AccountMeta::new(rent_destination, false));
+    }
+
+    for escrow_token_account in escrow_token_accounts {
+        accounts.push(AccountMeta::new_readonly(*escrow_token_account, false));
+    }
+
+    let data = MetadataInstruction::ReclaimOrphanedEscrow
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_reclaim_orphaned_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    assert_signer(authority_info)?;
+
+    assert_owned_by(escrow_account_info, program_id)?;
+    let toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+    // The escrow must be attached to the mint passed in.
+    assert_account_key(
+        mint_account_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        mint_account_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    // Nobody can prove they hold a token that no longer exists, which is what would
+    // otherwise leave the escrow stuck.
+    assert_owned_by(mint_account_info, &spl_token::id())?;
+    if get_mint_supply(mint_account_info)? != 0 {
+        return Err(MetadataError::BaseTokenNotBurned.into());
+    }
+
+    match toe.authority {
+        EscrowAuthority::TokenOwner => {
+            // Without a holder, the update authority of the burned token speaks for it. It is
+            // recorded on the escrow since `BurnNft` clears the metadata, only escrows created
+            // before it was recorded still read it from the metadata.
+            let update_authority = match toe.update_authority {
+                Some(update_authority) => update_authority,
+                None => {
+                    assert_account_key(
+                        metadata_account_info,
+                        &find_metadata_account(mint_account_info.key).0,
+                        MetadataError::InvalidMetadataKey,
+                    )?;
+                    Metadata::from_account_info(metadata_account_info)?.update_authority
+                }
+            };
+
+            if update_authority != *authority_info.key {
+                return Err(MetadataError::MustBeEscrowAuthority.into());
+            }
+        }
+        EscrowAuthority::Creator(creator) => {
+            if creator != *authority_info.key {
+                return Err(MetadataError::MustBeEscrowAuthority.into());
+            }
+        }
+        // The members follow the authority.
+        EscrowAuthority::Multisig(ref multisig) => {
+            let member_infos = next_account_infos(account_info_iter, multisig.members.len())?;
+            assert_multisig_signers(multisig, member_infos)?;
+        }
+    }
+
+    // Escrows that recorded a rent recipient or a funder refund it instead of the authority.
+    let rent_destination_info =
+        next_recorded_rent_destination(&toe, account_info_iter)?.unwrap_or(authority_info);
+
+    // Any token accounts the escrow owns are passed in as remaining accounts.
+    assert_escrow_empty(escrow_account_info.key, account_info_iter.as_slice())?;
+
+    close_account_raw(rent_destination_info, escrow_account_info)?;
+
+    emit_event(&EscrowEvent::Closed(EscrowClosed {
+        escrow: *escrow_account_info.key,
+        base_token: toe.base_token,
+        rent_destination: *rent_destination_info.key,
+    }))?;
+
+    Ok(())
+}
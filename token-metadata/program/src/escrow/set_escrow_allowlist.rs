@@ -0,0 +1,119 @@
+use crate::{
+    error::MetadataError,
+    escrow::pda::find_escrow_allowlist_seeds,
+    instruction::MetadataInstruction,
+    state::{EscrowAllowlist, Key, TokenMetadataAccount, MAX_ESCROW_ALLOWLIST_MINTS},
+    utils::{assert_derivation, assert_owned_by, assert_signer, create_or_allocate_account_raw},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct SetEscrowAllowlistArgs {
+    /// Attribute mints that may be deposited, replacing any previously allowed.
+    pub mints: Vec<Pubkey>,
+}
+
+/// Creates or replaces the escrow allowlist of the update authority `authority`.
+pub fn set_escrow_allowlist(
+    program_id: Pubkey,
+    allowlist_account: Pubkey,
+    authority: Pubkey,
+    payer_account: Pubkey,
+    mints: Vec<Pubkey>,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(allowlist_account, false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new(payer_account, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = MetadataInstruction::SetEscrowAllowlist(SetEscrowAllowlistArgs { mints })
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_set_escrow_allowlist<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: SetEscrowAllowlistArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let allowlist_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    if *system_account_info.key != system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
+
+    assert_signer(authority_info)?;
+    assert_signer(payer_account_info)?;
+
+    let allowlist_seeds = find_escrow_allowlist_seeds(authority_info.key);
+    let bump = assert_derivation(program_id, allowlist_info, &allowlist_seeds)?;
+
+    let mut mints = args.mints;
+    mints.sort();
+    mints.dedup();
+
+    if mints.len() > MAX_ESCROW_ALLOWLIST_MINTS {
+        return Err(MetadataError::EscrowAllowlistTooLong.into());
+    }
+
+    if allowlist_info.data_is_empty() {
+        let bump_seed = [bump];
+        let signer_seeds = [allowlist_seeds, vec![&bump_seed]].concat();
+
+        create_or_allocate_account_raw(
+            *program_id,
+            allowlist_info,
+            system_account_info,
+            payer_account_info,
+            EscrowAllowlist::LEN,
+            &signer_seeds,
+        )?;
+    } else {
+        assert_owned_by(allowlist_info, program_id)?;
+        EscrowAllowlist::from_account_info(allowlist_info)?;
+    }
+
+    let allowlist = EscrowAllowlist {
+        key: Key::EscrowAllowlist,
+        authority: *authority_info.key,
+        bump,
+        mints,
+    };
+
+    let mut allowlist_data = allowlist_info
+        .try_borrow_mut_data()
+        .map_err(|_| MetadataError::DataBorrowFailed)?;
+
+    // A shorter list must not leave mints of the previous one behind.
+    allowlist_data.fill(0);
+    allowlist
+        .serialize(&mut *allowlist_data)
+        .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use crate::{
+    error::MetadataError,
+    escrow::{assert_escrow_authority, pda::assert_escrow_derivation_tagged},
+    instruction::MetadataInstruction,
+    state::{TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{assert_account_key, assert_owned_by, assert_signer, resize_or_reallocate_account_raw},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct SetEscrowOperatorArgs {
+    /// New operator, or `None` to remove the current one.
+    pub operator: Option<Pubkey>,
+}
+
+/// Sets or clears the operator allowed to transfer tokens into and out of an escrow. Only the
+/// escrow authority may call it, never the operator itself. Older escrows are migrated to the
+/// current layout, with the payer covering any extra rent.
+#[allow(clippy::too_many_arguments)]
+pub fn set_escrow_operator(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    authority: Pubkey,
+    payer_account: Pubkey,
+    operator: Option<Pubkey>,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(escrow_account, false),
+        AccountMeta::new_readonly(mint_account, false),
+        AccountMeta::new_readonly(token_account, false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new(payer_account, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = MetadataInstruction::SetEscrowOperator(SetEscrowOperatorArgs { operator })
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_set_escrow_operator<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+    args: SetEscrowOperatorArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    if *system_account_info.key != system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
+
+    assert_signer(authority_info)?;
+    assert_signer(payer_account_info)?;
+
+    assert_owned_by(escrow_account_info, program_id)?;
+    let mut toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+    // The escrow must be attached to the mint passed in.
+    assert_account_key(
+        mint_account_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        mint_account_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    // The operator is never accepted here, so it can't replace or keep itself.
+    assert_escrow_authority(&toe, mint_account_info, token_account_info, authority_info)?;
+
+    // The operator is only stored by the current layout.
+    toe.migrate();
+    toe.operator = args.operator;
+
+    if escrow_account_info.data_len() < TokenOwnedEscrow::size_for(&toe.authority) {
+        resize_or_reallocate_account_raw(
+            escrow_account_info,
+            payer_account_info,
+            system_account_info,
+            TokenOwnedEscrow::size_for(&toe.authority),
+        )?;
+    }
+
+    let mut escrow_data = escrow_account_info
+        .try_borrow_mut_data()
+        .map_err(|_| MetadataError::DataBorrowFailed)?;
+
+    toe.serialize(&mut *escrow_data)
+        .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use crate::{
+    error::MetadataError,
+    escrow::{
+        events::{emit_event, EscrowEvent, EscrowTransfer},
+        pda::{
+            assert_escrow_derivation_tagged, find_escrow_allowlist_account,
+            find_escrow_allowlist_seeds, find_escrow_attribute_ata,
+        },
+    },
+    instruction::MetadataInstruction,
+    state::{EscrowAllowlist, EscrowAuthority, Metadata, TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{
+        assert_account_key, assert_derivation, assert_holds_base_token, assert_initialized,
+        assert_multisig_signers, assert_owned_by, assert_signer, is_token_holder, transfer_spl,
+        TokenTransferParams,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+use spl_token::state::is_initialized_account;
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct TransferIntoEscrowArgs {
+    pub amount: u64,
+    /// Whether an authority other than the payer follows the escrow allowlist.
+    pub has_authority: bool,
+}
+
+/// Deposits an attribute token into the escrow's associated token account. The source
+/// token account must be owned by the escrow authority, or by the payer if none is given.
+/// The escrow allowlist of `update_authority`, the update authority of the base token, is
+/// always passed, and restricts the attribute mints once it has been set.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_into_escrow(
+    program_id: Pubkey,
+    escrow: Pubkey,
+    metadata: Pubkey,
+    payer: Pubkey,
+    attribute_mint: Pubkey,
+    attribute_src: Pubkey,
+    escrow_mint: Pubkey,
+    escrow_account: Pubkey,
+    update_authority: Pubkey,
+    authority: Option<Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let attribute_dst = find_escrow_attribute_ata(&escrow, &attribute_mint);
+    let (allowlist, _) = find_escrow_allowlist_account(&update_authority);
+
+    let mut accounts = vec![
+        AccountMeta::new_readonly(escrow, false),
+        AccountMeta::new_readonly(metadata, false),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(attribute_mint, false),
+        AccountMeta::new(attribute_src, false),
+        AccountMeta::new(attribute_dst, false),
+        AccountMeta::new_readonly(escrow_mint, false),
+        AccountMeta::new_readonly(escrow_account, false),
+        AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+        AccountMeta::new_readonly(allowlist, false),
+    ];
+
+    if let Some(authority) = authority {
+        accounts.push(AccountMeta::new_readonly(authority, true));
+    }
+
+    let data = MetadataInstruction::TransferIntoEscrow(TransferIntoEscrowArgs {
+        amount,
+        has_authority: authority.is_some(),
+    })
+    .try_to_vec()
+    .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+/// Fails unless `attribute_mint_info` may be deposited into `escrow_info`, the escrow of the
+/// base token `escrow_mint_info` described by `metadata_info`, landing in `attribute_dst_info`.
+/// `allowlist_info` must be the escrow allowlist of the base token's update authority, and
+/// restricts the deposit once it has been set.
+pub(crate) fn assert_deposit_allowed(
+    program_id: &Pubkey,
+    escrow_info: &AccountInfo,
+    metadata_info: &AccountInfo,
+    escrow_mint_info: &AccountInfo,
+    attribute_mint_info: &AccountInfo,
+    attribute_dst_info: &AccountInfo,
+    allowlist_info: &AccountInfo,
+) -> ProgramResult {
+    assert_owned_by(metadata_info, program_id)?;
+
+    assert_account_key(
+        escrow_mint_info,
+        &Metadata::mint_from_account_info(metadata_info)?,
+        MetadataError::MintMismatch,
+    )?;
+
+    // The escrow is owned through the base token, so the base token can't be put inside it.
+    if attribute_mint_info.key == escrow_mint_info.key {
+        return Err(MetadataError::CannotDepositBaseToken.into());
+    }
+
+    // Only the allowlist of the base token's own update authority restricts its escrow.
+    let update_authority = Metadata::update_authority_from_account_info(metadata_info)?;
+    assert_derivation(
+        program_id,
+        allowlist_info,
+        &find_escrow_allowlist_seeds(&update_authority),
+    )?;
+
+    // Until the update authority sets an allowlist, any attribute mint can be deposited.
+    if !allowlist_info.data_is_empty() {
+        assert_owned_by(allowlist_info, program_id)?;
+        let allowlist = EscrowAllowlist::from_account_info(allowlist_info)?;
+        if !allowlist.allows(attribute_mint_info.key) {
+            return Err(MetadataError::TokenNotAllowed.into());
+        }
+    }
+
+    // Deposits always land in the escrow's associated token account.
+    assert_account_key(
+        attribute_dst_info,
+        &find_escrow_attribute_ata(escrow_info.key, attribute_mint_info.key),
+        MetadataError::InvalidOwner,
+    )
+}
+
+pub fn process_transfer_into_escrow(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: TransferIntoEscrowArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let attribute_mint_info = next_account_info(account_info_iter)?;
+    let attribute_src_info = next_account_info(account_info_iter)?;
+    let attribute_dst_info = next_account_info(account_info_iter)?;
+    let escrow_mint_info = next_account_info(account_info_iter)?;
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let ata_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let allowlist_info = next_account_info(account_info_iter)?;
+
+    // Allow the option to set a different authority than the payer.
+    let maybe_authority_info: Option<&AccountInfo> = if args.has_authority {
+        let auth = next_account_info(account_info_iter)?;
+        assert_signer(auth)?;
+        Some(auth)
+    } else {
+        None
+    };
+    let authority = maybe_authority_info.unwrap_or(payer_info);
+
+    assert_signer(payer_info)?;
+
+    assert_owned_by(escrow_info, program_id)?;
+    let toe = TokenOwnedEscrow::from_account_info(escrow_info)?;
+
+    if toe.locked {
+        return Err(MetadataError::EscrowLocked.into());
+    }
+
+    // The escrow must be attached to the base mint passed in.
+    assert_account_key(
+        escrow_mint_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
+
+    assert_deposit_allowed(
+        program_id,
+        escrow_info,
+        metadata_info,
+        escrow_mint_info,
+        attribute_mint_info,
+        attribute_dst_info,
+        allowlist_info,
+    )?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_info,
+        escrow_mint_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    // The base NFT must still be held by the token account passed in.
+    assert_owned_by(escrow_account_info, &spl_token::id())?;
+    let escrow_account: spl_token::state::Account = assert_initialized(escrow_account_info)?;
+    assert_account_key(
+        escrow_mint_info,
+        &escrow_account.mint,
+        MetadataError::MintMismatch,
+    )?;
+    assert_holds_base_token(escrow_account.amount, toe.token_standard)?;
+
+    // Check that the authority matches based on the authority type, unless the escrow
+    // operator signed in its place.
+    if toe.operator != Some(*authority.key) {
+        match toe.authority {
+            EscrowAuthority::TokenOwner => {
+                if !is_token_holder(&escrow_account, authority.key) {
+                    return Err(MetadataError::MustBeEscrowAuthority.into());
+                }
+            }
+            EscrowAuthority::Creator(creator) => {
+                if creator != *authority.key {
+                    return Err(MetadataError::MustBeEscrowAuthority.into());
+                }
+            }
+            // The members follow the optional authority.
+            EscrowAuthority::Multisig(ref multisig) => {
+                assert_multisig_signers(multisig, account_info_iter.as_slice())?;
+            }
+        }
+    }
+
+    // Allocate the escrow ATA if it doesn't exist.
+    if !is_initialized_account(*attribute_dst_info.data.borrow()) {
+        #[allow(deprecated)]
+        let create_escrow_ata_ix = spl_associated_token_account::create_associated_token_account(
+            payer_info.key,
+            escrow_info.key,
+            attribute_mint_info.key,
+        );
+
+        invoke(
+            &create_escrow_ata_ix,
+            &[
+                payer_info.clone(),
+                attribute_dst_info.clone(),
+                escrow_info.clone(),
+                attribute_mint_info.clone(),
+                system_account_info.clone(),
+                token_program_info.clone(),
+                ata_program_info.clone(),
+                rent_info.clone(),
+            ],
+        )?;
+    }
+
+    let attribute_src = spl_token::state::Account::unpack(&attribute_src_info.data.borrow())?;
+    assert_account_key(
+        attribute_mint_info,
+        &attribute_src.mint,
+        MetadataError::MintMismatch,
+    )?;
+    if attribute_src.amount < args.amount {
+        return Err(MetadataError::InsufficientTokens.into());
+    }
+
+    // Transfer the token from the authority into the escrow ATA.
+    transfer_spl(TokenTransferParams {
+        source: attribute_src_info.clone(),
+        destination: attribute_dst_info.clone(),
+        amount: args.amount,
+        authority: authority.clone(),
+        authority_signer_seeds: None,
+        token_program: token_program_info.clone(),
+    })?;
+
+    emit_event(&EscrowEvent::TransferIn(EscrowTransfer {
+        escrow: *escrow_info.key,
+        mint: *attribute_mint_info.key,
+        amount: args.amount,
+    }))?;
+
+    Ok(())
+}
@@ -1,16 +1,22 @@
 use crate::{
     error::MetadataError,
-    escrow::pda::find_escrow_seeds,
+    escrow::{
+        events::{emit_event, EscrowEvent, EscrowTransfer},
+        pda::{assert_escrow_derivation_tagged, invoke_escrow_signed_tagged},
+    },
     instruction::MetadataInstruction,
-    state::{EscrowAuthority, TokenMetadataAccount, TokenOwnedEscrow},
-    utils::{assert_derivation, assert_owned_by, assert_signer},
+    state::{EscrowAuthority, Metadata, TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{
+        assert_account_key, assert_holds_base_token, assert_initialized, assert_multisig_signers,
+        assert_owned_by, assert_signer, is_token_holder,
+    },
 };
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{maybestd::io::Error as BorshError, BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
-    program::{invoke, invoke_signed},
+    program::invoke,
     program_pack::Pack,
     pubkey::Pubkey,
 };
@@ -21,14 +27,37 @@ use spl_token::state::is_initialized_account;
 
 #[repr(C)]
 #[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+#[derive(BorshSerialize, PartialEq, Eq, Debug, Clone)]
 pub struct TransferOutOfEscrowArgs {
     pub amount: u64,
+    /// Whether an authority other than the payer follows the fixed accounts, with the
+    /// metadata of the base token after it. `None` for instructions encoded before the
+    /// flag, which end after the amount and pass the authority last, if at all, and no
+    /// metadata.
+    pub has_authority: Option<bool>,
 }
 
+impl BorshDeserialize for TransferOutOfEscrowArgs {
+    fn deserialize(buf: &mut &[u8]) -> Result<Self, BorshError> {
+        let amount: u64 = BorshDeserialize::deserialize(buf)?;
+        let has_authority: Option<bool> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+
+        Ok(TransferOutOfEscrowArgs {
+            amount,
+            has_authority,
+        })
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_out_of_escrow(
     program_id: Pubkey,
     escrow: Pubkey,
+    metadata: Pubkey,
     payer: Pubkey,
     attribute_mint: Pubkey,
     attribute_src: Pubkey,
@@ -56,9 +85,14 @@ pub fn transfer_out_of_escrow(
         accounts.push(AccountMeta::new_readonly(authority, true));
     }
 
-    let data = MetadataInstruction::TransferOutOfEscrow(TransferOutOfEscrowArgs { amount })
-        .try_to_vec()
-        .unwrap();
+    accounts.push(AccountMeta::new_readonly(metadata, false));
+
+    let data = MetadataInstruction::TransferOutOfEscrow(TransferOutOfEscrowArgs {
+        amount,
+        has_authority: Some(authority.is_some()),
+    })
+    .try_to_vec()
+    .unwrap();
 
     Instruction {
         program_id,
@@ -86,9 +120,10 @@ pub fn process_transfer_out_of_escrow(
     let token_program_info = next_account_info(account_info_iter)?;
     let rent_info = next_account_info(account_info_iter)?;
 
-    // Allow the option to set a different authority than the payer.
-    let is_using_authority = account_info_iter.len() == 1;
-    let maybe_authority_info: Option<&AccountInfo> = if is_using_authority {
+    // Allow the option to set a different authority than the payer. Instructions encoded
+    // before the flag pass it as the only account after the fixed ones.
+    let has_authority = args.has_authority.unwrap_or(account_info_iter.len() == 1);
+    let maybe_authority_info: Option<&AccountInfo> = if has_authority {
         let auth = next_account_info(account_info_iter)?;
         assert_signer(auth)?;
         Some(auth)
@@ -97,14 +132,46 @@ pub fn process_transfer_out_of_escrow(
     };
     let authority = maybe_authority_info.unwrap_or(payer_info);
 
+    // Those older instructions don't pass the metadata either.
+    let maybe_metadata_info: Option<&AccountInfo> = if args.has_authority.is_some() {
+        Some(next_account_info(account_info_iter)?)
+    } else {
+        None
+    };
+
     assert_owned_by(escrow_info, program_id)?;
     let toe = TokenOwnedEscrow::from_account_info(escrow_info)?;
 
-    // Derive the seeds for PDA signing.
-    let escrow_seeds = find_escrow_seeds(escrow_mint_info.key, &toe.authority);
+    if toe.locked {
+        return Err(MetadataError::EscrowLocked.into());
+    }
+
+    // The escrow must be attached to the base mint passed in.
+    assert_account_key(
+        escrow_mint_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
 
-    let bump_seed = &[assert_derivation(&crate::id(), escrow_info, &escrow_seeds)?];
-    let escrow_authority_seeds = [escrow_seeds, vec![bump_seed]].concat();
+    if let Some(metadata_info) = maybe_metadata_info {
+        assert_owned_by(metadata_info, program_id)?;
+
+        assert_account_key(
+            escrow_mint_info,
+            &Metadata::mint_from_account_info(metadata_info)?,
+            MetadataError::MintMismatch,
+        )?;
+    }
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_info,
+        escrow_mint_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
 
     assert_signer(payer_info)?;
 
@@ -133,32 +200,53 @@ pub fn process_transfer_out_of_escrow(
 
     // Deserialize the token accounts and perform checks.
     let attribute_src = spl_token::state::Account::unpack(&attribute_src_info.data.borrow())?;
-    if attribute_src.mint != *attribute_mint_info.key {
-        return Err(MetadataError::MintMismatch.into());
-    }
+    assert_account_key(
+        attribute_mint_info,
+        &attribute_src.mint,
+        MetadataError::MintMismatch,
+    )?;
     if attribute_src.amount < args.amount {
         return Err(MetadataError::InsufficientTokens.into());
     }
 
-    // Check that the authority matches based on the authority type.
-    let escrow_account = spl_token::state::Account::unpack(&escrow_account_info.data.borrow())?;
-    match toe.authority {
-        EscrowAuthority::TokenOwner => {
-            if escrow_account.owner != *authority.key {
-                return Err(MetadataError::MustBeEscrowAuthority.into());
+    // The base NFT must still be held by the token account passed in, so a former
+    // holder can't drain the escrow after selling the NFT.
+    assert_owned_by(escrow_account_info, &spl_token::id())?;
+    let escrow_account: spl_token::state::Account = assert_initialized(escrow_account_info)?;
+    assert_account_key(
+        escrow_mint_info,
+        &escrow_account.mint,
+        MetadataError::MintMismatch,
+    )?;
+    assert_holds_base_token(escrow_account.amount, toe.token_standard)?;
+
+    // Check that the authority matches based on the authority type, unless the escrow
+    // operator signed in its place.
+    if toe.operator != Some(*authority.key) {
+        match toe.authority {
+            EscrowAuthority::TokenOwner => {
+                if !is_token_holder(&escrow_account, authority.key) {
+                    return Err(MetadataError::MustBeEscrowAuthority.into());
+                }
             }
-        }
-        EscrowAuthority::Creator(creator) => {
-            if creator != *authority.key {
-                return Err(MetadataError::MustBeEscrowAuthority.into());
+            EscrowAuthority::Creator(creator) => {
+                if creator != *authority.key {
+                    return Err(MetadataError::MustBeEscrowAuthority.into());
+                }
+            }
+            // The members follow the optional authority.
+            EscrowAuthority::Multisig(ref multisig) => {
+                assert_multisig_signers(multisig, account_info_iter.as_slice())?;
             }
         }
     }
 
     let attribute_dst = spl_token::state::Account::unpack(&attribute_dst_info.data.borrow())?;
-    if attribute_dst.mint != *attribute_mint_info.key {
-        return Err(MetadataError::MintMismatch.into());
-    }
+    assert_account_key(
+        attribute_mint_info,
+        &attribute_dst.mint,
+        MetadataError::MintMismatch,
+    )?;
 
     // Transfer the token out of the escrow to the destination ATA.
     let transfer_ix = spl_token::instruction::transfer(
@@ -170,7 +258,7 @@ pub fn process_transfer_out_of_escrow(
         args.amount,
     )?;
 
-    invoke_signed(
+    invoke_escrow_signed_tagged(
         &transfer_ix,
         &[
             attribute_src_info.clone(),
@@ -178,10 +266,27 @@ pub fn process_transfer_out_of_escrow(
             escrow_info.clone(),
             token_program_info.clone(),
         ],
-        &[&escrow_authority_seeds],
+        escrow_mint_info.key,
+        &toe.authority,
+        toe.tag,
+        toe.bump,
     )?;
 
-    // Close the source ATA and return funds to the user.
+    emit_event(&EscrowEvent::TransferOut(EscrowTransfer {
+        escrow: *escrow_info.key,
+        mint: *attribute_mint_info.key,
+        amount: args.amount,
+    }))?;
+
+    // Close the source ATA and return funds to the user once it has been emptied.
+    let remaining_amount = attribute_src
+        .amount
+        .checked_sub(args.amount)
+        .ok_or(MetadataError::NumericalOverflowError)?;
+    if remaining_amount > 0 {
+        return Ok(());
+    }
+
     let close_ix = spl_token::instruction::close_account(
         &spl_token::id(),
         attribute_src_info.key,
@@ -190,7 +295,7 @@ pub fn process_transfer_out_of_escrow(
         &[escrow_info.key],
     )?;
 
-    invoke_signed(
+    invoke_escrow_signed_tagged(
         &close_ix,
         &[
             attribute_src_info.clone(),
@@ -198,7 +303,10 @@ pub fn process_transfer_out_of_escrow(
             escrow_info.clone(),
             token_program_info.clone(),
         ],
-        &[&escrow_authority_seeds],
+        escrow_mint_info.key,
+        &toe.authority,
+        toe.tag,
+        toe.bump,
     )?;
 
     Ok(())
@@ -0,0 +1,337 @@
+use crate::{
+    error::MetadataError,
+    escrow::{
+        assert_escrow_token_standard,
+        events::{emit_event, EscrowAuthorityUpdated, EscrowEvent},
+        next_recorded_rent_destination,
+        pda::{
+            assert_escrow_derivation_tagged, find_escrow_account_tagged, find_escrow_seeds_tagged,
+            invoke_escrow_signed_tagged, ESCROW_DEFAULT_TAG,
+        },
+    },
+    instruction::MetadataInstruction,
+    state::{
+        EscrowAuthority, Key, Metadata, TokenMetadataAccount, TokenOwnedEscrow,
+        TOKEN_OWNED_ESCROW_VERSION,
+    },
+    utils::{
+        assert_account_key, assert_derivation_bump_seed, assert_edition_valid, assert_initialized,
+        assert_owned_by, assert_signer, assert_token_owned_by,
+        assert_token_program_matches_package, close_account_raw, create_or_allocate_account_raw,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program,
+};
+
+/// Hands a creator owned escrow over to the holder of the base token. Each entry in
+/// `escrow_token_accounts` is a `(source, destination)` pair where the source is owned by
+/// the current escrow and the destination is the matching token account of the new escrow.
+/// The rent of the current escrow goes to `payer_account`, or to `rent_destination` when
+/// the escrow recorded a rent recipient or a funder.
+#[allow(clippy::too_many_arguments)]
+pub fn update_escrow_authority(
+    program_id: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Pubkey,
+    new_authority: Pubkey,
+    rent_destination: Option<Pubkey>,
+    escrow_token_accounts: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    update_escrow_authority_tagged(
+        program_id,
+        metadata_account,
+        mint_account,
+        token_account,
+        edition_account,
+        payer_account,
+        authority,
+        new_authority,
+        rent_destination,
+        escrow_token_accounts,
+        ESCROW_DEFAULT_TAG,
+    )
+}
+
+/// Hands the creator owned escrow created with `tag` over to the holder of the base token,
+/// like [`update_escrow_authority`]. The holder's escrow keeps the same tag.
+#[allow(clippy::too_many_arguments)]
+pub fn update_escrow_authority_tagged(
+    program_id: Pubkey,
+    metadata_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    edition_account: Pubkey,
+    payer_account: Pubkey,
+    authority: Pubkey,
+    new_authority: Pubkey,
+    rent_destination: Option<Pubkey>,
+    escrow_token_accounts: &[(Pubkey, Pubkey)],
+    tag: u8,
+) -> Instruction {
+    let (escrow_account, _) =
+        find_escrow_account_tagged(&mint_account, &EscrowAuthority::Creator(authority), tag);
+    let (new_escrow_account, _) =
+        find_escrow_account_tagged(&mint_account, &EscrowAuthority::TokenOwner, tag);
+
+    let mut accounts = vec![
+        AccountMeta::new(escrow_account, false),
+        AccountMeta::new(new_escrow_account, false),
+        AccountMeta::new_readonly(metadata_account, false),
+        AccountMeta::new_readonly(mint_account, false),
+        AccountMeta::new_readonly(token_account, false),
+        AccountMeta::new_readonly(edition_account, false),
+        AccountMeta::new(payer_account, true),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new_readonly(new_authority, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+
+    if let Some(rent_destination) = rent_destination {
+        accounts.push(AccountMeta::new(rent_destination, false));
+    }
+
+    for (source, destination) in escrow_token_accounts {
+        accounts.push(AccountMeta::new(*source, false));
+        accounts.push(AccountMeta::new(*destination, false));
+    }
+
+    let data = MetadataInstruction::UpdateEscrowAuthority
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_update_escrow_authority<'a>(
+    program_id: &'a Pubkey,
+    accounts: &'a [AccountInfo<'a>],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let new_escrow_account_info = next_account_info(account_info_iter)?;
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let edition_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let new_authority_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    if *system_account_info.key != system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
+    assert_token_program_matches_package(token_program_info)?;
+
+    assert_signer(payer_account_info)?;
+    assert_signer(authority_info)?;
+    assert_owned_by(metadata_account_info, program_id)?;
+    assert_owned_by(mint_account_info, &spl_token::id())?;
+    assert_owned_by(token_account_info, &spl_token::id())?;
+
+    let metadata: Metadata = Metadata::from_account_info(metadata_account_info)?;
+    metadata.validate_lengths()?;
+
+    // Mint account passed in must be the mint of the metadata account passed in.
+    assert_account_key(
+        mint_account_info,
+        &metadata.mint,
+        MetadataError::MintMismatch,
+    )?;
+
+    assert_edition_valid(program_id, mint_account_info.key, edition_account_info)?;
+
+    // Fungible asset escrows can only exist if they were allowed at creation.
+    let token_standard =
+        assert_escrow_token_standard(mint_account_info, edition_account_info, true, true)?;
+
+    assert_owned_by(escrow_account_info, program_id)?;
+    let toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+    // Handing the escrow over moves its tokens, which a lock forbids.
+    if toe.locked {
+        return Err(MetadataError::EscrowLocked.into());
+    }
+
+    assert_account_key(
+        mint_account_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
+
+    // The escrow authority is part of the escrow seeds, so a token owner escrow already
+    // follows the token and there is nothing to hand off. A multisig escrow stays with its
+    // members.
+    match toe.authority {
+        EscrowAuthority::TokenOwner | EscrowAuthority::Multisig(_) => {
+            return Err(MetadataError::InvalidAuthorityType.into());
+        }
+        EscrowAuthority::Creator(creator) => {
+            if creator != *authority_info.key {
+                return Err(MetadataError::MustBeEscrowAuthority.into());
+            }
+        }
+    }
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        mint_account_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    // The new authority must currently hold the base token.
+    assert_token_owned_by(
+        token_account_info,
+        mint_account_info.key,
+        Some(new_authority_info.key),
+        1,
+    )?;
+
+    let new_authority = EscrowAuthority::TokenOwner;
+    let new_escrow_seeds = find_escrow_seeds_tagged(mint_account_info.key, &new_authority, toe.tag);
+    let new_bump_seed =
+        assert_derivation_bump_seed(program_id, new_escrow_account_info, &new_escrow_seeds)?;
+    let new_escrow_authority_seeds = [new_escrow_seeds, vec![&new_bump_seed[..]]].concat();
+
+    if !new_escrow_account_info.data_is_empty() {
+        return Err(MetadataError::AlreadyInitialized.into());
+    }
+
+    // Escrows that recorded a rent recipient or a funder refund it instead of the payer.
+    let rent_destination_info =
+        next_recorded_rent_destination(&toe, account_info_iter)?.unwrap_or(payer_account_info);
+
+    // Move every token held by the current escrow over to the new escrow.
+    let token_accounts = account_info_iter.as_slice();
+    if !token_accounts.len().is_multiple_of(2) {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    for pair in token_accounts.chunks(2) {
+        let source_info = &pair[0];
+        let destination_info = &pair[1];
+
+        assert_owned_by(source_info, &spl_token::id())?;
+        assert_owned_by(destination_info, &spl_token::id())?;
+        let source: spl_token::state::Account = assert_initialized(source_info)?;
+        let destination: spl_token::state::Account = assert_initialized(destination_info)?;
+
+        if source.owner != *escrow_account_info.key
+            || destination.owner != *new_escrow_account_info.key
+        {
+            return Err(MetadataError::IncorrectOwner.into());
+        }
+        if source.mint != destination.mint {
+            return Err(MetadataError::MintMismatch.into());
+        }
+
+        invoke_escrow_signed_tagged(
+            &spl_token::instruction::transfer(
+                token_program_info.key,
+                source_info.key,
+                destination_info.key,
+                escrow_account_info.key,
+                &[],
+                source.amount,
+            )?,
+            &[
+                source_info.clone(),
+                destination_info.clone(),
+                escrow_account_info.clone(),
+                token_program_info.clone(),
+            ],
+            mint_account_info.key,
+            &toe.authority,
+            toe.tag,
+            toe.bump,
+        )?;
+
+        invoke_escrow_signed_tagged(
+            &spl_token::instruction::close_account(
+                token_program_info.key,
+                source_info.key,
+                payer_account_info.key,
+                escrow_account_info.key,
+                &[],
+            )?,
+            &[
+                source_info.clone(),
+                payer_account_info.clone(),
+                escrow_account_info.clone(),
+                token_program_info.clone(),
+            ],
+            mint_account_info.key,
+            &toe.authority,
+            toe.tag,
+            toe.bump,
+        )?;
+    }
+
+    // Close the current escrow and recreate it under the new authority.
+    close_account_raw(rent_destination_info, escrow_account_info)?;
+
+    let new_toe = TokenOwnedEscrow {
+        key: Key::TokenOwnedEscrowV2,
+        base_token: *mint_account_info.key,
+        authority: EscrowAuthority::TokenOwner,
+        bump: new_bump_seed[0],
+        rent_recipient: toe.rent_recipient,
+        version: TOKEN_OWNED_ESCROW_VERSION,
+        locked: false,
+        token_standard: Some(token_standard),
+        // The operator was chosen by the previous authority, so it isn't carried over.
+        operator: None,
+        // The rent refunds keep going where the creator agreed to send them.
+        funder: toe.funder,
+        tag: toe.tag,
+        update_authority: Some(metadata.update_authority),
+    };
+
+    create_or_allocate_account_raw(
+        *program_id,
+        new_escrow_account_info,
+        system_account_info,
+        payer_account_info,
+        TokenOwnedEscrow::size_for(&new_toe.authority),
+        &new_escrow_authority_seeds,
+    )?;
+
+    let mut escrow_data = new_escrow_account_info
+        .try_borrow_mut_data()
+        .map_err(|_| MetadataError::DataBorrowFailed)?;
+
+    new_toe
+        .serialize(&mut *escrow_data)
+        .map_err(|_| MetadataError::BorshSerializationError)?;
+
+    emit_event(&EscrowEvent::AuthorityUpdated(EscrowAuthorityUpdated {
+        escrow: *escrow_account_info.key,
+        new_escrow: *new_escrow_account_info.key,
+        base_token: new_toe.base_token,
+        new_authority: new_toe.authority,
+    }))?;
+
+    Ok(())
+}
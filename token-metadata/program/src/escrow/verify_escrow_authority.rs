@@ -0,0 +1,115 @@
+use crate::{
+    error::MetadataError,
+    escrow::pda::assert_escrow_derivation_tagged,
+    instruction::MetadataInstruction,
+    state::{EscrowAuthority, TokenMetadataAccount, TokenOwnedEscrow},
+    utils::{
+        assert_account_key, assert_initialized, assert_owned_by, assert_signer, is_token_holder,
+    },
+};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+/// Guard instruction that only succeeds if `authority` signs and currently controls the
+/// escrow, for composing in front of other instructions.
+pub fn verify_escrow_authority(
+    program_id: Pubkey,
+    escrow_account: Pubkey,
+    mint_account: Pubkey,
+    token_account: Pubkey,
+    authority: Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new_readonly(escrow_account, false),
+        AccountMeta::new_readonly(mint_account, false),
+        AccountMeta::new_readonly(token_account, false),
+        AccountMeta::new_readonly(authority, true),
+    ];
+
+    let data = MetadataInstruction::VerifyEscrowAuthority
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_verify_escrow_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let escrow_account_info = next_account_info(account_info_iter)?;
+    let mint_account_info = next_account_info(account_info_iter)?;
+    let token_account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+
+    assert_signer(authority_info)?;
+
+    assert_owned_by(escrow_account_info, program_id)?;
+    let toe = TokenOwnedEscrow::from_account_info(escrow_account_info)?;
+
+    // The escrow must be attached to the mint passed in.
+    assert_account_key(
+        mint_account_info,
+        &toe.base_token,
+        MetadataError::MintMismatch,
+    )?;
+
+    let bump_seed = assert_escrow_derivation_tagged(
+        escrow_account_info,
+        mint_account_info.key,
+        &toe.authority,
+        toe.tag,
+    )?;
+
+    if bump_seed != toe.bump {
+        return Err(MetadataError::InvalidEscrowBumpSeed.into());
+    }
+
+    assert_escrow_authority(&toe, mint_account_info, token_account_info, authority_info)
+}
+
+/// Assert `authority_info` controls the escrow: it holds the base token in
+/// `token_account_info` for a token owner escrow, or is the creator of a creator escrow.
+pub fn assert_escrow_authority(
+    toe: &TokenOwnedEscrow,
+    mint_account_info: &AccountInfo,
+    token_account_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> ProgramResult {
+    match toe.authority {
+        EscrowAuthority::TokenOwner => {
+            assert_owned_by(token_account_info, &spl_token::id())?;
+            let token_account: spl_token::state::Account = assert_initialized(token_account_info)?;
+
+            assert_account_key(
+                mint_account_info,
+                &token_account.mint,
+                MetadataError::MintMismatch,
+            )?;
+
+            if token_account.amount < 1 || !is_token_holder(&token_account, authority_info.key) {
+                return Err(MetadataError::NotEscrowTokenOwner.into());
+            }
+        }
+        EscrowAuthority::Creator(creator) => {
+            if creator != *authority_info.key {
+                return Err(MetadataError::NotEscrowCreator.into());
+            }
+        }
+        // A single authority can't speak for a multisig.
+        EscrowAuthority::Multisig(_) => return Err(MetadataError::InvalidAuthorityType.into()),
+    }
+
+    Ok(())
+}
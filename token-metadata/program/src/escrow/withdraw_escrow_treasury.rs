@@ -0,0 +1,132 @@
+use crate::{
+    error::MetadataError,
+    escrow::pda::{find_escrow_treasury_account, find_escrow_treasury_seeds},
+    instruction::MetadataInstruction,
+    utils::{assert_owned_by, assert_signer, checked_minimum_balance},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+    sysvar::Sysvar,
+};
+
+#[cfg(feature = "serde-feature")]
+use serde::{Deserialize, Serialize};
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct WithdrawEscrowTreasuryArgs {
+    pub amount: u64,
+}
+
+/// Address of the program data account holding the upgrade authority of `program_id`.
+pub fn find_program_data_account(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id())
+}
+
+/// Withdraws `amount` lamports from the escrow treasury to `destination`. Only the upgrade
+/// authority of the program can withdraw, and the treasury must be left either empty or
+/// rent exempt.
+pub fn withdraw_escrow_treasury(
+    program_id: Pubkey,
+    authority: Pubkey,
+    destination: Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (treasury, _) = find_escrow_treasury_account();
+    let (program_data, _) = find_program_data_account(&program_id);
+
+    let accounts = vec![
+        AccountMeta::new(treasury, false),
+        AccountMeta::new_readonly(authority, true),
+        AccountMeta::new_readonly(program_data, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let data = MetadataInstruction::WithdrawEscrowTreasury(WithdrawEscrowTreasuryArgs { amount })
+        .try_to_vec()
+        .unwrap();
+
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn process_withdraw_escrow_treasury(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: WithdrawEscrowTreasuryArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let treasury_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let program_data_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    if *system_account_info.key != system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
+
+    let (treasury, bump_seed) = find_escrow_treasury_account();
+    if *treasury_info.key != treasury {
+        return Err(MetadataError::InvalidFeeAccount.into());
+    }
+
+    // The treasury belongs to whoever can upgrade the program.
+    assert_signer(authority_info)?;
+    if *program_data_info.key != find_program_data_account(program_id).0 {
+        return Err(MetadataError::InvalidTreasuryAuthority.into());
+    }
+    assert_owned_by(program_data_info, &bpf_loader_upgradeable::id())?;
+
+    // Only the metadata ahead of the program bytes is read.
+    let upgrade_authority = match limited_deserialize(
+        &program_data_info.try_borrow_data()?,
+        UpgradeableLoaderState::size_of_programdata_metadata() as u64,
+    ) {
+        Ok(UpgradeableLoaderState::ProgramData {
+            upgrade_authority_address,
+            ..
+        }) => upgrade_authority_address,
+        _ => return Err(MetadataError::InvalidTreasuryAuthority.into()),
+    };
+    if upgrade_authority != Some(*authority_info.key) {
+        return Err(MetadataError::InvalidTreasuryAuthority.into());
+    }
+
+    let remaining = treasury_info
+        .lamports()
+        .checked_sub(args.amount)
+        .ok_or(MetadataError::InvalidTreasuryWithdrawal)?;
+    if remaining != 0 && remaining < checked_minimum_balance(&Rent::get()?, 0)? {
+        return Err(MetadataError::InvalidTreasuryWithdrawal.into());
+    }
+
+    let bump = [bump_seed];
+    let mut signer_seeds = find_escrow_treasury_seeds();
+    signer_seeds.push(&bump);
+
+    invoke_signed(
+        &system_instruction::transfer(treasury_info.key, destination_info.key, args.amount),
+        &[
+            treasury_info.clone(),
+            destination_info.clone(),
+            system_account_info.clone(),
+        ],
+        &[&signer_seeds],
+    )
+}
@@ -1,6 +1,10 @@
 use crate::{
     deprecated_instruction::{MintPrintingTokensViaTokenArgs, SetReservationListArgs},
-    escrow::TransferOutOfEscrowArgs,
+    escrow::{
+        BurnNftAndCloseEscrowArgs, CreateEscrowAccountArgs, CreateEscrowAccountV2Args,
+        LogEscrowBumpArgs, SetEscrowAllowlistArgs, SetEscrowLockArgs, SetEscrowOperatorArgs,
+        TransferIntoEscrowArgs, TransferOutOfEscrowArgs, WithdrawEscrowTreasuryArgs,
+    },
     state::{
         Collection, CollectionDetails, Creator, Data, DataV2, Uses, EDITION,
         EDITION_MARKER_BIT_SIZE, PREFIX,
@@ -48,6 +52,17 @@ pub struct UpdateMetadataAccountArgsV2 {
     pub is_mutable: Option<bool>,
 }
 
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, Default)]
+/// Args for partial update call, fields left as `None` are unchanged
+pub struct UpdateMetadataFieldsArgs {
+    pub name: Option<String>,
+    pub symbol: Option<String>,
+    pub uri: Option<String>,
+    pub seller_fee_basis_points: Option<u16>,
+}
+
 #[repr(C)]
 #[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
@@ -120,6 +135,9 @@ pub struct SetCollectionSizeArgs {
 }
 
 /// Instructions supported by the Metadata program.
+///
+/// Deployed clients identify an instruction by its Borsh variant index, so new variants must
+/// only ever be appended. Reordering or removing a variant is a breaking change.
 #[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
 #[derive(BorshSerialize, BorshDeserialize, Clone, ShankInstruction)]
 #[rustfmt::skip]
@@ -543,6 +561,13 @@ pub enum MetadataInstruction {
     BurnEditionNft,
 
     /// Create an escrow account to hold tokens.
+    /// With an initial deposit, the attribute mint, attribute source, escrow attribute account,
+    /// token program, associated token program, rent and escrow allowlist accounts follow the
+    /// authority.
+    /// With collection verification, the collection mint follows them.
+    /// While `ESCROW_CREATION_FEE` is set, the escrow treasury follows the system program.
+    /// With a memo, the SPL Memo program follows them. The members of a multisig escrow
+    /// come last.
     #[account(0, writable, name="escrow", desc="Escrow account")]
     #[account(1, name="metadata", desc="Metadata account")]
     #[account(2, name="mint", desc="Mint account")]
@@ -550,10 +575,14 @@ pub enum MetadataInstruction {
     #[account(4, name="edition", desc="Edition account")]
     #[account(5, writable, signer, name="payer", desc="Wallet paying for the transaction and new account")]
     #[account(6, name="system_program", desc="System program")]
-    #[account(7, optional, signer, name="authority", desc="Authority/creator of the escrow account")]
-    CreateEscrowAccount,
+    #[account(7, optional, signer, name="authority", desc="Authority/creator of the escrow account, passed when has_authority is set")]
+    CreateEscrowAccount(CreateEscrowAccountArgs),
 
     /// Close the escrow account.
+    /// If the escrow recorded a rent recipient, it must follow the system program, and
+    /// otherwise so must the recorded funder. Escrows with neither may pass the escrow
+    /// treasury there to receive the rent instead of the payer.
+    /// Any token accounts owned by the escrow must be appended after that and be empty.
     #[account(0, writable, name="escrow", desc="Escrow account")]
     #[account(1, name="metadata", desc="Metadata account")]
     #[account(2, name="mint", desc="Mint account")]
@@ -561,9 +590,11 @@ pub enum MetadataInstruction {
     #[account(4, name="edition", desc="Edition account")]
     #[account(5, writable, signer, name="payer", desc="Wallet paying for the transaction and new account")]
     #[account(6, name="system_program", desc="System program")]
+    #[account(7, optional, writable, name="rent_recipient", desc="Account receiving the escrow rent: the recorded rent recipient or funder, or the escrow treasury")]
     CloseEscrowAccount,
 
     /// Transfer the token out of Escrow.
+    /// Instructions encoded without `has_authority` may pass the authority and no metadata.
     #[account(0, name="escrow", desc="Escrow account")]
     #[account(1, writable, signer, name="payer", desc="Wallet paying for the transaction and new account")]
     #[account(2, name="attribute_mint", desc="Mint account for the new attribute")]
@@ -575,8 +606,174 @@ pub enum MetadataInstruction {
     #[account(8, name="ata_program", desc="Associated Token program")]
     #[account(9, name="token_program", desc="Token program")]
     #[account(10, name="rent", desc="Rent info")]
-    #[account(11, optional, signer, name="authority", desc="Authority/creator of the escrow account")]
+    #[account(11, optional, signer, name="authority", desc="Authority/creator of the escrow account, passed when has_authority is set")]
+    #[account(12, name="metadata", desc="Metadata account of the token the escrow is attached to")]
     TransferOutOfEscrow(TransferOutOfEscrowArgs),
+
+    /// Create a batch of creator owned escrow accounts sharing a payer and authority.
+    /// Each escrow is followed by its escrow, metadata, mint, token account and edition accounts.
+    /// While `ESCROW_CREATION_FEE` is set, the escrow treasury follows the authority and collects
+    /// the fee for every escrow in the batch.
+    #[account(0, writable, signer, name="payer", desc="Wallet paying for the transaction and new accounts")]
+    #[account(1, name="system_program", desc="System program")]
+    #[account(2, signer, name="authority", desc="Creator authority of the escrow accounts")]
+    CreateEscrowAccountsBatch,
+
+    /// Hand a creator owned escrow over to the current holder of the token it is attached to.
+    /// The rent recipient or funder recorded on the escrow, if any, follows the token program,
+    /// then pairs of (source, destination) token accounts, moving every token held by the
+    /// current escrow into the new token owner escrow.
+    #[account(0, writable, name="escrow", desc="Current creator owned escrow account")]
+    #[account(1, writable, name="new_escrow", desc="New token owner escrow account")]
+    #[account(2, name="metadata", desc="Metadata account")]
+    #[account(3, name="mint", desc="Mint account")]
+    #[account(4, name="token_account", desc="Token account of the new authority holding the token")]
+    #[account(5, name="edition", desc="Edition account")]
+    #[account(6, writable, signer, name="payer", desc="Wallet paying for the transaction and new account")]
+    #[account(7, signer, name="authority", desc="Current creator authority of the escrow account")]
+    #[account(8, name="new_authority", desc="Holder of the token taking over the escrow")]
+    #[account(9, name="system_program", desc="System program")]
+    #[account(10, name="token_program", desc="Token program")]
+    UpdateEscrowAuthority,
+
+    /// Assert that the signer currently controls the escrow, failing otherwise.
+    /// The token account is only read for token owner escrows.
+    #[account(0, name="escrow", desc="Escrow account")]
+    #[account(1, name="mint", desc="Mint account the escrow is attached to")]
+    #[account(2, name="token_account", desc="Token account of the signer holding the token")]
+    #[account(3, signer, name="authority", desc="Authority to verify against the escrow")]
+    VerifyEscrowAuthority,
+
+    /// Upgrade an escrow account to the current layout version, growing it if needed.
+    /// Passing the base token mint and master edition after the system program also
+    /// records the token standard of escrows created without one.
+    #[account(0, writable, name="escrow", desc="Escrow account")]
+    #[account(1, writable, signer, name="payer", desc="Wallet paying for any additional rent")]
+    #[account(2, name="system_program", desc="System program")]
+    MigrateEscrow,
+
+    /// Log the balances of the token accounts owned by an escrow, meant for simulation.
+    /// Token accounts owned by the escrow follow the escrow account.
+    #[account(0, name="escrow", desc="Escrow account")]
+    ListEscrowContents,
+
+    /// Grow a metadata account created at an older, smaller size up to the current maximum size.
+    #[account(0, writable, name="metadata", desc="Metadata account")]
+    #[account(1, signer, name="update_authority", desc="Metadata update authority")]
+    #[account(2, writable, signer, name="payer", desc="Wallet paying for the additional rent")]
+    #[account(3, name="system_program", desc="System program")]
+    ResizeMetadata,
+
+    /// Update only the provided name, symbol, uri and seller fee of a metadata account.
+    #[account(0, writable, name="metadata", desc="Metadata account")]
+    #[account(1, signer, name="update_authority", desc="Update authority key")]
+    UpdateMetadataFields(UpdateMetadataFieldsArgs),
+
+    /// Burn a master edition NFT and close its empty escrow in one instruction.
+    /// Escrow owned token accounts follow the token program and must be empty, then the
+    /// recorded rent recipient or funder if any, then the collection metadata if the NFT is in a verified collection.
+    #[account(0, writable, name="escrow", desc="Escrow account")]
+    #[account(1, writable, name="metadata", desc="Metadata (pda of ['metadata', program id, mint id])")]
+    #[account(2, writable, signer, name="owner", desc="NFT owner")]
+    #[account(3, writable, name="mint", desc="Mint of the NFT")]
+    #[account(4, writable, name="token_account", desc="Token account to close")]
+    #[account(5, writable, name="master_edition_account", desc="MasterEdition2 of the NFT")]
+    #[account(6, name="spl_token_program", desc="SPL Token Program")]
+    BurnNftAndCloseEscrow(BurnNftAndCloseEscrowArgs),
+
+    /// Transfer a token from the escrow authority into the escrow's associated token account.
+    /// Once the update authority of the base token has set its escrow allowlist, the attribute
+    /// mint must be on it. The members of a multisig escrow come last.
+    #[account(0, name="escrow", desc="Escrow account")]
+    #[account(1, name="metadata", desc="Metadata account of the token the escrow is attached to")]
+    #[account(2, writable, signer, name="payer", desc="Wallet paying for the transaction and new account")]
+    #[account(3, name="attribute_mint", desc="Mint account for the new attribute")]
+    #[account(4, writable, name="attribute_src", desc="Token account source for the new attribute")]
+    #[account(5, writable, name="attribute_dst", desc="Associated token account of the escrow, destination for the new attribute")]
+    #[account(6, name="escrow_mint", desc="Mint account that the escrow is attached")]
+    #[account(7, name="escrow_account", desc="Token account that holds the token the escrow is attached to")]
+    #[account(8, name="system_program", desc="System program")]
+    #[account(9, name="ata_program", desc="Associated Token program")]
+    #[account(10, name="token_program", desc="Token program")]
+    #[account(11, name="rent", desc="Rent info")]
+    #[account(12, name="allowlist", desc="Escrow allowlist of the base token's update authority")]
+    #[account(13, optional, signer, name="authority", desc="Authority/creator of the escrow account")]
+    TransferIntoEscrow(TransferIntoEscrowArgs),
+
+    /// Log which of the candidate creators have an escrow on the mint, meant for simulation.
+    /// Each candidate creator is followed by its derived escrow account.
+    #[account(0, name="mint", desc="Mint account the escrows are attached to")]
+    FindCreatorEscrows,
+
+    /// Lock or unlock an escrow, rejecting transfers into or out of it while locked.
+    #[account(0, writable, name="escrow", desc="Escrow account")]
+    #[account(1, name="mint", desc="Mint account the escrow is attached to")]
+    #[account(2, name="token_account", desc="Token account holding the token the escrow is attached to")]
+    #[account(3, signer, name="authority", desc="Authority/creator of the escrow account")]
+    #[account(4, writable, signer, name="payer", desc="Wallet paying for any additional rent")]
+    #[account(5, name="system_program", desc="System program")]
+    SetEscrowLock(SetEscrowLockArgs),
+
+    /// Close an escrow whose base token was burned without it, returning its rent.
+    /// The rent recipient or funder recorded on the escrow, if any, follows the authority, then any
+    /// token accounts owned by the escrow, which must be empty.
+    #[account(0, writable, name="escrow", desc="Escrow account")]
+    #[account(1, name="metadata", desc="Metadata account of the burned token, for a token owner escrow")]
+    #[account(2, name="mint", desc="Mint account the escrow is attached to")]
+    #[account(3, writable, signer, name="authority", desc="Creator of the escrow, or update authority of the metadata for a token owner escrow")]
+    ReclaimOrphanedEscrow,
+
+    /// Create an escrow account like `CreateEscrowAccount`, failing unless the base token has
+    /// the token standard the caller expects. Takes the same accounts.
+    #[account(0, writable, name="escrow", desc="Escrow account")]
+    #[account(1, name="metadata", desc="Metadata account")]
+    #[account(2, name="mint", desc="Mint account")]
+    #[account(3, name="token_account", desc="Token account of the token")]
+    #[account(4, name="edition", desc="Edition account")]
+    #[account(5, writable, signer, name="payer", desc="Wallet paying for the transaction and new account")]
+    #[account(6, name="system_program", desc="System program")]
+    #[account(7, optional, signer, name="authority", desc="Authority/creator of the escrow account")]
+    CreateEscrowAccountV2(CreateEscrowAccountV2Args),
+
+    /// Create or replace the allowlist of attribute mints that may be deposited into the
+    /// escrows of NFTs with the signer as update authority.
+    #[account(0, writable, name="allowlist", desc="Escrow allowlist account (pda of ['metadata', program id, authority, 'escrow_allowlist'])")]
+    #[account(1, signer, name="authority", desc="Update authority owning the allowlist")]
+    #[account(2, writable, signer, name="payer", desc="Wallet paying for the allowlist account")]
+    #[account(3, name="system_program", desc="System program")]
+    SetEscrowAllowlist(SetEscrowAllowlistArgs),
+
+    /// Set or clear the operator allowed to transfer tokens into and out of an escrow.
+    #[account(0, writable, name="escrow", desc="Escrow account")]
+    #[account(1, name="mint", desc="Mint account the escrow is attached to")]
+    #[account(2, name="token_account", desc="Token account holding the token the escrow is attached to")]
+    #[account(3, signer, name="authority", desc="Authority/creator of the escrow account")]
+    #[account(4, writable, signer, name="payer", desc="Wallet paying for any additional rent")]
+    #[account(5, name="system_program", desc="System program")]
+    SetEscrowOperator(SetEscrowOperatorArgs),
+
+    /// Verify that an NFT belongs to a sized or unsized collection, incrementing the size of
+    /// sized collections.
+    #[account(0, writable, name="metadata", desc="Metadata account")]
+    #[account(1, signer, name="collection_authority", desc="Collection Update authority")]
+    #[account(2, signer, writable, name="payer", desc="payer")]
+    #[account(3, name="collection_mint", desc="Mint of the Collection")]
+    #[account(4, writable, name="collection", desc="Metadata Account of the Collection")]
+    #[account(5, name="collection_master_edition_account", desc="MasterEdition2 Account of the Collection Token")]
+    #[account(6, optional, name="collection_authority_record", desc="Collection Authority Record PDA")]
+    VerifyCollectionItem,
+
+    /// Log the canonical address and bump of an escrow, meant to be simulated.
+    #[account(0, name="mint", desc="Mint account the escrow is attached to")]
+    LogEscrowBump(LogEscrowBumpArgs),
+
+    /// Withdraw lamports from the escrow treasury, signed by the upgrade authority of the program.
+    #[account(0, writable, name="treasury", desc="Escrow treasury account")]
+    #[account(1, signer, name="authority", desc="Upgrade authority of the program")]
+    #[account(2, name="program_data", desc="Program data account of the program")]
+    #[account(3, writable, name="destination", desc="Account receiving the lamports")]
+    #[account(4, name="system_program", desc="System program")]
+    WithdrawEscrowTreasury(WithdrawEscrowTreasuryArgs),
 }
 
 /// Creates an CreateMetadataAccounts instruction
@@ -1569,6 +1766,52 @@ pub fn verify_sized_collection_item(
     }
 }
 
+/// # Verify Collection Item
+///
+/// Verify an NFT in a sized or unsized collection, incrementing the size of sized collections.
+///
+/// ### Accounts:
+///
+///   0. `[writable]` Metadata account
+///   1. `[signer]` Collection Update authority
+///   2. `[signer]` payer
+///   3. `[]` Mint of the Collection
+///   4. `[writable]` Metadata Account of the Collection
+///   5. `[]` MasterEdition2 Account of the Collection Token
+///   6. Optional `[]` Collection Authority Record PDA
+#[allow(clippy::too_many_arguments)]
+pub fn verify_collection_item(
+    program_id: Pubkey,
+    metadata: Pubkey,
+    collection_authority: Pubkey,
+    payer: Pubkey,
+    collection_mint: Pubkey,
+    collection: Pubkey,
+    collection_master_edition_account: Pubkey,
+    collection_authority_record: Option<Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(metadata, false),
+        AccountMeta::new_readonly(collection_authority, true),
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(collection_mint, false),
+        AccountMeta::new(collection, false),
+        AccountMeta::new_readonly(collection_master_edition_account, false),
+    ];
+
+    if let Some(record) = collection_authority_record {
+        accounts.push(AccountMeta::new_readonly(record, false));
+    }
+
+    Instruction {
+        program_id,
+        accounts,
+        data: MetadataInstruction::VerifyCollectionItem
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
 /// # Unverify Collection V2 -- Supports v1.3 Collection Details
 ///
 /// If a MetadataAccount Has a Collection allow an Authority of the Collection to unverify an NFT in a Collection
@@ -1816,3 +2059,40 @@ pub fn set_token_standard(
         data,
     }
 }
+
+pub fn resize_metadata(
+    program_id: Pubkey,
+    metadata_account: Pubkey,
+    update_authority: Pubkey,
+    payer: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(metadata_account, false),
+            AccountMeta::new_readonly(update_authority, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(solana_program::system_program::id(), false),
+        ],
+        data: MetadataInstruction::ResizeMetadata.try_to_vec().unwrap(),
+    }
+}
+
+/// update metadata fields instruction, only the fields passed as `Some` are changed
+pub fn update_metadata_fields(
+    program_id: Pubkey,
+    metadata_account: Pubkey,
+    update_authority: Pubkey,
+    args: UpdateMetadataFieldsArgs,
+) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(metadata_account, false),
+            AccountMeta::new_readonly(update_authority, true),
+        ],
+        data: MetadataInstruction::UpdateMetadataFields(args)
+            .try_to_vec()
+            .unwrap(),
+    }
+}
@@ -0,0 +1,270 @@
+#![cfg(test)]
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{
+    deprecated_instruction::{MintPrintingTokensViaTokenArgs, SetReservationListArgs},
+    escrow::{
+        BurnNftAndCloseEscrowArgs, CreateEscrowAccountArgs, CreateEscrowAccountV2Args,
+        LogEscrowBumpArgs, SetEscrowAllowlistArgs, SetEscrowLockArgs, SetEscrowOperatorArgs,
+        TransferIntoEscrowArgs, TransferOutOfEscrowArgs, WithdrawEscrowTreasuryArgs,
+    },
+    instruction::{
+        ApproveUseAuthorityArgs, CreateMasterEditionArgs, CreateMetadataAccountArgs,
+        CreateMetadataAccountArgsV2, CreateMetadataAccountArgsV3, MetadataInstruction,
+        MintNewEditionFromMasterEditionViaTokenArgs, SetCollectionSizeArgs,
+        UpdateMetadataAccountArgs, UpdateMetadataAccountArgsV2, UpdateMetadataFieldsArgs,
+        UtilizeArgs,
+    },
+    state::{Data, DataV2, EscrowAuthority, TokenStandard},
+};
+
+fn data() -> Data {
+    Data {
+        name: String::new(),
+        symbol: String::new(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+    }
+}
+
+fn data_v2() -> DataV2 {
+    DataV2 {
+        name: String::new(),
+        symbol: String::new(),
+        uri: String::new(),
+        seller_fee_basis_points: 0,
+        creators: None,
+        collection: None,
+        uses: None,
+    }
+}
+
+#[test]
+fn instruction_discriminants_are_stable() {
+    // Deployed clients serialize instructions by variant index. Reordering or removing a
+    // variant of `MetadataInstruction` is a breaking change, so new variants are only ever
+    // appended and every existing byte here must stay the same.
+    let master_edition = CreateMasterEditionArgs { max_supply: None };
+    let new_edition = MintNewEditionFromMasterEditionViaTokenArgs { edition: 1 };
+    let printing_tokens = MintPrintingTokensViaTokenArgs { supply: 1 };
+    let collection_size = SetCollectionSizeArgs { size: 1 };
+
+    let instructions = vec![
+        (
+            MetadataInstruction::CreateMetadataAccount(CreateMetadataAccountArgs {
+                data: data(),
+                is_mutable: true,
+            }),
+            0,
+        ),
+        (
+            MetadataInstruction::UpdateMetadataAccount(UpdateMetadataAccountArgs {
+                data: None,
+                update_authority: None,
+                primary_sale_happened: None,
+            }),
+            1,
+        ),
+        (
+            MetadataInstruction::DeprecatedCreateMasterEdition(master_edition.clone()),
+            2,
+        ),
+        (
+            MetadataInstruction::DeprecatedMintNewEditionFromMasterEditionViaPrintingToken,
+            3,
+        ),
+        (MetadataInstruction::UpdatePrimarySaleHappenedViaToken, 4),
+        (
+            MetadataInstruction::DeprecatedSetReservationList(SetReservationListArgs {
+                reservations: vec![],
+                total_reservation_spots: None,
+                offset: 0,
+                total_spot_offset: 0,
+            }),
+            5,
+        ),
+        (MetadataInstruction::DeprecatedCreateReservationList, 6),
+        (MetadataInstruction::SignMetadata, 7),
+        (
+            MetadataInstruction::DeprecatedMintPrintingTokensViaToken(printing_tokens.clone()),
+            8,
+        ),
+        (
+            MetadataInstruction::DeprecatedMintPrintingTokens(printing_tokens),
+            9,
+        ),
+        (
+            MetadataInstruction::CreateMasterEdition(master_edition.clone()),
+            10,
+        ),
+        (
+            MetadataInstruction::MintNewEditionFromMasterEditionViaToken(new_edition.clone()),
+            11,
+        ),
+        (MetadataInstruction::ConvertMasterEditionV1ToV2, 12),
+        (
+            MetadataInstruction::MintNewEditionFromMasterEditionViaVaultProxy(new_edition),
+            13,
+        ),
+        (MetadataInstruction::PuffMetadata, 14),
+        (
+            MetadataInstruction::UpdateMetadataAccountV2(UpdateMetadataAccountArgsV2 {
+                data: None,
+                update_authority: None,
+                primary_sale_happened: None,
+                is_mutable: None,
+            }),
+            15,
+        ),
+        (
+            MetadataInstruction::CreateMetadataAccountV2(CreateMetadataAccountArgsV2 {
+                data: data_v2(),
+                is_mutable: true,
+            }),
+            16,
+        ),
+        (
+            MetadataInstruction::CreateMasterEditionV3(master_edition),
+            17,
+        ),
+        (MetadataInstruction::VerifyCollection, 18),
+        (
+            MetadataInstruction::Utilize(UtilizeArgs { number_of_uses: 1 }),
+            19,
+        ),
+        (
+            MetadataInstruction::ApproveUseAuthority(ApproveUseAuthorityArgs { number_of_uses: 1 }),
+            20,
+        ),
+        (MetadataInstruction::RevokeUseAuthority, 21),
+        (MetadataInstruction::UnverifyCollection, 22),
+        (MetadataInstruction::ApproveCollectionAuthority, 23),
+        (MetadataInstruction::RevokeCollectionAuthority, 24),
+        (MetadataInstruction::SetAndVerifyCollection, 25),
+        (MetadataInstruction::FreezeDelegatedAccount, 26),
+        (MetadataInstruction::ThawDelegatedAccount, 27),
+        (MetadataInstruction::RemoveCreatorVerification, 28),
+        (MetadataInstruction::BurnNft, 29),
+        (MetadataInstruction::VerifySizedCollectionItem, 30),
+        (MetadataInstruction::UnverifySizedCollectionItem, 31),
+        (MetadataInstruction::SetAndVerifySizedCollectionItem, 32),
+        (
+            MetadataInstruction::CreateMetadataAccountV3(CreateMetadataAccountArgsV3 {
+                data: data_v2(),
+                is_mutable: true,
+                collection_details: None,
+            }),
+            33,
+        ),
+        (
+            MetadataInstruction::SetCollectionSize(collection_size.clone()),
+            34,
+        ),
+        (MetadataInstruction::SetTokenStandard, 35),
+        (
+            MetadataInstruction::BubblegumSetCollectionSize(collection_size),
+            36,
+        ),
+        (MetadataInstruction::BurnEditionNft, 37),
+        (
+            MetadataInstruction::CreateEscrowAccount(CreateEscrowAccountArgs::default()),
+            38,
+        ),
+        (MetadataInstruction::CloseEscrowAccount, 39),
+        (
+            MetadataInstruction::TransferOutOfEscrow(TransferOutOfEscrowArgs {
+                amount: 1,
+                has_authority: Some(false),
+            }),
+            40,
+        ),
+        (MetadataInstruction::CreateEscrowAccountsBatch, 41),
+        (MetadataInstruction::UpdateEscrowAuthority, 42),
+        (MetadataInstruction::VerifyEscrowAuthority, 43),
+        (MetadataInstruction::MigrateEscrow, 44),
+        (MetadataInstruction::ListEscrowContents, 45),
+        (MetadataInstruction::ResizeMetadata, 46),
+        (
+            MetadataInstruction::UpdateMetadataFields(UpdateMetadataFieldsArgs::default()),
+            47,
+        ),
+        (
+            MetadataInstruction::BurnNftAndCloseEscrow(BurnNftAndCloseEscrowArgs {
+                escrow_token_accounts: 0,
+            }),
+            48,
+        ),
+        (
+            MetadataInstruction::TransferIntoEscrow(TransferIntoEscrowArgs {
+                amount: 1,
+                has_authority: false,
+            }),
+            49,
+        ),
+        (MetadataInstruction::FindCreatorEscrows, 50),
+        (
+            MetadataInstruction::SetEscrowLock(SetEscrowLockArgs { locked: true }),
+            51,
+        ),
+        (MetadataInstruction::ReclaimOrphanedEscrow, 52),
+        (
+            MetadataInstruction::CreateEscrowAccountV2(CreateEscrowAccountV2Args {
+                args: CreateEscrowAccountArgs::default(),
+                expected_standard: TokenStandard::NonFungible,
+            }),
+            53,
+        ),
+        (
+            MetadataInstruction::SetEscrowAllowlist(SetEscrowAllowlistArgs { mints: vec![] }),
+            54,
+        ),
+        (
+            MetadataInstruction::SetEscrowOperator(SetEscrowOperatorArgs { operator: None }),
+            55,
+        ),
+        (MetadataInstruction::VerifyCollectionItem, 56),
+        (
+            MetadataInstruction::LogEscrowBump(LogEscrowBumpArgs {
+                authority: EscrowAuthority::TokenOwner,
+            }),
+            57,
+        ),
+        (
+            MetadataInstruction::WithdrawEscrowTreasury(WithdrawEscrowTreasuryArgs { amount: 1 }),
+            58,
+        ),
+    ];
+
+    for (instruction, discriminant) in instructions {
+        assert_eq!(instruction.try_to_vec().unwrap()[0], discriminant);
+    }
+}
+
+#[test]
+fn transfer_out_of_escrow_without_authority_flag_still_decodes() {
+    let decode = |data: &[u8]| match MetadataInstruction::try_from_slice(data).unwrap() {
+        MetadataInstruction::TransferOutOfEscrow(args) => args,
+        _ => panic!("expected TransferOutOfEscrow"),
+    };
+
+    // Encoded before `has_authority` existed: the discriminant and the amount.
+    let mut data = vec![40];
+    data.extend_from_slice(&7u64.to_le_bytes());
+    assert_eq!(
+        decode(&data),
+        TransferOutOfEscrowArgs {
+            amount: 7,
+            has_authority: None,
+        }
+    );
+
+    let args = TransferOutOfEscrowArgs {
+        amount: 7,
+        has_authority: Some(true),
+    };
+    let data = MetadataInstruction::TransferOutOfEscrow(args.clone())
+        .try_to_vec()
+        .unwrap();
+    assert_eq!(decode(&data), args);
+}
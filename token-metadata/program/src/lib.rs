@@ -6,9 +6,12 @@ pub mod deprecated_processor;
 mod deser;
 pub mod entrypoint;
 pub mod error;
+pub mod error_test;
 pub mod escrow;
 pub mod instruction;
+pub mod instruction_test;
 pub mod pda;
+pub mod pda_test;
 pub mod processor;
 pub mod state;
 pub mod state_test;
@@ -33,6 +33,36 @@ pub fn find_metadata_account(mint: &Pubkey) -> (Pubkey, u8) {
     )
 }
 
+/// The standard accounts derived from an NFT mint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NftAccounts {
+    pub metadata: Pubkey,
+    pub master_edition: Pubkey,
+}
+
+/// Derive the standard accounts of `mint` under `program_id` in one call, so clients and
+/// builders don't each spell out the seeds.
+pub fn derive_nft_accounts(program_id: &Pubkey, mint: &Pubkey) -> NftAccounts {
+    let (metadata, _) = Pubkey::find_program_address(
+        &[PREFIX.as_bytes(), program_id.as_ref(), mint.as_ref()],
+        program_id,
+    );
+    let (master_edition, _) = Pubkey::find_program_address(
+        &[
+            PREFIX.as_bytes(),
+            program_id.as_ref(),
+            mint.as_ref(),
+            EDITION.as_bytes(),
+        ],
+        program_id,
+    );
+
+    NftAccounts {
+        metadata,
+        master_edition,
+    }
+}
+
 pub fn find_use_authority_account(mint: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
         &[
@@ -0,0 +1,46 @@
+#![cfg(test)]
+use std::str::FromStr;
+
+use solana_program::pubkey::Pubkey;
+
+use crate::pda::{derive_nft_accounts, find_master_edition_account, find_metadata_account};
+
+#[test]
+fn derive_nft_accounts_matches_fixtures() {
+    let fixtures = [
+        (
+            // Wrapped SOL.
+            "So11111111111111111111111111111111111111112",
+            "6dM4TqWyWJsbx7obrdLcviBkTafD5E8av61zfU6jq57X",
+            "7r1W5yu5i7ev1wPNGsNuRLcdKW1sCy2x4rwyQkdi9ew2",
+        ),
+        (
+            // USDC.
+            "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "5x38Kp4hvdomTCnCrAny4UtMUt5rQBdB6px2K1Ui45Wq",
+            "A7FGB2kzjpDPRLMeqRLgW9XZ3JQ2RYRL4w5kUZv64ZB",
+        ),
+    ];
+
+    for (mint, metadata, master_edition) in fixtures {
+        let accounts = derive_nft_accounts(&crate::id(), &Pubkey::from_str(mint).unwrap());
+
+        assert_eq!(accounts.metadata, Pubkey::from_str(metadata).unwrap());
+        assert_eq!(
+            accounts.master_edition,
+            Pubkey::from_str(master_edition).unwrap()
+        );
+    }
+}
+
+#[test]
+fn derive_nft_accounts_matches_finders() {
+    let mint = Pubkey::new_unique();
+    let accounts = derive_nft_accounts(&crate::id(), &mint);
+
+    assert_eq!(accounts.metadata, find_metadata_account(&mint).0);
+    assert_eq!(
+        accounts.master_edition,
+        find_master_edition_account(&mint).0
+    );
+}
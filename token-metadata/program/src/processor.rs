@@ -12,9 +12,16 @@ use crate::{
     deser::clean_write_metadata,
     error::MetadataError,
     escrow::{
-        process_close_escrow_account, process_create_escrow_account, process_transfer_out_of_escrow,
+        process_burn_nft_and_close_escrow, process_close_escrow_account,
+        process_create_escrow_account, process_create_escrow_account_v2,
+        process_create_escrow_accounts_batch, process_find_creator_escrows,
+        process_list_escrow_contents, process_log_escrow_bump, process_migrate_escrow,
+        process_reclaim_orphaned_escrow, process_set_escrow_allowlist, process_set_escrow_lock,
+        process_set_escrow_operator, process_transfer_into_escrow, process_transfer_out_of_escrow,
+        process_update_escrow_authority, process_verify_escrow_authority,
+        process_withdraw_escrow_treasury,
     },
-    instruction::{MetadataInstruction, SetCollectionSizeArgs},
+    instruction::{MetadataInstruction, SetCollectionSizeArgs, UpdateMetadataFieldsArgs},
     solana_program::program_memory::sol_memset,
     state::{
         Collection, CollectionAuthorityRecord, CollectionDetails, DataV2, Edition, EditionMarker,
@@ -25,7 +32,7 @@ use crate::{
     },
     utils::{
         assert_currently_holding, assert_data_valid, assert_delegated_tokens, assert_derivation,
-        assert_freeze_authority_matches_mint, assert_initialized,
+        assert_derivation_bump_seed, assert_freeze_authority_matches_mint, assert_initialized,
         assert_mint_authority_matches_mint, assert_owned_by, assert_signer,
         assert_token_program_matches_package, assert_update_authority_is_correct,
         assert_verified_member_of_collection, check_token_standard, create_or_allocate_account_raw,
@@ -33,7 +40,8 @@ use crate::{
         get_owner_from_token_account, increment_collection_size, is_master_edition,
         is_print_edition, process_create_metadata_accounts_logic,
         process_mint_new_edition_from_master_edition_via_token_logic, puff_out_data_fields,
-        spl_token_burn, spl_token_close, transfer_mint_authority, CreateMetadataAccountsLogicArgs,
+        resize_or_reallocate_account_raw, set_creator_verified, spl_token_burn, spl_token_close,
+        transfer_mint_authority, CreateMetadataAccountsLogicArgs,
         MintNewEditionFromMasterEditionViaTokenLogicArgs, TokenBurnParams, TokenCloseParams,
         BUBBLEGUM_ACTIVATED, BUBBLEGUM_PROGRAM_ADDRESS,
     },
@@ -250,9 +258,9 @@ pub fn process_instruction<'a>(
             msg!("Instruction: Bubblegum Program Set Collection Size");
             bubblegum_set_collection_size(program_id, accounts, args)
         }
-        MetadataInstruction::CreateEscrowAccount => {
+        MetadataInstruction::CreateEscrowAccount(args) => {
             msg!("Instruction: Create Escrow Account");
-            process_create_escrow_account(program_id, accounts)
+            process_create_escrow_account(program_id, accounts, args)
         }
         MetadataInstruction::CloseEscrowAccount => {
             msg!("Instruction: Close Escrow Account");
@@ -262,6 +270,78 @@ pub fn process_instruction<'a>(
             msg!("Instruction: Transfer Out Of Escrow");
             process_transfer_out_of_escrow(program_id, accounts, args)
         }
+        MetadataInstruction::CreateEscrowAccountsBatch => {
+            msg!("Instruction: Create Escrow Accounts Batch");
+            process_create_escrow_accounts_batch(program_id, accounts)
+        }
+        MetadataInstruction::UpdateEscrowAuthority => {
+            msg!("Instruction: Update Escrow Authority");
+            process_update_escrow_authority(program_id, accounts)
+        }
+        MetadataInstruction::VerifyEscrowAuthority => {
+            msg!("Instruction: Verify Escrow Authority");
+            process_verify_escrow_authority(program_id, accounts)
+        }
+        MetadataInstruction::MigrateEscrow => {
+            msg!("Instruction: Migrate Escrow");
+            process_migrate_escrow(program_id, accounts)
+        }
+        MetadataInstruction::ListEscrowContents => {
+            msg!("Instruction: List Escrow Contents");
+            process_list_escrow_contents(program_id, accounts)
+        }
+        MetadataInstruction::ResizeMetadata => {
+            msg!("Instruction: Resize Metadata");
+            process_resize_metadata(program_id, accounts)
+        }
+        MetadataInstruction::UpdateMetadataFields(args) => {
+            msg!("Instruction: Update Metadata Fields");
+            process_update_metadata_fields(program_id, accounts, args)
+        }
+        MetadataInstruction::BurnNftAndCloseEscrow(args) => {
+            msg!("Instruction: Burn NFT And Close Escrow");
+            process_burn_nft_and_close_escrow(program_id, accounts, args)
+        }
+        MetadataInstruction::TransferIntoEscrow(args) => {
+            msg!("Instruction: Transfer Into Escrow");
+            process_transfer_into_escrow(program_id, accounts, args)
+        }
+        MetadataInstruction::FindCreatorEscrows => {
+            msg!("Instruction: Find Creator Escrows");
+            process_find_creator_escrows(program_id, accounts)
+        }
+        MetadataInstruction::SetEscrowLock(args) => {
+            msg!("Instruction: Set Escrow Lock");
+            process_set_escrow_lock(program_id, accounts, args)
+        }
+        MetadataInstruction::ReclaimOrphanedEscrow => {
+            msg!("Instruction: Reclaim Orphaned Escrow");
+            process_reclaim_orphaned_escrow(program_id, accounts)
+        }
+        MetadataInstruction::CreateEscrowAccountV2(args) => {
+            msg!("Instruction: Create Escrow Account V2");
+            process_create_escrow_account_v2(program_id, accounts, args)
+        }
+        MetadataInstruction::SetEscrowAllowlist(args) => {
+            msg!("Instruction: Set Escrow Allowlist");
+            process_set_escrow_allowlist(program_id, accounts, args)
+        }
+        MetadataInstruction::SetEscrowOperator(args) => {
+            msg!("Instruction: Set Escrow Operator");
+            process_set_escrow_operator(program_id, accounts, args)
+        }
+        MetadataInstruction::VerifyCollectionItem => {
+            msg!("Instruction: Verify Collection Item");
+            process_verify_collection(program_id, accounts)
+        }
+        MetadataInstruction::LogEscrowBump(args) => {
+            msg!("Instruction: Log Escrow Bump");
+            process_log_escrow_bump(program_id, accounts, args)
+        }
+        MetadataInstruction::WithdrawEscrowTreasury(args) => {
+            msg!("Instruction: Withdraw Escrow Treasury");
+            process_withdraw_escrow_treasury(program_id, accounts, args)
+        }
     }
 }
 
@@ -459,21 +539,7 @@ pub fn process_sign_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> P
 
     let mut metadata = Metadata::from_account_info(metadata_info)?;
 
-    if let Some(creators) = &mut metadata.data.creators {
-        let mut found = false;
-        for creator in creators {
-            if creator.address == *creator_info.key {
-                creator.verified = true;
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return Err(MetadataError::CreatorNotFound.into());
-        }
-    } else {
-        return Err(MetadataError::NoCreatorsPresentOnMetadata.into());
-    }
+    set_creator_verified(&mut metadata.data.creators, creator_info.key, true)?;
     metadata.serialize(&mut *metadata_info.try_borrow_mut_data()?)?;
 
     Ok(())
@@ -493,26 +559,60 @@ pub fn process_remove_creator_verification(
 
     let mut metadata = Metadata::from_account_info(metadata_info)?;
 
-    if let Some(creators) = &mut metadata.data.creators {
-        let mut found = false;
-        for creator in creators {
-            if creator.address == *creator_info.key {
-                creator.verified = false;
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            return Err(MetadataError::CreatorNotFound.into());
-        }
-    } else {
-        return Err(MetadataError::NoCreatorsPresentOnMetadata.into());
-    }
+    set_creator_verified(&mut metadata.data.creators, creator_info.key, false)?;
     metadata.serialize(&mut *metadata_info.try_borrow_mut_data()?)?;
 
     Ok(())
 }
 
+pub fn process_update_metadata_fields(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    args: UpdateMetadataFieldsArgs,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let update_authority_info = next_account_info(account_info_iter)?;
+
+    assert_owned_by(metadata_account_info, program_id)?;
+    let mut metadata = Metadata::from_account_info(metadata_account_info)?;
+
+    assert_update_authority_is_correct(&metadata, update_authority_info)?;
+
+    if !metadata.is_mutable {
+        return Err(MetadataError::DataIsImmutable.into());
+    }
+
+    // Start from the current data so creators and any omitted field are preserved.
+    let mut data = metadata.data.clone();
+    if let Some(name) = args.name {
+        data.name = name;
+    }
+    if let Some(symbol) = args.symbol {
+        data.symbol = symbol;
+    }
+    if let Some(uri) = args.uri {
+        data.uri = uri;
+    }
+    if let Some(seller_fee_basis_points) = args.seller_fee_basis_points {
+        data.seller_fee_basis_points = seller_fee_basis_points;
+    }
+
+    assert_data_valid(
+        &data,
+        update_authority_info.key,
+        &metadata,
+        false,
+        update_authority_info.is_signer,
+    )?;
+    metadata.data = data;
+
+    puff_out_data_fields(&mut metadata);
+    clean_write_metadata(&mut metadata, metadata_account_info)?;
+    Ok(())
+}
+
 /// Create master edition
 pub fn process_create_master_edition(
     program_id: &Pubkey,
@@ -959,6 +1059,63 @@ pub fn verify_sized_collection_item(
     Ok(())
 }
 
+/// Verifies an item of either a sized or an unsized collection, so callers don't need to know
+/// which kind the collection is. The item is marked verified and, for sized collections, the
+/// collection size is incremented in the same instruction.
+pub fn process_verify_collection(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let metadata_info = next_account_info(account_info_iter)?;
+    let collection_authority_info = next_account_info(account_info_iter)?;
+    let payer_info = next_account_info(account_info_iter)?;
+    let collection_mint = next_account_info(account_info_iter)?;
+    let collection_info = next_account_info(account_info_iter)?;
+    let edition_account_info = next_account_info(account_info_iter)?;
+    let collection_authority_record = next_account_info(account_info_iter).ok();
+
+    assert_signer(collection_authority_info)?;
+    assert_signer(payer_info)?;
+
+    assert_owned_by(metadata_info, program_id)?;
+    assert_owned_by(collection_info, program_id)?;
+    assert_owned_by(collection_mint, &spl_token::id())?;
+
+    let mut metadata = Metadata::from_account_info(metadata_info)?;
+    let mut collection_metadata = Metadata::from_account_info(collection_info)?;
+
+    // Verifying twice would count the item twice in a sized collection.
+    if let Some(collection) = &metadata.collection {
+        if collection.verified {
+            return Err(MetadataError::AlreadyVerified.into());
+        }
+    }
+
+    // The edition isn't checked for program ownership first, so a collection without a
+    // master edition fails as not being one.
+    assert_collection_verify_is_valid(
+        &metadata.collection,
+        &collection_metadata,
+        collection_mint,
+        edition_account_info,
+    )?;
+
+    assert_has_collection_authority(
+        collection_authority_info,
+        &collection_metadata,
+        collection_mint.key,
+        collection_authority_record,
+    )?;
+
+    if collection_metadata.collection_details.is_some() {
+        increment_collection_size(&mut collection_metadata, collection_info)?;
+    }
+
+    if let Some(collection) = &mut metadata.collection {
+        collection.verified = true;
+        clean_write_metadata(&mut metadata, metadata_info)?;
+    }
+    Ok(())
+}
+
 pub fn unverify_collection(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let metadata_info = next_account_info(account_info_iter)?;
@@ -1391,13 +1548,13 @@ pub fn process_approve_collection_authority(
         COLLECTION_AUTHORITY.as_bytes(),
         new_collection_authority.key.as_ref(),
     ]);
-    let collection_authority_bump_seed = &[assert_derivation(
+    let collection_authority_bump_seed = assert_derivation_bump_seed(
         program_id,
         collection_authority_record,
         &collection_authority_path,
-    )?];
+    )?;
     let mut collection_authority_seeds = collection_authority_path.clone();
-    collection_authority_seeds.push(collection_authority_bump_seed);
+    collection_authority_seeds.push(&collection_authority_bump_seed);
     create_or_allocate_account_raw(
         *program_id,
         collection_authority_record,
@@ -1634,13 +1791,10 @@ pub fn process_freeze_delegated_account(
         mint_info.key.as_ref(),
         EDITION.as_bytes(),
     ]);
-    let edition_info_path_bump_seed = &[assert_derivation(
-        program_id,
-        edition_info,
-        &edition_info_path,
-    )?];
+    let edition_info_path_bump_seed =
+        assert_derivation_bump_seed(program_id, edition_info, &edition_info_path)?;
     let mut edition_info_seeds = edition_info_path.clone();
-    edition_info_seeds.push(edition_info_path_bump_seed);
+    edition_info_seeds.push(&edition_info_path_bump_seed);
     invoke_signed(
         &freeze_account(
             token_program_account_info.key,
@@ -1689,13 +1843,10 @@ pub fn process_thaw_delegated_account(
         mint_info.key.as_ref(),
         EDITION.as_bytes(),
     ]);
-    let edition_info_path_bump_seed = &[assert_derivation(
-        program_id,
-        edition_info,
-        &edition_info_path,
-    )?];
+    let edition_info_path_bump_seed =
+        assert_derivation_bump_seed(program_id, edition_info, &edition_info_path)?;
     let mut edition_info_seeds = edition_info_path.clone();
-    edition_info_seeds.push(edition_info_path_bump_seed);
+    edition_info_seeds.push(&edition_info_path_bump_seed);
     invoke_signed(
         &thaw_account(
             token_program_account_info.key,
@@ -2258,3 +2409,52 @@ pub fn set_token_standard(program_id: &Pubkey, accounts: &[AccountInfo]) -> Prog
     clean_write_metadata(&mut metadata, metadata_account_info)?;
     Ok(())
 }
+
+pub fn process_resize_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+
+    let metadata_account_info = next_account_info(account_info_iter)?;
+    let update_authority_account_info = next_account_info(account_info_iter)?;
+    let payer_account_info = next_account_info(account_info_iter)?;
+    let system_account_info = next_account_info(account_info_iter)?;
+
+    if *system_account_info.key != solana_program::system_program::id() {
+        return Err(MetadataError::InvalidSystemProgram.into());
+    }
+
+    assert_owned_by(metadata_account_info, program_id)?;
+    assert_signer(payer_account_info)?;
+
+    // Legacy sized accounts fail the size check in `from_account_info`, so only the key is checked.
+    let metadata = {
+        let data = metadata_account_info.data.borrow();
+        if data.is_empty() || data[0] != Key::MetadataV1 as u8 {
+            return Err(MetadataError::DataTypeMismatch.into());
+        }
+        Metadata::deserialize(&mut &data[..]).map_err(|_| MetadataError::DataTypeMismatch)?
+    };
+
+    // Update authority is a signer and matches update authority on metadata.
+    assert_update_authority_is_correct(&metadata, update_authority_account_info)?;
+
+    let current_len = metadata_account_info.data_len();
+    if current_len > MAX_METADATA_LEN {
+        return Err(MetadataError::InvalidMetadataSize.into());
+    }
+    if current_len == MAX_METADATA_LEN {
+        return Ok(());
+    }
+
+    resize_or_reallocate_account_raw(
+        metadata_account_info,
+        payer_account_info,
+        system_account_info,
+        MAX_METADATA_LEN,
+    )?;
+
+    // New bytes must read as unset optional fields.
+    let mut data = metadata_account_info.try_borrow_mut_data()?;
+    sol_memset(&mut data[current_len..], 0, MAX_METADATA_LEN - current_len);
+
+    Ok(())
+}
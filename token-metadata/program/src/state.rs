@@ -3,6 +3,7 @@ use std::io::ErrorKind;
 use crate::{
     deser::meta_deser_unchecked,
     error::MetadataError,
+    escrow::ESCROW_DEFAULT_TAG,
     utils::{assert_owned_by, try_from_slice_checked},
     ID,
 };
@@ -156,6 +157,8 @@ pub enum Key {
     CollectionAuthorityRecord,
     TokenOwnedEscrow,
     EscrowConstraintModel,
+    TokenOwnedEscrowV2,
+    EscrowAllowlist,
 }
 #[repr(C)]
 #[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
@@ -381,6 +384,62 @@ impl TokenMetadataAccount for Metadata {
     }
 }
 
+/// Byte offset of `update_authority` in a serialized `Metadata` account, right after the 1 byte key.
+pub const METADATA_UPDATE_AUTHORITY_OFFSET: usize = 1;
+
+/// Byte offset of `mint` in a serialized `Metadata` account, after the key and the 32 byte
+/// update authority.
+pub const METADATA_MINT_OFFSET: usize = METADATA_UPDATE_AUTHORITY_OFFSET + 32;
+
+impl Metadata {
+    /// Read the mint straight from the account data, without deserializing the whole struct.
+    pub fn mint_from_account_info(a: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        Self::pubkey_from_account_info(a, METADATA_MINT_OFFSET)
+    }
+
+    /// Read the update authority straight from the account data, without deserializing the
+    /// whole struct.
+    pub fn update_authority_from_account_info(a: &AccountInfo) -> Result<Pubkey, ProgramError> {
+        Self::pubkey_from_account_info(a, METADATA_UPDATE_AUTHORITY_OFFSET)
+    }
+
+    /// Check that the name, symbol and uri fit their maximum lengths. These are enforced on
+    /// every write, so this only catches legacy or corrupted accounts and is left to callers
+    /// that want the early failure.
+    pub fn validate_lengths(&self) -> ProgramResult {
+        if self.data.name.len() > MAX_NAME_LENGTH {
+            return Err(MetadataError::NameTooLong.into());
+        }
+
+        if self.data.symbol.len() > MAX_SYMBOL_LENGTH {
+            return Err(MetadataError::SymbolTooLong.into());
+        }
+
+        if self.data.uri.len() > MAX_URI_LENGTH {
+            return Err(MetadataError::UriTooLong.into());
+        }
+
+        Ok(())
+    }
+
+    fn pubkey_from_account_info(a: &AccountInfo, offset: usize) -> Result<Pubkey, ProgramError> {
+        // Check that this is a `token-metadata` owned account.
+        assert_owned_by(a, &ID)?;
+
+        let data = a.data.borrow();
+
+        if data.len() != MAX_METADATA_LEN || data[0] != Key::MetadataV1 as u8 {
+            return Err(MetadataError::DataTypeMismatch.into());
+        }
+
+        let bytes: [u8; 32] = data[offset..offset + 32]
+            .try_into()
+            .map_err(|_| MetadataError::DataTypeMismatch)?;
+
+        Ok(Pubkey::new_from_array(bytes))
+    }
+}
+
 // We have a custom implementation of BorshDeserialize for Metadata because of corrupted metadata issues
 // caused by resizing of the Creators array. We use a custom `meta_deser_unchecked` function
 // that has fallback values for corrupted fields.
@@ -944,31 +1003,300 @@ impl EditionMarker {
 
 pub const ESCROW_POSTFIX: &str = "escrow";
 
+/// Seed ahead of the tag of a tagged escrow, keeping tags apart from seed versions.
+pub const ESCROW_TAG: &str = "tag";
+
+pub const ESCROW_TREASURY: &str = "escrow_treasury";
+
+pub const ESCROW_ALLOWLIST: &str = "escrow_allowlist";
+
+/// Most attribute mints an escrow allowlist can hold.
+pub const MAX_ESCROW_ALLOWLIST_MINTS: usize = 32;
+
 #[repr(C)]
 #[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
 pub enum EscrowAuthority {
     TokenOwner,
-    Creator(Pubkey),
+    Creator(#[cfg_attr(feature = "serde-feature", serde(with = "As::<DisplayFromStr>"))] Pubkey),
+    /// Controlled by `threshold` signers out of a fixed set of members.
+    Multisig(EscrowMultisig),
+}
+
+/// Most members a multisig escrow authority can have.
+pub const MAX_ESCROW_MULTISIG_MEMBERS: usize = 10;
+
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone)]
+pub struct EscrowMultisig {
+    /// Members sorted by key, without duplicates.
+    #[cfg_attr(feature = "serde-feature", serde(with = "As::<Vec<DisplayFromStr>>"))]
+    pub members: Vec<Pubkey>,
+    /// Number of members that must sign.
+    pub threshold: u8,
+    /// Hash of the sorted members, used as an escrow seed since the members themselves
+    /// don't fit in the seeds.
+    pub members_hash: [u8; 32],
+}
+
+impl EscrowMultisig {
+    /// A multisig over `members`, which are sorted and deduplicated. Between one and all of
+    /// them must be required to sign.
+    pub fn new(mut members: Vec<Pubkey>, threshold: u8) -> Result<EscrowMultisig, ProgramError> {
+        members.sort();
+        members.dedup();
+
+        if members.is_empty()
+            || members.len() > MAX_ESCROW_MULTISIG_MEMBERS
+            || threshold == 0
+            || threshold as usize > members.len()
+        {
+            return Err(MetadataError::InvalidEscrowMultisig.into());
+        }
+
+        let members_hash = EscrowMultisig::hash_members(&members);
+
+        Ok(EscrowMultisig {
+            members,
+            threshold,
+            members_hash,
+        })
+    }
+
+    /// Hash `members` in sorted order, so the same set always derives the same escrow.
+    pub fn hash_members(members: &[Pubkey]) -> [u8; 32] {
+        let mut sorted = members.to_vec();
+        sorted.sort();
+
+        let seeds: Vec<&[u8]> = sorted.iter().map(|member| member.as_ref()).collect();
+        solana_program::hash::hashv(&seeds).to_bytes()
+    }
 }
 
 impl EscrowAuthority {
+    /// The escrow is owned by the token holder when they create it themselves, otherwise by
+    /// the creator. A holder that is also the update authority still gets a token owner
+    /// escrow, `CreateEscrowAccountArgs::force_creator` opts into a creator escrow instead.
+    /// The escrow instructions also treat a delegate approved for the full balance as the
+    /// holder, see `utils::is_token_holder`.
+    pub fn from_creator_and_token(creator: &Pubkey, token_owner: &Pubkey) -> EscrowAuthority {
+        if creator == token_owner {
+            EscrowAuthority::TokenOwner
+        } else {
+            EscrowAuthority::Creator(*creator)
+        }
+    }
+
+    /// The key controlling the escrow while `token_owner` holds the base token. A multisig
+    /// has no single controlling key, so it resolves to `None`.
+    pub fn resolved_authority(&self, token_owner: &Pubkey) -> Option<Pubkey> {
+        match self {
+            EscrowAuthority::TokenOwner => Some(*token_owner),
+            EscrowAuthority::Creator(creator) => Some(*creator),
+            EscrowAuthority::Multisig(_) => None,
+        }
+    }
+
     pub fn to_seeds(&self) -> Vec<&[u8]> {
         match self {
             EscrowAuthority::TokenOwner => vec![&[0]],
             EscrowAuthority::Creator(creator) => vec![&[1], creator.as_ref()],
+            EscrowAuthority::Multisig(multisig) => vec![
+                &[2],
+                multisig.members_hash.as_ref(),
+                std::slice::from_ref(&multisig.threshold),
+            ],
+        }
+    }
+
+    /// Number of bytes the authority takes in a serialized escrow.
+    pub fn serialized_len(&self) -> usize {
+        match self {
+            EscrowAuthority::TokenOwner => 1,
+            EscrowAuthority::Creator(_) => 33,
+            EscrowAuthority::Multisig(multisig) => 1 + 4 + 32 * multisig.members.len() + 1 + 32,
         }
     }
 }
 
+/// Layout version written to new `TokenOwnedEscrow` accounts. Version 0 escrows end after
+/// the bump, version 1 escrows end after the version, version 2 escrows end after `locked`,
+/// version 3 escrows end after the token standard, version 4 escrows end after the
+/// operator, version 5 escrows end after the funder and version 6 escrows end after the
+/// tag. Older escrows are upgraded with
+/// `TokenOwnedEscrow::migrate`.
+pub const TOKEN_OWNED_ESCROW_VERSION: u8 = 7;
+
 #[repr(C)]
 #[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
-#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, ShankAccount)]
+#[derive(BorshSerialize, PartialEq, Eq, Debug, Clone, ShankAccount)]
 pub struct TokenOwnedEscrow {
     pub key: Key,
+    #[cfg_attr(feature = "serde-feature", serde(with = "As::<DisplayFromStr>"))]
     pub base_token: Pubkey,
     pub authority: EscrowAuthority,
     pub bump: u8,
+    /// Account refunded with the escrow rent on close, the closing authority when `None`.
+    #[cfg_attr(
+        feature = "serde-feature",
+        serde(with = "As::<Option<DisplayFromStr>>")
+    )]
+    pub rent_recipient: Option<Pubkey>,
+    pub version: u8,
+    /// Locked escrows reject every transfer into or out of them until unlocked.
+    pub locked: bool,
+    /// Token standard of the base token checked at creation, so instructions that verified
+    /// the escrow key can trust it instead of reading the edition again. `None` for escrows
+    /// created before it was recorded, until `MigrateEscrow` is given the base token accounts.
+    pub token_standard: Option<TokenStandard>,
+    /// Key allowed to transfer tokens into and out of the escrow alongside the authority.
+    /// Only the authority may change the operator or close the escrow.
+    #[cfg_attr(
+        feature = "serde-feature",
+        serde(with = "As::<Option<DisplayFromStr>>")
+    )]
+    pub operator: Option<Pubkey>,
+    /// Payer that funded the escrow rent on behalf of a different authority, which a close
+    /// may refund instead of the authority. `None` when the authority paid for itself.
+    #[cfg_attr(
+        feature = "serde-feature",
+        serde(with = "As::<Option<DisplayFromStr>>")
+    )]
+    pub funder: Option<Pubkey>,
+    /// Tag the escrow address was derived with, so instructions can check the address from
+    /// the escrow alone. `ESCROW_DEFAULT_TAG` for untagged escrows, including every escrow
+    /// created before tags were recorded.
+    pub tag: u8,
+    /// Update authority of the base token when the escrow was created, which may reclaim a
+    /// Token Owned Escrow once the base token is burned, even when burning it cleared the
+    /// metadata. `None` for escrows created before it was recorded.
+    #[cfg_attr(
+        feature = "serde-feature",
+        serde(with = "As::<Option<DisplayFromStr>>")
+    )]
+    pub update_authority: Option<Pubkey>,
+}
+
+impl TokenOwnedEscrow {
+    /// Size of the current layout for a `Creator` authority and every optional key set.
+    /// Escrows are allocated with [`TokenOwnedEscrow::size_for`], which has room for all the
+    /// optional keys so they never need to grow while on the current layout.
+    pub const LEN: usize = 1 // key
+        + 32 // base token
+        + 33 // authority, a creator pubkey in the worst case
+        + 1 // bump
+        + 33 // optional rent recipient
+        + 1 // version
+        + 1 // locked
+        + 2 // optional token standard
+        + 33 // optional operator
+        + 33 // optional funder
+        + 1 // tag
+        + 33; // optional update authority
+
+    /// Size of an escrow controlled by `authority`, sized for that exact authority variant so
+    /// a token owner escrow doesn't pay rent for a creator key. Only multisig authorities
+    /// outgrow [`TokenOwnedEscrow::LEN`], growing with their member count. An escrow with
+    /// unset optional keys serializes shorter than this, leaving zeroed padding at the end of
+    /// the account, so its length is an upper bound rather than the exact serialized size.
+    pub fn size_for(authority: &EscrowAuthority) -> usize {
+        TokenOwnedEscrow::LEN - EscrowAuthority::Creator(Pubkey::default()).serialized_len()
+            + authority.serialized_len()
+    }
+
+    /// Upgrade an escrow read from an older layout to the current version, returning whether
+    /// anything changed. Fields missing from the old layout keep their defaults, and the
+    /// escrow moves to `Key::TokenOwnedEscrowV2` since it is written out with every field.
+    pub fn migrate(&mut self) -> bool {
+        if self.version >= TOKEN_OWNED_ESCROW_VERSION && self.key == Key::TokenOwnedEscrowV2 {
+            return false;
+        }
+
+        self.key = Key::TokenOwnedEscrowV2;
+        self.version = TOKEN_OWNED_ESCROW_VERSION;
+        true
+    }
+}
+
+/// Both escrow keys load into the same `TokenOwnedEscrow`, so instructions don't need to
+/// know which layout an account was written with. New and migrated escrows are written
+/// with `Key::TokenOwnedEscrowV2`, while `Key::TokenOwnedEscrow` is kept for older accounts.
+fn is_token_owned_escrow_key(key: Key) -> bool {
+    matches!(key, Key::TokenOwnedEscrow | Key::TokenOwnedEscrowV2)
+}
+
+impl borsh::de::BorshDeserialize for TokenOwnedEscrow {
+    fn deserialize(buf: &mut &[u8]) -> ::core::result::Result<Self, BorshError> {
+        let key: Key = BorshDeserialize::deserialize(buf)?;
+        if !is_token_owned_escrow_key(key) {
+            return Err(BorshError::other("DataTypeMismatch"));
+        }
+
+        let base_token: Pubkey = BorshDeserialize::deserialize(buf)?;
+        let authority: EscrowAuthority = BorshDeserialize::deserialize(buf)?;
+        let bump: u8 = BorshDeserialize::deserialize(buf)?;
+
+        // Older escrows end early, while `TokenOwnedEscrowV2` accounts always carry every
+        // field.
+        let is_legacy = key == Key::TokenOwnedEscrow;
+        let rent_recipient: Option<Pubkey> = if is_legacy && buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let version: u8 = if is_legacy && buf.is_empty() {
+            0
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let locked: bool = if is_legacy && buf.is_empty() {
+            false
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        // Added after `TokenOwnedEscrowV2`, so no key guarantees they are present.
+        let token_standard: Option<TokenStandard> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let operator: Option<Pubkey> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let funder: Option<Pubkey> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let tag: u8 = if buf.is_empty() {
+            ESCROW_DEFAULT_TAG
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+        let update_authority: Option<Pubkey> = if buf.is_empty() {
+            None
+        } else {
+            BorshDeserialize::deserialize(buf)?
+        };
+
+        Ok(TokenOwnedEscrow {
+            key,
+            base_token,
+            authority,
+            bump,
+            rent_recipient,
+            version,
+            locked,
+            token_standard,
+            operator,
+            funder,
+            tag,
+            update_authority,
+        })
+    }
 }
 
 impl TokenMetadataAccount for TokenOwnedEscrow {
@@ -977,14 +1305,72 @@ impl TokenMetadataAccount for TokenOwnedEscrow {
     }
 
     fn size() -> usize {
-        0
+        TokenOwnedEscrow::LEN
     }
 
     fn is_correct_account_type(data: &[u8], data_type: Key, _data_size: usize) -> bool {
+        if data.is_empty() {
+            return false;
+        }
+
         let key: Option<Key> = Key::from_u8(data[0]);
         match key {
-            Some(key) => key == data_type || key == Key::Uninitialized,
+            Some(key) if data_type == Key::TokenOwnedEscrow => is_token_owned_escrow_key(key),
+            Some(key) => key == data_type,
             None => false,
         }
     }
+
+    fn from_account_info(a: &AccountInfo) -> Result<Self, ProgramError> {
+        // Check that this is a `token-metadata` owned account.
+        assert_owned_by(a, &ID)?;
+
+        let data = a.data.borrow();
+
+        // An escrow is always written with its key, so an empty or zeroed account was never created.
+        if data.is_empty() || data[0] == Key::Uninitialized as u8 {
+            return Err(MetadataError::Uninitialized.into());
+        }
+
+        let escrow = Self::safe_deserialize(&data).map_err(|_| MetadataError::DataTypeMismatch)?;
+
+        Ok(escrow)
+    }
+}
+
+/// Attribute mints that may be deposited into the escrows of NFTs whose metadata has
+/// `authority` as its update authority. Deposits are only checked against the allowlist
+/// when it is passed to `TransferIntoEscrow`.
+#[repr(C)]
+#[cfg_attr(feature = "serde-feature", derive(Serialize, Deserialize))]
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Debug, Clone, ShankAccount)]
+pub struct EscrowAllowlist {
+    pub key: Key,
+    /// Update authority that owns the allowlist.
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub mints: Vec<Pubkey>,
+}
+
+impl EscrowAllowlist {
+    /// Size of an allowlist holding [`MAX_ESCROW_ALLOWLIST_MINTS`] mints. Allowlists are
+    /// allocated at this size so they never need to grow.
+    pub const LEN: usize = 1 // key
+        + 32 // authority
+        + 1 // bump
+        + 4 + 32 * MAX_ESCROW_ALLOWLIST_MINTS; // mints
+
+    pub fn allows(&self, mint: &Pubkey) -> bool {
+        self.mints.contains(mint)
+    }
+}
+
+impl TokenMetadataAccount for EscrowAllowlist {
+    fn key() -> Key {
+        Key::EscrowAllowlist
+    }
+
+    fn size() -> usize {
+        EscrowAllowlist::LEN
+    }
 }
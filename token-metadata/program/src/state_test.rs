@@ -6,9 +6,13 @@ use solana_sdk::{signature::Keypair, signer::Signer};
 use crate::{
     deser::tests::{expected_pesky_metadata, pesky_data},
     error::MetadataError,
+    escrow::ESCROW_DEFAULT_TAG,
     state::{
-        CollectionAuthorityRecord, Edition, EditionMarker, Key, MasterEditionV2, Metadata,
-        UseAuthorityRecord, MAX_METADATA_LEN,
+        CollectionAuthorityRecord, Edition, EditionMarker, EscrowAllowlist, EscrowAuthority,
+        EscrowMultisig, Key, MasterEditionV2, Metadata, TokenOwnedEscrow, TokenStandard,
+        UseAuthorityRecord, MAX_ESCROW_ALLOWLIST_MINTS, MAX_ESCROW_MULTISIG_MEMBERS,
+        MAX_METADATA_LEN, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH,
+        TOKEN_OWNED_ESCROW_VERSION,
     },
     ID,
 };
@@ -67,6 +71,74 @@ mod metadata {
         assert_eq!(md, expected_metadata);
     }
 
+    #[test]
+    fn read_fixed_offsets_from_metadata() {
+        let mut fixtures = vec![expected_pesky_metadata(), Metadata::default()];
+        fixtures.push(Metadata {
+            update_authority: Keypair::new().pubkey(),
+            mint: Keypair::new().pubkey(),
+            ..Metadata::default()
+        });
+
+        for expected_metadata in fixtures {
+            let mut buf = Vec::new();
+            expected_metadata.serialize(&mut buf).unwrap();
+            pad_metadata_length(&mut buf);
+
+            let pubkey = Keypair::new().pubkey();
+            let owner = &ID;
+            let mut lamports = 1_000_000_000;
+            let mut data = buf.clone();
+
+            let md_account_info = AccountInfo::new(
+                &pubkey,
+                false,
+                true,
+                &mut lamports,
+                &mut data,
+                owner,
+                false,
+                1_000_000_000,
+            );
+
+            let md = Metadata::from_account_info(&md_account_info).unwrap();
+            assert_eq!(
+                Metadata::mint_from_account_info(&md_account_info).unwrap(),
+                md.mint
+            );
+            assert_eq!(
+                Metadata::update_authority_from_account_info(&md_account_info).unwrap(),
+                md.update_authority
+            );
+        }
+    }
+
+    #[test]
+    fn fail_to_read_fixed_offsets_from_wrong_account_type() {
+        let mut buf = Vec::new();
+        UseAuthorityRecord::default().serialize(&mut buf).unwrap();
+        pad_metadata_length(&mut buf);
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let error = Metadata::mint_from_account_info(&account_info).unwrap_err();
+        assert_eq!(error, MetadataError::DataTypeMismatch.into());
+    }
+
     #[test]
     fn fail_to_deserialize_metadata_with_wrong_owner() {
         let expected_metadata = expected_pesky_metadata();
@@ -281,6 +353,33 @@ mod metadata {
         let err = Metadata::from_account_info(&account_info).unwrap_err();
         assert_eq!(err, MetadataError::DataTypeMismatch.into());
     }
+
+    #[test]
+    fn validate_metadata_lengths() {
+        let metadata = expected_pesky_metadata();
+        assert!(metadata.validate_lengths().is_ok());
+
+        let mut long_name = metadata.clone();
+        long_name.data.name = "n".repeat(MAX_NAME_LENGTH + 1);
+        assert_eq!(
+            long_name.validate_lengths().unwrap_err(),
+            MetadataError::NameTooLong.into()
+        );
+
+        let mut long_symbol = metadata.clone();
+        long_symbol.data.symbol = "s".repeat(MAX_SYMBOL_LENGTH + 1);
+        assert_eq!(
+            long_symbol.validate_lengths().unwrap_err(),
+            MetadataError::SymbolTooLong.into()
+        );
+
+        let mut long_uri = metadata;
+        long_uri.data.uri = "u".repeat(MAX_URI_LENGTH + 1);
+        assert_eq!(
+            long_uri.validate_lengths().unwrap_err(),
+            MetadataError::UriTooLong.into()
+        );
+    }
 }
 
 mod master_edition {
@@ -595,3 +694,819 @@ mod collection_authority_record {
         assert_eq!(error, MetadataError::DataTypeMismatch.into());
     }
 }
+
+mod token_owned_escrow {
+    use solana_program::pubkey::Pubkey;
+
+    use crate::state::TokenMetadataAccount;
+
+    use super::*;
+
+    fn escrow_data() -> Vec<u8> {
+        let escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::Creator(Pubkey::new_unique()),
+            bump: 254,
+            rent_recipient: Some(Pubkey::new_unique()),
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: Some(Pubkey::new_unique()),
+            funder: Some(Pubkey::new_unique()),
+            tag: 0,
+            update_authority: Some(Pubkey::new_unique()),
+        };
+
+        escrow.try_to_vec().unwrap()
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_without_rent_recipient() {
+        // Escrows created before the rent recipient was recorded end after the bump.
+        let base_token = Pubkey::new_unique();
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(base_token.as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let escrow = TokenOwnedEscrow::from_account_info(&account_info).unwrap();
+        assert_eq!(
+            escrow,
+            TokenOwnedEscrow {
+                key: Key::TokenOwnedEscrow,
+                base_token,
+                authority: EscrowAuthority::TokenOwner,
+                bump: 253,
+                rent_recipient: None,
+                version: 0,
+                locked: false,
+                token_standard: None,
+                operator: None,
+                funder: None,
+                tag: 0,
+                update_authority: None,
+            }
+        );
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_version_1_as_unlocked() {
+        // Version 1 escrows end after the version.
+        let base_token = Pubkey::new_unique();
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(base_token.as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(1);
+
+        let mut escrow = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(escrow.version, 1);
+        assert!(!escrow.locked);
+
+        assert!(escrow.migrate());
+        // `locked`, an empty token standard, operator and funder, the tag and an empty update
+        // authority are appended.
+        assert_eq!(escrow.try_to_vec().unwrap().len(), buf.len() + 6);
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_version_2_without_token_standard() {
+        // Version 2 escrows end after `locked`, possibly padded with zeroes to their old size.
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(Pubkey::new_unique().as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(2);
+        buf.push(1);
+
+        for data in [buf.clone(), [buf.as_slice(), &[0; 64]].concat()] {
+            let mut escrow = TokenOwnedEscrow::deserialize(&mut data.as_slice()).unwrap();
+            assert_eq!(escrow.version, 2);
+            assert!(escrow.locked);
+            assert_eq!(escrow.token_standard, None);
+
+            assert!(escrow.migrate());
+            assert_eq!(escrow.version, TOKEN_OWNED_ESCROW_VERSION);
+        }
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_version_3_without_operator() {
+        // Version 3 escrows end after the token standard.
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(Pubkey::new_unique().as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(3);
+        buf.push(0);
+        buf.extend_from_slice(&Some(TokenStandard::NonFungible).try_to_vec().unwrap());
+
+        let mut escrow = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(escrow.version, 3);
+        assert_eq!(escrow.token_standard, Some(TokenStandard::NonFungible));
+        assert_eq!(escrow.operator, None);
+
+        assert!(escrow.migrate());
+        assert_eq!(escrow.try_to_vec().unwrap().len(), buf.len() + 4);
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_version_4_without_funder() {
+        // Version 4 escrows end after the operator.
+        let operator = Pubkey::new_unique();
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(Pubkey::new_unique().as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(4);
+        buf.push(0);
+        buf.extend_from_slice(&Some(TokenStandard::NonFungible).try_to_vec().unwrap());
+        buf.extend_from_slice(&Some(operator).try_to_vec().unwrap());
+
+        let mut escrow = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(escrow.version, 4);
+        assert_eq!(escrow.operator, Some(operator));
+        assert_eq!(escrow.funder, None);
+
+        assert!(escrow.migrate());
+        assert_eq!(escrow.try_to_vec().unwrap().len(), buf.len() + 3);
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_version_5_as_untagged() {
+        // Version 5 escrows end after the funder, and were all derived without a tag.
+        let funder = Pubkey::new_unique();
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(Pubkey::new_unique().as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(5);
+        buf.push(0);
+        buf.extend_from_slice(&Some(TokenStandard::NonFungible).try_to_vec().unwrap());
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.extend_from_slice(&Some(funder).try_to_vec().unwrap());
+
+        let mut escrow = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(escrow.version, 5);
+        assert_eq!(escrow.funder, Some(funder));
+        assert_eq!(escrow.tag, ESCROW_DEFAULT_TAG);
+
+        assert!(escrow.migrate());
+        assert_eq!(escrow.try_to_vec().unwrap().len(), buf.len() + 2);
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_version_6_without_update_authority() {
+        // Version 6 escrows end after the tag.
+        let mut buf = vec![Key::TokenOwnedEscrowV2 as u8];
+        buf.extend_from_slice(Pubkey::new_unique().as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(6);
+        buf.push(0);
+        buf.extend_from_slice(&Some(TokenStandard::NonFungible).try_to_vec().unwrap());
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        buf.push(3);
+
+        let mut escrow = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(escrow.version, 6);
+        assert_eq!(escrow.tag, 3);
+        assert_eq!(escrow.update_authority, None);
+
+        assert!(escrow.migrate());
+        assert_eq!(escrow.try_to_vec().unwrap().len(), buf.len() + 1);
+    }
+
+    #[test]
+    fn successfully_round_trip_escrow_tag() {
+        let escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrowV2,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::Creator(Pubkey::new_unique()),
+            bump: 252,
+            rent_recipient: Some(Pubkey::new_unique()),
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: Some(Pubkey::new_unique()),
+            funder: Some(Pubkey::new_unique()),
+            tag: 7,
+            update_authority: Some(Pubkey::new_unique()),
+        };
+
+        // With every optional key set, a creator escrow fills its allocation exactly.
+        let buf = escrow.try_to_vec().unwrap();
+        assert_eq!(buf.len(), TokenOwnedEscrow::size_for(&escrow.authority));
+        assert_eq!(
+            TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap(),
+            escrow
+        );
+    }
+
+    #[test]
+    fn successfully_round_trip_escrow_operator() {
+        let operator = Pubkey::new_unique();
+        let escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::TokenOwner,
+            bump: 253,
+            rent_recipient: None,
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: Some(operator),
+            funder: Some(Pubkey::new_unique()),
+            tag: 0,
+            update_authority: None,
+        };
+
+        // Escrows are allocated with room for every optional key, so setting an operator
+        // never resizes. Unset keys serialize shorter and leave zeroed padding behind.
+        let buf = escrow.try_to_vec().unwrap();
+        assert!(buf.len() <= TokenOwnedEscrow::size_for(&EscrowAuthority::TokenOwner));
+
+        let deserialized = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(deserialized.operator, Some(operator));
+        assert_eq!(deserialized, escrow);
+    }
+
+    #[test]
+    fn migrate_escrow_from_version_0() {
+        let base_token = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+        let mut buf = vec![Key::TokenOwnedEscrow as u8];
+        buf.extend_from_slice(base_token.as_ref());
+        buf.extend_from_slice(&EscrowAuthority::Creator(creator).try_to_vec().unwrap());
+        buf.push(252);
+
+        let mut escrow = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+        assert_eq!(escrow.version, 0);
+        assert!(escrow.migrate());
+        assert!(!escrow.migrate());
+
+        let migrated = escrow.try_to_vec().unwrap();
+        assert!(migrated.len() > buf.len());
+
+        let escrow = TokenOwnedEscrow::deserialize(&mut migrated.as_slice()).unwrap();
+        assert_eq!(
+            escrow,
+            TokenOwnedEscrow {
+                key: Key::TokenOwnedEscrowV2,
+                base_token,
+                authority: EscrowAuthority::Creator(creator),
+                bump: 252,
+                rent_recipient: None,
+                version: TOKEN_OWNED_ESCROW_VERSION,
+                locked: false,
+                token_standard: None,
+                operator: None,
+                funder: None,
+                tag: 0,
+                update_authority: None,
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_current_escrow_to_v2_key() {
+        // Escrows written on the current layout before the V2 key existed only swap keys.
+        let mut escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::TokenOwner,
+            bump: 250,
+            rent_recipient: None,
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: true,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: None,
+            funder: None,
+            tag: 0,
+            update_authority: None,
+        };
+
+        assert!(escrow.migrate());
+        assert_eq!(escrow.key, Key::TokenOwnedEscrowV2);
+        assert!(escrow.locked);
+        assert!(!escrow.migrate());
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow() {
+        let buf = escrow_data();
+        let expected = TokenOwnedEscrow::deserialize(&mut buf.as_slice()).unwrap();
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let escrow = TokenOwnedEscrow::from_account_info(&account_info).unwrap();
+        assert_eq!(escrow, expected);
+    }
+
+    #[test]
+    fn fail_to_deserialize_truncated_escrow() {
+        let mut buf = escrow_data();
+        buf.truncate(20);
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let error = TokenOwnedEscrow::from_account_info(&account_info).unwrap_err();
+        assert_eq!(error, MetadataError::DataTypeMismatch.into());
+    }
+
+    #[test]
+    fn fail_to_deserialize_uninitialized_escrow() {
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = vec![];
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let error = TokenOwnedEscrow::from_account_info(&account_info).unwrap_err();
+        assert_eq!(error, MetadataError::Uninitialized.into());
+    }
+
+    #[test]
+    fn fail_to_deserialize_wrong_account_type_into_escrow() {
+        let mut buf = Vec::new();
+        UseAuthorityRecord::default().serialize(&mut buf).unwrap();
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let error = TokenOwnedEscrow::from_account_info(&account_info).unwrap_err();
+        assert_eq!(error, MetadataError::DataTypeMismatch.into());
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_v2() {
+        let escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrowV2,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::TokenOwner,
+            bump: 251,
+            rent_recipient: None,
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::FungibleAsset),
+            operator: None,
+            funder: None,
+            tag: 0,
+            update_authority: None,
+        };
+        let buf = escrow.try_to_vec().unwrap();
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let deserialized = TokenOwnedEscrow::from_account_info(&account_info).unwrap();
+        assert_eq!(deserialized, escrow);
+    }
+
+    #[test]
+    fn fail_to_deserialize_escrow_v2_without_version() {
+        // Only the original key may omit the fields added after the bump.
+        let mut buf = vec![Key::TokenOwnedEscrowV2 as u8];
+        buf.extend_from_slice(Pubkey::new_unique().as_ref());
+        buf.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        buf.push(253);
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let error = TokenOwnedEscrow::from_account_info(&account_info).unwrap_err();
+        assert_eq!(error, MetadataError::DataTypeMismatch.into());
+    }
+
+    #[test]
+    fn escrow_len_matches_largest_serialized_escrow() {
+        // `escrow_data` uses a creator authority, a rent recipient and an operator, the largest
+        // variants.
+        assert_eq!(escrow_data().len(), TokenOwnedEscrow::LEN);
+
+        let token_owner_escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::TokenOwner,
+            bump: 254,
+            rent_recipient: Some(Pubkey::new_unique()),
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: None,
+            funder: None,
+            tag: 0,
+            update_authority: None,
+        };
+        assert!(token_owner_escrow.try_to_vec().unwrap().len() < TokenOwnedEscrow::LEN);
+    }
+
+    #[test]
+    fn multisig_escrow_len_grows_with_members() {
+        let members = vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::Multisig(EscrowMultisig::new(members, 2).unwrap()),
+            bump: 254,
+            rent_recipient: Some(Pubkey::new_unique()),
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: None,
+            funder: None,
+            tag: 0,
+            update_authority: None,
+        };
+
+        // The unset operator and funder leave padding after the serialized escrow.
+        let len = TokenOwnedEscrow::size_for(&escrow.authority);
+        assert!(escrow.try_to_vec().unwrap().len() <= len);
+        assert!(len > TokenOwnedEscrow::LEN);
+        assert_eq!(
+            TokenOwnedEscrow::size_for(&EscrowAuthority::Creator(Pubkey::new_unique())),
+            TokenOwnedEscrow::LEN
+        );
+    }
+
+    #[test]
+    fn escrow_size_matches_authority_variant() {
+        let escrow = |authority| TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority,
+            bump: 254,
+            rent_recipient: Some(Pubkey::new_unique()),
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: Some(Pubkey::new_unique()),
+            funder: Some(Pubkey::new_unique()),
+            tag: 0,
+            update_authority: Some(Pubkey::new_unique()),
+        };
+
+        for authority in [
+            EscrowAuthority::TokenOwner,
+            EscrowAuthority::Creator(Pubkey::new_unique()),
+        ] {
+            let size = TokenOwnedEscrow::size_for(&authority);
+            assert_eq!(escrow(authority).try_to_vec().unwrap().len(), size);
+        }
+
+        assert_eq!(
+            TokenOwnedEscrow::size_for(&EscrowAuthority::TokenOwner) + 32,
+            TokenOwnedEscrow::size_for(&EscrowAuthority::Creator(Pubkey::new_unique()))
+        );
+    }
+
+    #[test]
+    fn successfully_deserialize_escrow_padded_to_len() {
+        let escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority: EscrowAuthority::TokenOwner,
+            bump: 254,
+            rent_recipient: None,
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: true,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: Some(Pubkey::new_unique()),
+            funder: Some(Pubkey::new_unique()),
+            tag: 0,
+            update_authority: None,
+        };
+        let mut buf = escrow.try_to_vec().unwrap();
+        buf.resize(TokenOwnedEscrow::LEN, 0);
+
+        let pubkey = Keypair::new().pubkey();
+        let owner = &ID;
+        let mut lamports = 1_000_000_000;
+        let mut data = buf.clone();
+
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            owner,
+            false,
+            1_000_000_000,
+        );
+
+        let deserialized = TokenOwnedEscrow::from_account_info(&account_info).unwrap();
+        assert_eq!(deserialized, escrow);
+    }
+}
+
+mod escrow_authority {
+    use solana_program::pubkey::Pubkey;
+
+    use super::*;
+
+    #[test]
+    fn token_owner_creating_escrow_owns_it() {
+        let owner = Pubkey::new_unique();
+
+        assert_eq!(
+            EscrowAuthority::from_creator_and_token(&owner, &owner),
+            EscrowAuthority::TokenOwner
+        );
+    }
+
+    #[test]
+    fn other_creator_owns_escrow() {
+        let creator = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        assert_eq!(
+            EscrowAuthority::from_creator_and_token(&creator, &owner),
+            EscrowAuthority::Creator(creator)
+        );
+    }
+
+    #[test]
+    fn token_owner_resolves_to_holder() {
+        let owner = Pubkey::new_unique();
+
+        assert_eq!(
+            EscrowAuthority::TokenOwner.resolved_authority(&owner),
+            Some(owner)
+        );
+    }
+
+    #[test]
+    fn creator_resolves_to_creator() {
+        let creator = Pubkey::new_unique();
+
+        assert_eq!(
+            EscrowAuthority::Creator(creator).resolved_authority(&Pubkey::new_unique()),
+            Some(creator)
+        );
+    }
+
+    #[test]
+    fn multisig_resolves_to_no_single_key() {
+        let multisig = EscrowMultisig::new(vec![Pubkey::new_unique()], 1).unwrap();
+
+        assert_eq!(
+            EscrowAuthority::Multisig(multisig).resolved_authority(&Pubkey::new_unique()),
+            None
+        );
+    }
+
+    #[test]
+    fn multisig_members_hash_ignores_order() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        let c = Pubkey::new_unique();
+
+        let multisig = EscrowMultisig::new(vec![c, a, b, a], 2).unwrap();
+        let mut sorted = vec![a, b, c];
+        sorted.sort();
+
+        assert_eq!(multisig.members, sorted);
+        assert_eq!(multisig, EscrowMultisig::new(vec![b, c, a], 2).unwrap());
+        assert_eq!(
+            multisig.members_hash,
+            EscrowMultisig::hash_members(&[a, c, b])
+        );
+    }
+
+    #[test]
+    fn multisig_threshold_must_be_reachable() {
+        let members = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+        for (members, threshold) in [
+            (members.clone(), 0),
+            (members, 3),
+            (vec![], 1),
+            (vec![Pubkey::new_unique(); 1], 2),
+            (
+                (0..=MAX_ESCROW_MULTISIG_MEMBERS)
+                    .map(|_| Pubkey::new_unique())
+                    .collect(),
+                1,
+            ),
+        ] {
+            assert_eq!(
+                EscrowMultisig::new(members, threshold).unwrap_err(),
+                MetadataError::InvalidEscrowMultisig.into()
+            );
+        }
+    }
+}
+
+mod escrow_allowlist {
+    use solana_program::pubkey::Pubkey;
+
+    use super::*;
+    use crate::state::TokenMetadataAccount;
+
+    #[test]
+    fn allowlist_len_fits_max_mints() {
+        let allowlist = EscrowAllowlist {
+            key: Key::EscrowAllowlist,
+            authority: Pubkey::new_unique(),
+            bump: 254,
+            mints: (0..MAX_ESCROW_ALLOWLIST_MINTS)
+                .map(|_| Pubkey::new_unique())
+                .collect(),
+        };
+
+        assert_eq!(allowlist.try_to_vec().unwrap().len(), EscrowAllowlist::LEN);
+    }
+
+    #[test]
+    fn successfully_deserialize_allowlist_padded_to_len() {
+        let allowed = Pubkey::new_unique();
+        let allowlist = EscrowAllowlist {
+            key: Key::EscrowAllowlist,
+            authority: Pubkey::new_unique(),
+            bump: 254,
+            mints: vec![allowed],
+        };
+        let mut data = allowlist.try_to_vec().unwrap();
+        data.resize(EscrowAllowlist::LEN, 0);
+
+        let pubkey = Pubkey::new_unique();
+        let mut lamports = 1_000_000_000;
+        let account_info = AccountInfo::new(
+            &pubkey,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &ID,
+            false,
+            0,
+        );
+
+        let deserialized = EscrowAllowlist::from_account_info(&account_info).unwrap();
+        assert_eq!(deserialized, allowlist);
+        assert!(deserialized.allows(&allowed));
+        assert!(!deserialized.allows(&Pubkey::new_unique()));
+    }
+}
+
+#[cfg(feature = "serde-feature")]
+mod escrow_json {
+    use solana_program::pubkey::Pubkey;
+
+    use super::*;
+
+    fn escrow(authority: EscrowAuthority) -> TokenOwnedEscrow {
+        TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: Pubkey::new_unique(),
+            authority,
+            bump: 254,
+            rent_recipient: Some(Pubkey::new_unique()),
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: None,
+            funder: Some(Pubkey::new_unique()),
+            tag: 0,
+            update_authority: None,
+        }
+    }
+
+    #[test]
+    fn escrow_round_trips_through_json() {
+        let multisig =
+            EscrowMultisig::new(vec![Pubkey::new_unique(), Pubkey::new_unique()], 1).unwrap();
+
+        for authority in [
+            EscrowAuthority::TokenOwner,
+            EscrowAuthority::Creator(Pubkey::new_unique()),
+            EscrowAuthority::Multisig(multisig),
+        ] {
+            let escrow = escrow(authority);
+            let json = serde_json::to_string(&escrow).unwrap();
+
+            assert_eq!(
+                serde_json::from_str::<TokenOwnedEscrow>(&json).unwrap(),
+                escrow
+            );
+        }
+    }
+
+    #[test]
+    fn escrow_keys_serialize_as_base58() {
+        let creator = Pubkey::new_unique();
+        let escrow = escrow(EscrowAuthority::Creator(creator));
+        let json = serde_json::to_value(&escrow).unwrap();
+
+        assert_eq!(json["base_token"], escrow.base_token.to_string());
+        assert_eq!(json["authority"]["Creator"], creator.to_string());
+        assert_eq!(json["funder"], escrow.funder.unwrap().to_string());
+        assert!(json["operator"].is_null());
+    }
+}
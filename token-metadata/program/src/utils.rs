@@ -5,8 +5,8 @@ use crate::{
     pda::find_master_edition_account,
     state::{
         get_reservation_list, CollectionDetails, Creator, Data, DataV2, Edition, EditionMarker,
-        Key, MasterEditionV1, MasterEditionV2, Metadata, TokenMetadataAccount, TokenStandard, Uses,
-        EDITION, EDITION_MARKER_BIT_SIZE, MAX_CREATOR_LIMIT, MAX_EDITION_LEN,
+        EscrowMultisig, Key, MasterEditionV1, MasterEditionV2, Metadata, TokenMetadataAccount,
+        TokenStandard, Uses, EDITION, EDITION_MARKER_BIT_SIZE, MAX_CREATOR_LIMIT, MAX_EDITION_LEN,
         MAX_EDITION_MARKER_SIZE, MAX_MASTER_EDITION_LEN, MAX_METADATA_LEN, MAX_NAME_LENGTH,
         MAX_SYMBOL_LENGTH, MAX_URI_LENGTH, PREFIX,
     },
@@ -17,12 +17,15 @@ use solana_program::{
     account_info::AccountInfo,
     borsh::try_from_slice_unchecked,
     entrypoint::ProgramResult,
+    instruction::Instruction,
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_memory::sol_memcpy,
     program_option::COption,
     program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
+    rent::ACCOUNT_STORAGE_OVERHEAD,
     system_instruction,
     sysvar::{rent::Rent, Sysvar},
 };
@@ -32,6 +35,41 @@ use spl_token::{
 };
 use std::{collections::HashMap, convert::TryInto};
 
+/// Creators must be unique and their shares must add up to exactly 100.
+pub fn validate_creator_shares(creators: &[Creator]) -> ProgramResult {
+    let mut addresses: Vec<&Pubkey> = Vec::with_capacity(creators.len());
+    let mut share_total: u8 = 0;
+
+    for creator in creators {
+        if addresses.contains(&&creator.address) {
+            return Err(MetadataError::DuplicateCreatorAddress.into());
+        }
+        addresses.push(&creator.address);
+
+        share_total = share_total
+            .checked_add(creator.share)
+            .ok_or(MetadataError::NumericalOverflowError)?;
+    }
+
+    if share_total != 100 {
+        return Err(MetadataError::ShareTotalMustBe100.into());
+    }
+
+    Ok(())
+}
+
+/// Maximum seller fee, i.e. 100%.
+pub const MAX_SELLER_FEE_BASIS_POINTS: u16 = 10000;
+
+/// Royalties can't be more than 100% of the sale.
+pub fn validate_seller_fee(seller_fee_basis_points: u16) -> ProgramResult {
+    if seller_fee_basis_points > MAX_SELLER_FEE_BASIS_POINTS {
+        return Err(MetadataError::InvalidBasisPoints.into());
+    }
+
+    Ok(())
+}
+
 pub fn assert_data_valid(
     data: &Data,
     update_authority: &Pubkey,
@@ -51,9 +89,7 @@ pub fn assert_data_valid(
         return Err(MetadataError::UriTooLong.into());
     }
 
-    if data.seller_fee_basis_points > 10000 {
-        return Err(MetadataError::InvalidBasisPoints.into());
-    }
+    validate_seller_fee(data.seller_fee_basis_points)?;
 
     if let Some(creators) = &data.creators {
         if creators.len() > MAX_CREATOR_LIMIT {
@@ -64,15 +100,13 @@ pub fn assert_data_valid(
             return Err(MetadataError::CreatorsMustBeAtleastOne.into());
         }
 
+        // No duplicate entries and shares must add up to 100%.
+        validate_creator_shares(creators)?;
+
         // Store caller-supplied creator's array into a hashmap for direct lookup.
         let new_creators_map: HashMap<&Pubkey, &Creator> =
             creators.iter().map(|c| (&c.address, c)).collect();
 
-        // Do not allow duplicate entries in the creator's array.
-        if new_creators_map.len() != creators.len() {
-            return Err(MetadataError::DuplicateCreatorAddress.into());
-        }
-
         // If there is an existing creator's array, store this in a hashmap as well.
         let existing_creators_map: Option<HashMap<&Pubkey, &Creator>> = existing_metadata
             .data
@@ -81,14 +115,7 @@ pub fn assert_data_valid(
             .map(|existing_creators| existing_creators.iter().map(|c| (&c.address, c)).collect());
 
         // Loop over new creator's map.
-        let mut share_total: u8 = 0;
         for (address, creator) in &new_creators_map {
-            // Add up creator shares.  After looping through all creators, will
-            // verify it adds up to 100%.
-            share_total = share_total
-                .checked_add(creator.share)
-                .ok_or(MetadataError::NumericalOverflowError)?;
-
             // If this flag is set we are allowing any and all creators to be marked as verified
             // without further checking.  This can only be done in special circumstances when the
             // metadata is fully trusted such as when minting a limited edition.  Note we are still
@@ -127,11 +154,6 @@ pub fn assert_data_valid(
             }
         }
 
-        // Ensure share total is 100%.
-        if share_total != 100 {
-            return Err(MetadataError::ShareTotalMustBe100.into());
-        }
-
         // Next make sure there were not any existing creators that were already verified but not
         // listed in the new creator's array.
         if allow_direct_creator_writes {
@@ -165,8 +187,140 @@ pub fn assert_initialized<T: Pack + IsInitialized>(
     }
 }
 
+/// Assert `token_info` is an initialized token account for `mint` holding at least
+/// `min_amount` tokens and, when given, owned by `owner`. Returns the token account.
+pub fn assert_token_owned_by(
+    token_info: &AccountInfo,
+    mint: &Pubkey,
+    owner: Option<&Pubkey>,
+    min_amount: u64,
+) -> Result<Account, ProgramError> {
+    assert_owned_by(token_info, &spl_token::id())?;
+    let token_account: Account = assert_initialized(token_info)?;
+
+    if token_account.mint != *mint {
+        return Err(MetadataError::InvalidTokenAccountMint.into());
+    }
+
+    if let Some(owner) = owner {
+        if token_account.owner != *owner {
+            return Err(MetadataError::InvalidOwner.into());
+        }
+    }
+
+    if token_account.amount < min_amount {
+        return Err(MetadataError::NotEnoughTokens.into());
+    }
+
+    Ok(token_account)
+}
+
+/// Assert `member_infos` are the members of `multisig`, in their stored order, and that at
+/// least `threshold` of them signed.
+pub fn assert_multisig_signers(
+    multisig: &EscrowMultisig,
+    member_infos: &[AccountInfo],
+) -> ProgramResult {
+    if member_infos.len() != multisig.members.len() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let mut signers = 0;
+    for (member, member_info) in multisig.members.iter().zip(member_infos) {
+        if member_info.key != member {
+            return Err(MetadataError::MustBeEscrowAuthority.into());
+        }
+        if member_info.is_signer {
+            signers += 1;
+        }
+    }
+
+    if signers < multisig.threshold as usize {
+        return Err(MetadataError::NotEnoughMultisigSigners.into());
+    }
+
+    Ok(())
+}
+
+/// Assert every account in `token_accounts` is a token account owned by `escrow` holding
+/// nothing. Token accounts can't be enumerated on-chain, so this only covers the accounts
+/// the caller passes in: callers must attest to every attribute account they know of, and
+/// tokens in an account left out stay with whoever can move them.
+pub fn assert_escrow_empty(escrow: &Pubkey, token_accounts: &[AccountInfo]) -> ProgramResult {
+    for token_account_info in token_accounts {
+        assert_owned_by(token_account_info, &spl_token::id())?;
+        let token_account: Account = assert_initialized(token_account_info)?;
+
+        if token_account.owner != *escrow {
+            return Err(MetadataError::InvalidOwner.into());
+        }
+
+        if token_account.amount > 0 {
+            return Err(MetadataError::EscrowNotEmpty.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Assert a token account holding `amount` of a base token of `token_standard` holds it. A
+/// `NonFungible` base token must be held exactly, as any other balance means the mint is not
+/// the NFT the escrow was created for.
+pub fn assert_holds_base_token(
+    amount: u64,
+    token_standard: Option<TokenStandard>,
+) -> ProgramResult {
+    if amount < 1 {
+        return Err(MetadataError::NotEnoughTokens.into());
+    }
+
+    if token_standard == Some(TokenStandard::NonFungible) && amount != 1 {
+        return Err(MetadataError::InvalidAmount.into());
+    }
+
+    Ok(())
+}
+
+/// The account that can move every token in `token_account`: its delegate when it is
+/// approved for the full balance, otherwise its owner. A wallet that has delegated its NFT,
+/// for example to a staking program that then froze it, no longer controls the token.
+pub fn effective_token_controller(token_account: &Account) -> Pubkey {
+    match token_account.delegate {
+        COption::Some(delegate) if token_account.delegated_amount >= token_account.amount => {
+            delegate
+        }
+        _ => token_account.owner,
+    }
+}
+
+/// Whether `key` may act for the holder of `token_account`: its owner, or the delegate
+/// controlling it, see `effective_token_controller`. Token Owned Escrows accept either, so
+/// a delegate that created one can also use it.
+pub fn is_token_holder(token_account: &Account, key: &Pubkey) -> bool {
+    *key == token_account.owner || *key == effective_token_controller(token_account)
+}
+
+/// Same as `Rent::minimum_balance`, but returns an error instead of overflowing on an
+/// oversized account.
+pub fn checked_minimum_balance(rent: &Rent, size: usize) -> Result<u64, ProgramError> {
+    let bytes = u64::try_from(size)
+        .ok()
+        .and_then(|size| size.checked_add(ACCOUNT_STORAGE_OVERHEAD))
+        .and_then(|bytes| bytes.checked_mul(rent.lamports_per_byte_year))
+        .ok_or(MetadataError::NumericalOverflowError)?;
+
+    let lamports = bytes as f64 * rent.exemption_threshold;
+    if !lamports.is_finite() || lamports >= u64::MAX as f64 {
+        return Err(MetadataError::NumericalOverflowError.into());
+    }
+
+    Ok(lamports as u64)
+}
+
 /// Create account almost from scratch, lifted from
 /// <https://github.com/solana-labs/solana-program-library/tree/master/associated-token-account/program/src/processor.rs#L51-L98>
+///
+/// Lamports already held by the new account count towards rent, so the payer only tops up the difference.
 #[inline(always)]
 pub fn create_or_allocate_account_raw<'a>(
     program_id: Pubkey,
@@ -177,10 +331,12 @@ pub fn create_or_allocate_account_raw<'a>(
     signer_seeds: &[&[u8]],
 ) -> ProgramResult {
     let rent = &Rent::get()?;
-    let required_lamports = rent
-        .minimum_balance(size)
+    let required_lamports = checked_minimum_balance(rent, size)?
         .max(1)
         .saturating_sub(new_account_info.lamports());
+    let space: u64 = size
+        .try_into()
+        .map_err(|_| MetadataError::NumericalOverflowError)?;
 
     if required_lamports > 0 {
         msg!("Transfer {} lamports to the new account", required_lamports);
@@ -198,7 +354,7 @@ pub fn create_or_allocate_account_raw<'a>(
 
     msg!("Allocate space for the account");
     invoke_signed(
-        &system_instruction::allocate(new_account_info.key, size.try_into().unwrap()),
+        &system_instruction::allocate(new_account_info.key, space),
         accounts,
         &[signer_seeds],
     )?;
@@ -257,6 +413,40 @@ pub fn close_account_raw<'a>(
     Ok(())
 }
 
+/// Set the `verified` flag of the creator at `address`, leaving every other creator and
+/// the shares untouched.
+pub fn set_creator_verified(
+    creators: &mut Option<Vec<Creator>>,
+    address: &Pubkey,
+    verified: bool,
+) -> ProgramResult {
+    let creators = match creators {
+        Some(creators) if !creators.is_empty() => creators,
+        _ => return Err(MetadataError::NoCreatorsPresentOnMetadata.into()),
+    };
+
+    let creator = creators
+        .iter_mut()
+        .find(|creator| creator.address == *address)
+        .ok_or(MetadataError::CreatorNotFound)?;
+    creator.verified = verified;
+
+    Ok(())
+}
+
+/// Check that `account_info` is the expected account, failing with `error` otherwise.
+pub fn assert_account_key(
+    account_info: &AccountInfo,
+    expected: &Pubkey,
+    error: MetadataError,
+) -> ProgramResult {
+    if account_info.key != expected {
+        return Err(error.into());
+    }
+
+    Ok(())
+}
+
 pub fn assert_update_authority_is_correct(
     metadata: &Metadata,
     update_authority_info: &AccountInfo,
@@ -364,6 +554,18 @@ pub fn assert_mint_authority_matches_mint(
     Ok(())
 }
 
+/// Assert the mint in `mint_info` can currently be minted by `expected`. A mint without a
+/// mint authority never matches.
+pub fn assert_mint_authority(mint_info: &AccountInfo, expected: &Pubkey) -> ProgramResult {
+    assert_owned_by(mint_info, &spl_token::id())?;
+    let mint: Mint = assert_initialized(mint_info)?;
+
+    match mint.mint_authority {
+        COption::Some(authority) if authority == *expected => Ok(()),
+        _ => Err(MetadataError::InvalidMintAuthority.into()),
+    }
+}
+
 pub fn assert_supply_invariance(
     master_edition: &MasterEditionV1,
     printing_mint: &Mint,
@@ -446,6 +648,22 @@ pub fn assert_rent_exempt(rent: &Rent, account_info: &AccountInfo) -> ProgramRes
     }
 }
 
+/// Copy `data` to the start of the account data, failing instead of truncating it when the
+/// account is smaller than `data`.
+pub fn write_account_data(account_info: &AccountInfo, data: &[u8]) -> ProgramResult {
+    let mut account_data = account_info
+        .try_borrow_mut_data()
+        .map_err(|_| MetadataError::DataBorrowFailed)?;
+
+    if account_data.len() < data.len() {
+        return Err(MetadataError::InvalidAccountSize.into());
+    }
+
+    sol_memcpy(&mut account_data, data, data.len());
+
+    Ok(())
+}
+
 // Todo deprecate this for assert derivation
 pub fn assert_edition_valid(
     program_id: &Pubkey,
@@ -823,6 +1041,66 @@ pub fn spl_token_close(params: TokenCloseParams<'_, '_>) -> ProgramResult {
     result.map_err(|_| MetadataError::TokenCloseFailed.into())
 }
 
+/// Transfer `amount` tokens from `source` to `destination`, signing with
+/// `authority_signer_seeds` when the authority is a PDA. Errors from the token program are
+/// returned as they are.
+pub fn transfer_spl(params: TokenTransferParams<'_, '_>) -> ProgramResult {
+    let instruction = token_transfer_instruction(&params)?;
+
+    let TokenTransferParams {
+        source,
+        destination,
+        authority,
+        token_program,
+        authority_signer_seeds,
+        ..
+    } = params;
+    let mut seeds: Vec<&[&[u8]]> = vec![];
+    if let Some(seed) = authority_signer_seeds {
+        seeds.push(seed);
+    }
+
+    invoke_signed(
+        &instruction,
+        &[source, destination, authority, token_program],
+        seeds.as_slice(),
+    )
+}
+
+/// The token program instruction `transfer_spl` invokes, rejecting empty transfers.
+pub(crate) fn token_transfer_instruction(
+    params: &TokenTransferParams,
+) -> Result<Instruction, ProgramError> {
+    if params.amount == 0 {
+        return Err(MetadataError::InvalidTransferAmount.into());
+    }
+
+    spl_token::instruction::transfer(
+        &spl_token::id(),
+        params.source.key,
+        params.destination.key,
+        params.authority.key,
+        &[],
+        params.amount,
+    )
+}
+
+/// TokenTransferParams
+pub struct TokenTransferParams<'a: 'b, 'b> {
+    /// source
+    pub source: AccountInfo<'a>,
+    /// destination
+    pub destination: AccountInfo<'a>,
+    /// amount
+    pub amount: u64,
+    /// authority
+    pub authority: AccountInfo<'a>,
+    /// authority_signer_seeds
+    pub authority_signer_seeds: Option<&'b [&'b [u8]]>,
+    /// token_program
+    pub token_program: AccountInfo<'a>,
+}
+
 /// TokenBurnParams
 pub struct TokenBurnParams<'a: 'b, 'b> {
     /// mint
@@ -909,6 +1187,16 @@ pub fn assert_derivation(
     Ok(bump)
 }
 
+/// Like `assert_derivation`, but returns the bump as the seed that completes `path` into the
+/// signer seeds of the account.
+pub fn assert_derivation_bump_seed(
+    program_id: &Pubkey,
+    account: &AccountInfo,
+    path: &[&[u8]],
+) -> Result<[u8; 1], ProgramError> {
+    Ok([assert_derivation(program_id, account, path)?])
+}
+
 pub fn assert_signer(account_info: &AccountInfo) -> ProgramResult {
     if !account_info.is_signer {
         Err(ProgramError::MissingRequiredSignature)
@@ -1425,6 +1713,13 @@ pub fn assert_verified_member_of_collection(
     Ok(())
 }
 
+/// Whether `edition_account_info` stands in for an edition that doesn't exist: an account
+/// that was never created, or the system program, which is also `Pubkey::default()`.
+pub fn is_missing_edition(edition_account_info: &AccountInfo) -> bool {
+    edition_account_info.data_is_empty()
+        || *edition_account_info.key == solana_program::system_program::id()
+}
+
 pub fn check_token_standard(
     mint_info: &AccountInfo,
     edition_account_info: Option<&AccountInfo>,
@@ -1432,7 +1727,8 @@ pub fn check_token_standard(
     let mint_decimals = get_mint_decimals(mint_info)?;
     let mint_supply = get_mint_supply(mint_info)?;
 
-    match edition_account_info {
+    // A missing edition is detected the same way as no edition being passed at all.
+    match edition_account_info.filter(|edition| !is_missing_edition(edition)) {
         Some(edition) => {
             if is_master_edition(edition, mint_decimals, mint_supply) {
                 Ok(TokenStandard::NonFungible)
@@ -86,3 +86,1122 @@ mod try_from_slice_checked {
         assert_eq!(metadata, expected_metadata);
     }
 }
+
+mod escrow_derivation {
+    use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+    use crate::{
+        error::MetadataError,
+        escrow::{
+            assert_escrow_derivation, assert_escrow_derivation_tagged, escrow_signer_seeds,
+            escrow_signer_seeds_tagged, find_escrow_account, find_escrow_account_tagged,
+            find_escrow_attribute_ata, find_escrow_seeds, find_escrow_seeds_tagged,
+            find_escrow_seeds_versioned, ESCROW_DEFAULT_TAG, ESCROW_SEEDS_V1,
+        },
+        state::{EscrowAuthority, ESCROW_POSTFIX, ESCROW_TAG, PREFIX},
+        utils::assert_derivation,
+        ID,
+    };
+
+    fn assert_parity(authority: EscrowAuthority) {
+        let mint = Pubkey::new_unique();
+        let (escrow, bump) = find_escrow_account(&mint, &authority);
+
+        let mut lamports = 0;
+        let mut data = vec![];
+        let owner = solana_program::system_program::id();
+        let escrow_info = AccountInfo::new(
+            &escrow,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        let seeds = find_escrow_seeds(&mint, &authority);
+        let derived_bump = assert_derivation(&ID, &escrow_info, &seeds).unwrap();
+
+        assert_eq!(derived_bump, bump);
+    }
+
+    #[test]
+    fn token_owner_escrow_matches_assert_derivation() {
+        assert_parity(EscrowAuthority::TokenOwner);
+    }
+
+    #[test]
+    fn creator_escrow_matches_assert_derivation() {
+        assert_parity(EscrowAuthority::Creator(Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn escrow_differs_between_authorities() {
+        let mint = Pubkey::new_unique();
+        let (token_owner_escrow, _) = find_escrow_account(&mint, &EscrowAuthority::TokenOwner);
+        let (creator_escrow, _) =
+            find_escrow_account(&mint, &EscrowAuthority::Creator(Pubkey::new_unique()));
+
+        assert_ne!(token_owner_escrow, creator_escrow);
+    }
+
+    #[test]
+    fn v1_seeds_match_original_derivation() {
+        let mint = Pubkey::new_unique();
+        let creator = Pubkey::new_unique();
+
+        let token_owner_seeds: &[&[u8]] = &[
+            PREFIX.as_bytes(),
+            ID.as_ref(),
+            mint.as_ref(),
+            &[0],
+            ESCROW_POSTFIX.as_bytes(),
+        ];
+        let creator_seeds: &[&[u8]] = &[
+            PREFIX.as_bytes(),
+            ID.as_ref(),
+            mint.as_ref(),
+            &[1],
+            creator.as_ref(),
+            ESCROW_POSTFIX.as_bytes(),
+        ];
+
+        assert_eq!(
+            find_escrow_seeds_versioned(&mint, &EscrowAuthority::TokenOwner, ESCROW_SEEDS_V1),
+            token_owner_seeds
+        );
+        assert_eq!(
+            find_escrow_seeds(&mint, &EscrowAuthority::Creator(creator)),
+            creator_seeds
+        );
+        assert_eq!(
+            find_escrow_account(&mint, &EscrowAuthority::TokenOwner),
+            Pubkey::find_program_address(token_owner_seeds, &ID)
+        );
+    }
+
+    #[test]
+    fn seed_constants_match_literal_seeds() {
+        // Every escrow address depends on these bytes, so the constants must never change.
+        assert_eq!(PREFIX, "metadata");
+        assert_eq!(ESCROW_POSTFIX, "escrow");
+        assert_eq!(ESCROW_TAG, "tag");
+
+        let mint = Pubkey::new_unique();
+        let literal_seeds: &[&[u8]] = &[b"metadata", ID.as_ref(), mint.as_ref(), &[0], b"escrow"];
+
+        assert_eq!(
+            find_escrow_account(&mint, &EscrowAuthority::TokenOwner),
+            Pubkey::find_program_address(literal_seeds, &ID)
+        );
+    }
+
+    #[test]
+    fn escrow_differs_between_seed_versions() {
+        let mint = Pubkey::new_unique();
+        let authority = EscrowAuthority::TokenOwner;
+
+        let v1_seeds = find_escrow_seeds_versioned(&mint, &authority, ESCROW_SEEDS_V1);
+        let v2_seeds = find_escrow_seeds_versioned(&mint, &authority, 2);
+
+        let (v1_escrow, _) = Pubkey::find_program_address(&v1_seeds, &ID);
+        let (v2_escrow, _) = Pubkey::find_program_address(&v2_seeds, &ID);
+
+        assert_ne!(v1_escrow, v2_escrow);
+        assert_eq!(*v2_seeds.last().unwrap(), &[2u8][..]);
+    }
+
+    #[test]
+    fn default_tag_matches_untagged_derivation() {
+        let mint = Pubkey::new_unique();
+
+        for authority in [
+            EscrowAuthority::TokenOwner,
+            EscrowAuthority::Creator(Pubkey::new_unique()),
+        ] {
+            assert_eq!(
+                find_escrow_seeds_tagged(&mint, &authority, ESCROW_DEFAULT_TAG),
+                find_escrow_seeds(&mint, &authority)
+            );
+            assert_eq!(
+                find_escrow_account_tagged(&mint, &authority, ESCROW_DEFAULT_TAG),
+                find_escrow_account(&mint, &authority)
+            );
+        }
+    }
+
+    #[test]
+    fn escrow_differs_between_tags() {
+        let mint = Pubkey::new_unique();
+        let authority = EscrowAuthority::TokenOwner;
+
+        let escrows: Vec<Pubkey> = [ESCROW_DEFAULT_TAG, 1, 2, u8::MAX]
+            .iter()
+            .map(|tag| find_escrow_account_tagged(&mint, &authority, *tag).0)
+            .collect();
+
+        for (i, escrow) in escrows.iter().enumerate() {
+            assert!(!escrows[i + 1..].contains(escrow));
+        }
+
+        let seeds = find_escrow_seeds_tagged(&mint, &authority, 1);
+        assert_eq!(
+            seeds[seeds.len() - 2..],
+            [ESCROW_TAG.as_bytes(), &[1u8][..]]
+        );
+    }
+
+    #[test]
+    fn tagged_escrow_differs_from_seed_version() {
+        let mint = Pubkey::new_unique();
+        let authority = EscrowAuthority::TokenOwner;
+
+        let versioned_seeds = find_escrow_seeds_versioned(&mint, &authority, 2);
+        let (versioned_escrow, _) = Pubkey::find_program_address(&versioned_seeds, &ID);
+
+        assert_ne!(
+            find_escrow_account_tagged(&mint, &authority, 2).0,
+            versioned_escrow
+        );
+    }
+
+    #[test]
+    fn tagged_escrow_derivation_checks_the_tag() {
+        let mint = Pubkey::new_unique();
+        let authority = EscrowAuthority::TokenOwner;
+        let (escrow, bump) = find_escrow_account_tagged(&mint, &authority, 1);
+
+        let mut lamports = 0;
+        let mut data = vec![];
+        let owner = solana_program::system_program::id();
+        let escrow_info = AccountInfo::new(
+            &escrow,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            assert_escrow_derivation_tagged(&escrow_info, &mint, &authority, 1).unwrap(),
+            bump
+        );
+        for tag in [ESCROW_DEFAULT_TAG, 2] {
+            assert_eq!(
+                assert_escrow_derivation_tagged(&escrow_info, &mint, &authority, tag).unwrap_err(),
+                MetadataError::DerivedKeyInvalid.into()
+            );
+        }
+        assert_eq!(
+            assert_escrow_derivation(&escrow_info, &mint, &authority).unwrap_err(),
+            MetadataError::DerivedKeyInvalid.into()
+        );
+    }
+
+    #[test]
+    fn tagged_escrow_signer_seeds_reproduce_escrow_address() {
+        let mint = Pubkey::new_unique();
+        let authority = EscrowAuthority::TokenOwner;
+        let (escrow, bump) = find_escrow_account_tagged(&mint, &authority, 1);
+        let bump_seed = [bump];
+        let seeds = escrow_signer_seeds_tagged(&mint, &authority, 1, &bump_seed);
+
+        assert_eq!(Pubkey::create_program_address(&seeds, &ID).unwrap(), escrow);
+    }
+
+    #[test]
+    fn escrow_attribute_ata_is_associated_token_address() {
+        let (escrow, _) = find_escrow_account(&Pubkey::new_unique(), &EscrowAuthority::TokenOwner);
+        let attribute_mint = Pubkey::new_unique();
+
+        assert_eq!(
+            find_escrow_attribute_ata(&escrow, &attribute_mint),
+            spl_associated_token_account::get_associated_token_address(&escrow, &attribute_mint)
+        );
+    }
+
+    #[test]
+    fn escrow_signer_seeds_reproduce_escrow_address() {
+        let mint = Pubkey::new_unique();
+
+        for authority in [
+            EscrowAuthority::TokenOwner,
+            EscrowAuthority::Creator(Pubkey::new_unique()),
+        ] {
+            let (escrow, bump) = find_escrow_account(&mint, &authority);
+            let bump_seed = [bump];
+            let seeds = escrow_signer_seeds(&mint, &authority, &bump_seed);
+
+            assert_eq!(Pubkey::create_program_address(&seeds, &ID).unwrap(), escrow);
+        }
+    }
+
+    #[test]
+    fn assert_escrow_derivation_returns_bump() {
+        let mint = Pubkey::new_unique();
+        let authority = EscrowAuthority::Creator(Pubkey::new_unique());
+        let (escrow, bump) = find_escrow_account(&mint, &authority);
+
+        let mut lamports = 0;
+        let mut data = vec![];
+        let owner = solana_program::system_program::id();
+        let escrow_info = AccountInfo::new(
+            &escrow,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            assert_escrow_derivation(&escrow_info, &mint, &authority).unwrap(),
+            bump
+        );
+
+        // The token owner escrow of the same mint lives at another address.
+        let err = assert_escrow_derivation(&escrow_info, &mint, &EscrowAuthority::TokenOwner)
+            .unwrap_err();
+        assert_eq!(err, MetadataError::DerivedKeyInvalid.into());
+    }
+}
+
+mod set_creator_verified {
+    use solana_program::pubkey::Pubkey;
+
+    use crate::{error::MetadataError, state::Creator, utils::set_creator_verified};
+
+    fn creators() -> Vec<Creator> {
+        vec![
+            Creator {
+                address: Pubkey::new_unique(),
+                verified: false,
+                share: 60,
+            },
+            Creator {
+                address: Pubkey::new_unique(),
+                verified: false,
+                share: 40,
+            },
+        ]
+    }
+
+    #[test]
+    fn verifies_only_the_matching_creator() {
+        let expected = creators();
+        let mut creators = Some(expected.clone());
+
+        set_creator_verified(&mut creators, &expected[1].address, true).unwrap();
+
+        let creators = creators.unwrap();
+        assert!(!creators[0].verified);
+        assert!(creators[1].verified);
+        assert_eq!(creators[0].share, expected[0].share);
+        assert_eq!(creators[1].share, expected[1].share);
+    }
+
+    #[test]
+    fn unverifies_only_the_matching_creator() {
+        let mut expected = creators();
+        expected[0].verified = true;
+        expected[1].verified = true;
+        let mut creators = Some(expected.clone());
+
+        set_creator_verified(&mut creators, &expected[0].address, false).unwrap();
+
+        let creators = creators.unwrap();
+        assert!(!creators[0].verified);
+        assert!(creators[1].verified);
+        assert_eq!(creators.len(), expected.len());
+    }
+
+    #[test]
+    fn fails_when_creator_is_missing() {
+        let mut creators = Some(creators());
+
+        let error = set_creator_verified(&mut creators, &Pubkey::new_unique(), true).unwrap_err();
+        assert_eq!(error, MetadataError::CreatorNotFound.into());
+    }
+
+    #[test]
+    fn fails_when_no_creators_are_present() {
+        let error = set_creator_verified(&mut None, &Pubkey::new_unique(), true).unwrap_err();
+        assert_eq!(error, MetadataError::NoCreatorsPresentOnMetadata.into());
+
+        let error =
+            set_creator_verified(&mut Some(vec![]), &Pubkey::new_unique(), true).unwrap_err();
+        assert_eq!(error, MetadataError::NoCreatorsPresentOnMetadata.into());
+    }
+}
+
+mod validate_seller_fee {
+    use crate::{
+        error::MetadataError,
+        utils::{validate_seller_fee, MAX_SELLER_FEE_BASIS_POINTS},
+    };
+
+    #[test]
+    fn accepts_up_to_max_basis_points() {
+        validate_seller_fee(0).unwrap();
+        validate_seller_fee(MAX_SELLER_FEE_BASIS_POINTS).unwrap();
+    }
+
+    #[test]
+    fn rejects_above_max_basis_points() {
+        let error = validate_seller_fee(MAX_SELLER_FEE_BASIS_POINTS + 1).unwrap_err();
+        assert_eq!(error, MetadataError::InvalidBasisPoints.into());
+    }
+}
+
+mod validate_creator_shares {
+    use solana_program::pubkey::Pubkey;
+
+    use crate::{error::MetadataError, state::Creator, utils::validate_creator_shares};
+
+    fn creators(shares: &[u8]) -> Vec<Creator> {
+        shares
+            .iter()
+            .map(|share| Creator {
+                address: Pubkey::new_unique(),
+                verified: false,
+                share: *share,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accepts_shares_totalling_100() {
+        validate_creator_shares(&creators(&[60, 40])).unwrap();
+    }
+
+    #[test]
+    fn rejects_shares_totalling_99() {
+        let error = validate_creator_shares(&creators(&[60, 39])).unwrap_err();
+        assert_eq!(error, MetadataError::ShareTotalMustBe100.into());
+    }
+
+    #[test]
+    fn rejects_shares_totalling_101() {
+        let error = validate_creator_shares(&creators(&[60, 41])).unwrap_err();
+        assert_eq!(error, MetadataError::ShareTotalMustBe100.into());
+    }
+
+    #[test]
+    fn rejects_overflowing_shares() {
+        let error = validate_creator_shares(&creators(&[200, 100])).unwrap_err();
+        assert_eq!(error, MetadataError::NumericalOverflowError.into());
+    }
+
+    #[test]
+    fn rejects_duplicate_creators() {
+        let mut creators = creators(&[50, 50]);
+        creators[1].address = creators[0].address;
+
+        let error = validate_creator_shares(&creators).unwrap_err();
+        assert_eq!(error, MetadataError::DuplicateCreatorAddress.into());
+    }
+}
+
+mod assert_account_key {
+    use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+    use crate::{error::MetadataError, utils::assert_account_key};
+
+    #[test]
+    fn matches_expected_key() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![];
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        assert_account_key(&account_info, &key, MetadataError::MintMismatch).unwrap();
+    }
+
+    #[test]
+    fn returns_passed_error_on_mismatch() {
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![];
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        let error = assert_account_key(
+            &account_info,
+            &Pubkey::new_unique(),
+            MetadataError::InvalidRentRecipient,
+        )
+        .unwrap_err();
+        assert_eq!(error, MetadataError::InvalidRentRecipient.into());
+    }
+}
+
+mod checked_minimum_balance {
+    use solana_program::rent::Rent;
+
+    use crate::{error::MetadataError, utils::checked_minimum_balance};
+
+    #[test]
+    fn matches_rent_minimum_balance() {
+        let rent = Rent::default();
+
+        for size in [0, 1, 165, 679, 10 * 1024 * 1024] {
+            assert_eq!(
+                checked_minimum_balance(&rent, size).unwrap(),
+                rent.minimum_balance(size)
+            );
+        }
+    }
+
+    #[test]
+    fn fail_on_oversized_account() {
+        let rent = Rent {
+            lamports_per_byte_year: 1_000_000,
+            ..Rent::default()
+        };
+
+        let err = checked_minimum_balance(&rent, usize::MAX / 2).unwrap_err();
+        assert_eq!(err, MetadataError::NumericalOverflowError.into());
+    }
+}
+
+mod assert_token_owned_by {
+    use solana_program::{
+        account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_token::state::{Account, AccountState};
+
+    use crate::{error::MetadataError, utils::assert_token_owned_by};
+
+    fn check(
+        token_account: Account,
+        mint: &Pubkey,
+        owner: Option<&Pubkey>,
+    ) -> Result<Account, ProgramError> {
+        let mut data = vec![0; Account::LEN];
+        Account::pack(token_account, &mut data).unwrap();
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &spl_token::ID,
+            false,
+            0,
+        );
+
+        assert_token_owned_by(&account_info, mint, owner, 1)
+    }
+
+    fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+        Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn accepts_owned_token() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let account = check(token_account(&mint, &owner, 1), &mint, Some(&owner)).unwrap();
+        assert_eq!(account.owner, owner);
+        assert!(check(token_account(&mint, &owner, 1), &mint, None).is_ok());
+    }
+
+    #[test]
+    fn rejects_other_mint() {
+        let owner = Pubkey::new_unique();
+        let account = token_account(&Pubkey::new_unique(), &owner, 1);
+
+        let err = check(account, &Pubkey::new_unique(), Some(&owner)).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidTokenAccountMint.into());
+    }
+
+    #[test]
+    fn rejects_other_owner() {
+        let mint = Pubkey::new_unique();
+        let account = token_account(&mint, &Pubkey::new_unique(), 1);
+
+        let err = check(account, &mint, Some(&Pubkey::new_unique())).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidOwner.into());
+    }
+
+    #[test]
+    fn rejects_empty_token_account() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let err = check(token_account(&mint, &owner, 0), &mint, Some(&owner)).unwrap_err();
+        assert_eq!(err, MetadataError::NotEnoughTokens.into());
+    }
+}
+
+mod assert_escrow_empty {
+    use solana_program::{
+        account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_token::state::{Account, AccountState};
+
+    use crate::{error::MetadataError, utils::assert_escrow_empty};
+
+    fn check(escrow: &Pubkey, token_accounts: Vec<Account>) -> Result<(), ProgramError> {
+        let mut keys = vec![];
+        let mut lamports = vec![];
+        let mut data = vec![];
+        for token_account in token_accounts {
+            let mut account_data = vec![0; Account::LEN];
+            Account::pack(token_account, &mut account_data).unwrap();
+
+            keys.push(Pubkey::new_unique());
+            lamports.push(0);
+            data.push(account_data);
+        }
+
+        let account_infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| {
+                AccountInfo::new(key, false, false, lamports, data, &spl_token::ID, false, 0)
+            })
+            .collect();
+
+        assert_escrow_empty(escrow, &account_infos)
+    }
+
+    fn token_account(owner: &Pubkey, amount: u64) -> Account {
+        Account {
+            mint: Pubkey::new_unique(),
+            owner: *owner,
+            amount,
+            state: AccountState::Initialized,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_escrow() {
+        let escrow = Pubkey::new_unique();
+
+        assert!(check(&escrow, vec![]).is_ok());
+        assert!(check(
+            &escrow,
+            vec![token_account(&escrow, 0), token_account(&escrow, 0)]
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn rejects_escrow_holding_tokens() {
+        let escrow = Pubkey::new_unique();
+
+        let err = check(
+            &escrow,
+            vec![token_account(&escrow, 0), token_account(&escrow, 1)],
+        )
+        .unwrap_err();
+        assert_eq!(err, MetadataError::EscrowNotEmpty.into());
+    }
+
+    #[test]
+    fn rejects_account_of_another_owner() {
+        let escrow = Pubkey::new_unique();
+
+        let err = check(&escrow, vec![token_account(&Pubkey::new_unique(), 0)]).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidOwner.into());
+    }
+}
+
+mod effective_token_controller {
+    use solana_program::{program_option::COption, pubkey::Pubkey};
+    use spl_token::state::{Account, AccountState};
+
+    use crate::utils::{effective_token_controller, is_token_holder};
+
+    fn token_account(owner: &Pubkey, delegate: Option<&Pubkey>, delegated_amount: u64) -> Account {
+        Account {
+            mint: Pubkey::new_unique(),
+            owner: *owner,
+            amount: 1,
+            delegate: delegate.copied().into(),
+            state: AccountState::Frozen,
+            delegated_amount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn owner_without_delegate() {
+        let owner = Pubkey::new_unique();
+
+        let account = token_account(&owner, None, 0);
+        assert_eq!(account.delegate, COption::None);
+        assert_eq!(effective_token_controller(&account), owner);
+    }
+
+    #[test]
+    fn delegate_of_full_amount() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+
+        let account = token_account(&owner, Some(&delegate), 1);
+        assert_eq!(effective_token_controller(&account), delegate);
+    }
+
+    #[test]
+    fn owner_with_partial_delegate() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+
+        let mut account = token_account(&owner, Some(&delegate), 1);
+        account.amount = 2;
+        assert_eq!(effective_token_controller(&account), owner);
+    }
+
+    #[test]
+    fn holder_is_owner_or_controlling_delegate() {
+        let owner = Pubkey::new_unique();
+        let delegate = Pubkey::new_unique();
+
+        let account = token_account(&owner, Some(&delegate), 1);
+        assert!(is_token_holder(&account, &owner));
+        assert!(is_token_holder(&account, &delegate));
+        assert!(!is_token_holder(&account, &Pubkey::new_unique()));
+
+        let mut account = token_account(&owner, Some(&delegate), 1);
+        account.amount = 2;
+        assert!(!is_token_holder(&account, &delegate));
+    }
+}
+
+mod assert_mint_authority {
+    use solana_program::{
+        account_info::AccountInfo, program_error::ProgramError, program_option::COption,
+        program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_token::state::Mint;
+
+    use crate::{error::MetadataError, utils::assert_mint_authority};
+
+    fn check(mint_authority: COption<Pubkey>, expected: &Pubkey) -> Result<(), ProgramError> {
+        let mint = Mint {
+            mint_authority,
+            supply: 1,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(mint, &mut data).unwrap();
+
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mint_info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &spl_token::ID,
+            false,
+            0,
+        );
+
+        assert_mint_authority(&mint_info, expected)
+    }
+
+    #[test]
+    fn matching_authority() {
+        let authority = Pubkey::new_unique();
+
+        assert!(check(COption::Some(authority), &authority).is_ok());
+    }
+
+    #[test]
+    fn mismatched_authority() {
+        let err = check(COption::Some(Pubkey::new_unique()), &Pubkey::new_unique()).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidMintAuthority.into());
+    }
+
+    #[test]
+    fn no_authority() {
+        let err = check(COption::None, &Pubkey::new_unique()).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidMintAuthority.into());
+    }
+}
+
+mod token_transfer_instruction {
+    use solana_program::{account_info::AccountInfo, instruction::AccountMeta, pubkey::Pubkey};
+
+    use crate::{
+        error::MetadataError,
+        utils::{token_transfer_instruction, TokenTransferParams},
+    };
+
+    #[test]
+    fn builds_spl_token_transfer() {
+        let keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut lamports = [0; 4];
+        let mut data: [Vec<u8>; 4] = Default::default();
+        let [source_lamports, destination_lamports, authority_lamports, program_lamports] =
+            &mut lamports;
+        let [source_data, destination_data, authority_data, program_data] = &mut data;
+        let owner = spl_token::id();
+
+        let params = TokenTransferParams {
+            source: AccountInfo::new(
+                &keys[0],
+                false,
+                true,
+                source_lamports,
+                source_data,
+                &owner,
+                false,
+                0,
+            ),
+            destination: AccountInfo::new(
+                &keys[1],
+                false,
+                true,
+                destination_lamports,
+                destination_data,
+                &owner,
+                false,
+                0,
+            ),
+            amount: 7,
+            authority: AccountInfo::new(
+                &keys[2],
+                true,
+                false,
+                authority_lamports,
+                authority_data,
+                &owner,
+                false,
+                0,
+            ),
+            authority_signer_seeds: None,
+            token_program: AccountInfo::new(
+                &owner,
+                false,
+                false,
+                program_lamports,
+                program_data,
+                &owner,
+                true,
+                0,
+            ),
+        };
+
+        let instruction = token_transfer_instruction(&params).unwrap();
+        assert_eq!(instruction.program_id, spl_token::id());
+        assert_eq!(
+            instruction.accounts,
+            vec![
+                AccountMeta::new(keys[0], false),
+                AccountMeta::new(keys[1], false),
+                AccountMeta::new_readonly(keys[2], true),
+            ]
+        );
+
+        let zero = TokenTransferParams {
+            amount: 0,
+            ..params
+        };
+        let err = token_transfer_instruction(&zero).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidTransferAmount.into());
+    }
+}
+
+mod assert_multisig_signers {
+    use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+
+    use crate::{error::MetadataError, state::EscrowMultisig, utils::assert_multisig_signers};
+
+    fn check(signed: [bool; 3]) -> Result<(), ProgramError> {
+        let multisig = EscrowMultisig::new(
+            vec![
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+            2,
+        )
+        .unwrap();
+        let owner = Pubkey::new_unique();
+        let mut lamports = [0; 3];
+        let mut data: [Vec<u8>; 3] = Default::default();
+
+        let member_infos: Vec<AccountInfo> = multisig
+            .members
+            .iter()
+            .zip(signed)
+            .zip(lamports.iter_mut().zip(data.iter_mut()))
+            .map(|((member, is_signer), (lamports, data))| {
+                AccountInfo::new(member, is_signer, false, lamports, data, &owner, false, 0)
+            })
+            .collect();
+
+        assert_multisig_signers(&multisig, &member_infos)
+    }
+
+    #[test]
+    fn threshold_met() {
+        assert!(check([true, false, true]).is_ok());
+        assert!(check([true, true, true]).is_ok());
+    }
+
+    #[test]
+    fn threshold_not_met() {
+        let err = check([false, true, false]).unwrap_err();
+        assert_eq!(err, MetadataError::NotEnoughMultisigSigners.into());
+    }
+}
+
+mod check_token_standard {
+    use solana_program::{
+        account_info::AccountInfo, program_option::COption, program_pack::Pack, pubkey::Pubkey,
+        system_program,
+    };
+    use spl_token::state::Mint;
+
+    use crate::{state::TokenStandard, utils::check_token_standard};
+
+    fn standard_with_edition(
+        decimals: u8,
+        edition_key: Pubkey,
+        edition_data: &mut [u8],
+    ) -> TokenStandard {
+        let mint = Mint {
+            mint_authority: COption::None,
+            supply: 1,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        let mut mint_data = vec![0; Mint::LEN];
+        Mint::pack(mint, &mut mint_data).unwrap();
+
+        let mint_key = Pubkey::new_unique();
+        let mut mint_lamports = 0;
+        let mint_info = AccountInfo::new(
+            &mint_key,
+            false,
+            false,
+            &mut mint_lamports,
+            &mut mint_data,
+            &spl_token::ID,
+            false,
+            0,
+        );
+
+        let mut edition_lamports = 0;
+        let edition_info = AccountInfo::new(
+            &edition_key,
+            false,
+            false,
+            &mut edition_lamports,
+            edition_data,
+            &system_program::ID,
+            false,
+            0,
+        );
+
+        check_token_standard(&mint_info, Some(&edition_info)).unwrap()
+    }
+
+    #[test]
+    fn empty_edition_is_detected_as_fungible() {
+        assert_eq!(
+            standard_with_edition(0, Pubkey::new_unique(), &mut []),
+            TokenStandard::FungibleAsset
+        );
+        assert_eq!(
+            standard_with_edition(6, Pubkey::new_unique(), &mut []),
+            TokenStandard::Fungible
+        );
+    }
+
+    #[test]
+    fn system_program_edition_is_detected_as_fungible() {
+        assert_eq!(
+            standard_with_edition(0, Pubkey::default(), &mut b"system_program".to_vec()),
+            TokenStandard::FungibleAsset
+        );
+    }
+}
+
+mod write_account_data {
+    use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+    use crate::{error::MetadataError, utils::write_account_data};
+
+    #[test]
+    fn copies_data_into_large_enough_account() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0; 4];
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &crate::ID,
+            false,
+            0,
+        );
+
+        write_account_data(&account_info, &[1, 2, 3]).unwrap();
+        assert_eq!(*account_info.data.borrow(), [1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn fail_to_write_into_undersized_account() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0; 2];
+        let account_info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &crate::ID,
+            false,
+            0,
+        );
+
+        let err = write_account_data(&account_info, &[1, 2, 3]).unwrap_err();
+        assert_eq!(err, MetadataError::InvalidAccountSize.into());
+        assert_eq!(*account_info.data.borrow(), [0, 0]);
+    }
+}
+
+mod assert_holds_base_token {
+    use crate::{error::MetadataError, state::TokenStandard, utils::assert_holds_base_token};
+
+    #[test]
+    fn non_fungible_must_be_held_exactly() {
+        let standard = Some(TokenStandard::NonFungible);
+
+        assert_eq!(
+            assert_holds_base_token(0, standard.clone()).unwrap_err(),
+            MetadataError::NotEnoughTokens.into()
+        );
+        assert!(assert_holds_base_token(1, standard.clone()).is_ok());
+        assert_eq!(
+            assert_holds_base_token(2, standard).unwrap_err(),
+            MetadataError::InvalidAmount.into()
+        );
+    }
+
+    #[test]
+    fn other_standards_only_need_a_token() {
+        for standard in [Some(TokenStandard::FungibleAsset), None] {
+            assert_eq!(
+                assert_holds_base_token(0, standard.clone()).unwrap_err(),
+                MetadataError::NotEnoughTokens.into()
+            );
+            assert!(assert_holds_base_token(1, standard.clone()).is_ok());
+            assert!(assert_holds_base_token(2, standard).is_ok());
+        }
+    }
+}
+
+mod assert_derivation_bump_seed {
+    use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+
+    use crate::{
+        error::MetadataError,
+        state::{EDITION, PREFIX},
+        utils::assert_derivation_bump_seed,
+        ID,
+    };
+
+    #[test]
+    fn bump_seed_completes_signer_seeds() {
+        let mint = Pubkey::new_unique();
+        let path: &[&[u8]] = &[
+            PREFIX.as_bytes(),
+            ID.as_ref(),
+            mint.as_ref(),
+            EDITION.as_bytes(),
+        ];
+        let (edition, bump) = Pubkey::find_program_address(path, &ID);
+
+        let mut lamports = 0;
+        let mut data = vec![];
+        let edition_info = AccountInfo::new(
+            &edition,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &ID,
+            false,
+            0,
+        );
+
+        let bump_seed = assert_derivation_bump_seed(&ID, &edition_info, path).unwrap();
+        assert_eq!(bump_seed, [bump]);
+
+        let mut seeds = path.to_vec();
+        seeds.push(&bump_seed);
+        assert_eq!(
+            Pubkey::create_program_address(&seeds, &ID).unwrap(),
+            edition
+        );
+    }
+
+    #[test]
+    fn fail_for_underived_account() {
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![];
+        let account_info =
+            AccountInfo::new(&key, false, false, &mut lamports, &mut data, &ID, false, 0);
+
+        let err =
+            assert_derivation_bump_seed(&ID, &account_info, &[PREFIX.as_bytes()]).unwrap_err();
+        assert_eq!(err, MetadataError::DerivedKeyInvalid.into());
+    }
+}
@@ -1,17 +1,44 @@
 #![cfg(feature = "test-bpf")]
 pub mod utils;
 
+use num_traits::FromPrimitive;
 use solana_program::borsh::try_from_slice_unchecked;
 use solana_program_test::*;
-use solana_sdk::{signer::Signer, transaction::Transaction};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
 use utils::*;
 
 mod escrow {
-    use mpl_token_metadata::{escrow::find_escrow_account, state::EscrowAuthority};
-    use solana_program::program_pack::Pack;
+    use borsh::BorshSerialize;
+    use mpl_token_metadata::{
+        error::MetadataError,
+        escrow::{
+            find_escrow_account, find_escrow_account_tagged, find_escrow_allowlist_account,
+            find_escrow_attribute_ata, find_escrow_treasury_account, find_program_data_account,
+            CreateEscrowAccountArgs, MAX_ESCROW_MEMO_LENGTH,
+        },
+        pda::find_master_edition_account,
+        state::{
+            EscrowAuthority, EscrowMultisig, Key, TokenOwnedEscrow, TokenStandard,
+            TOKEN_OWNED_ESCROW_VERSION,
+        },
+        utils::check_token_standard,
+    };
+    use solana_program::{
+        account_info::AccountInfo, bpf_loader_upgradeable, instruction::AccountMeta,
+        program_pack::Pack, pubkey::Pubkey,
+    };
+    use solana_sdk::account::{Account, AccountSharedData};
 
     use super::*;
 
+    // Built with the `bench` feature, `process_create_escrow_account` logs the remaining
+    // compute units on entry and on exit, so the cost of a create is the difference between
+    // the two "consumption" log lines printed by this test with `-- --nocapture`.
     #[tokio::test]
     async fn smoke_test_success() {
         let mut context = program_test().start_with_context().await;
@@ -55,6 +82,7 @@ mod escrow {
             parent_test_master_edition.pubkey,
             context.payer.pubkey(),
             None,
+            CreateEscrowAccountArgs::default(),
         );
         println!("{:?} {:?}", &context.payer, &parent_test_metadata.token);
 
@@ -166,6 +194,7 @@ mod escrow {
         let ix2 = mpl_token_metadata::escrow::transfer_out_of_escrow(
             mpl_token_metadata::id(),
             escrow_address.0,
+            parent_test_metadata.pubkey,
             context.payer.pubkey(),
             attribute_test_metadata.mint.pubkey(),
             escrow_attribute_token_account,
@@ -208,4 +237,5548 @@ mod escrow {
         assert!(attribute_dst.owner == context.payer.pubkey());
         println!("{:#?}", attribute_dst);
     }
+
+    #[tokio::test]
+    async fn close_escrow_account_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                true,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+            None,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address.0)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    #[tokio::test]
+    async fn create_escrow_accounts_batch_success() {
+        let mut context = program_test().start_with_context().await;
+        let authority = Keypair::new();
+
+        let mut entries = vec![];
+        for _ in 0..2 {
+            let test_metadata = Metadata::new();
+            let test_master_edition = MasterEditionV2::new(&test_metadata);
+            test_metadata
+                .create_v2(
+                    &mut context,
+                    "Test".to_string(),
+                    "TST".to_string(),
+                    "uri".to_string(),
+                    None,
+                    10,
+                    true,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+
+            test_master_edition
+                .create_v3(&mut context, Some(0))
+                .await
+                .unwrap();
+
+            entries.push((
+                test_metadata.mint.pubkey(),
+                test_metadata.pubkey,
+                test_metadata.token.pubkey(),
+                test_master_edition.pubkey,
+                context.payer.pubkey(),
+            ));
+        }
+
+        let ix = mpl_token_metadata::escrow::create_escrow_accounts_batch(
+            mpl_token_metadata::id(),
+            context.payer.pubkey(),
+            authority.pubkey(),
+            &entries,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        for (mint, _, _, _, _) in entries {
+            let escrow_address =
+                find_escrow_account(&mint, &EscrowAuthority::Creator(authority.pubkey()));
+            let escrow_account = get_account(&mut context, &escrow_address.0).await;
+            let escrow: mpl_token_metadata::state::TokenOwnedEscrow =
+                try_from_slice_unchecked(&escrow_account.data).unwrap();
+
+            assert_eq!(escrow.base_token, mint);
+            assert_eq!(
+                escrow.authority,
+                EscrowAuthority::Creator(authority.pubkey())
+            );
+            assert_eq!(escrow.bump, escrow_address.1);
+        }
+    }
+
+    #[tokio::test]
+    async fn create_escrow_accounts_batch_for_held_token() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        // The payer holds both NFTs; the first entry is created by the holder itself.
+        let mut entries = vec![];
+        for _ in 0..2 {
+            let test_metadata = Metadata::new();
+            let test_master_edition = MasterEditionV2::new(&test_metadata);
+            test_metadata.create_v2_default(&mut context).await.unwrap();
+            test_master_edition
+                .create_v3(&mut context, Some(0))
+                .await
+                .unwrap();
+
+            entries.push((
+                test_metadata.mint.pubkey(),
+                test_metadata.pubkey,
+                test_metadata.token.pubkey(),
+                test_master_edition.pubkey,
+                context.payer.pubkey(),
+            ));
+        }
+
+        let holder = context.payer.pubkey();
+        let held_ix = mpl_token_metadata::escrow::create_escrow_accounts_batch(
+            mpl_token_metadata::id(),
+            holder,
+            holder,
+            &entries[..1],
+        );
+        let creator_ix = mpl_token_metadata::escrow::create_escrow_accounts_batch(
+            mpl_token_metadata::id(),
+            holder,
+            creator.pubkey(),
+            &entries[1..],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[held_ix, creator_ix],
+            Some(&holder),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        for ((mint, _, _, _, _), authority) in entries.iter().zip([
+            EscrowAuthority::TokenOwner,
+            EscrowAuthority::Creator(creator.pubkey()),
+        ]) {
+            let escrow_address = find_escrow_account(mint, &authority);
+            let escrow_account = get_account(&mut context, &escrow_address.0).await;
+            let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+
+            assert_eq!(escrow.base_token, *mint);
+            assert_eq!(escrow.authority, authority);
+        }
+    }
+
+    #[tokio::test]
+    async fn create_fungible_asset_escrow_requires_flag() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+
+        // Fix the supply at a single token.
+        let revoke_ix = spl_token::instruction::set_authority(
+            &spl_token::id(),
+            &test_metadata.mint.pubkey(),
+            None,
+            spl_token::instruction::AuthorityType::MintTokens,
+            &context.payer.pubkey(),
+            &[&context.payer.pubkey()],
+        )
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[revoke_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let (edition, _) = find_master_edition_account(&test_metadata.mint.pubkey());
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let strict_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            edition,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[strict_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        assert!(context.banks_client.process_transaction(tx).await.is_err());
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            edition,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                allow_fungible_asset: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: mpl_token_metadata::state::TokenOwnedEscrow =
+            try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.base_token, test_metadata.mint.pubkey());
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_with_deposit_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &escrow_address.0,
+                &attribute_test_metadata.mint.pubkey(),
+            );
+        let attribute_dst_account =
+            get_account(&mut context, &escrow_attribute_token_account).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+
+        assert_eq!(attribute_dst.amount, 1);
+        assert_eq!(attribute_dst.owner, escrow_address.0);
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_depositing_base_token() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::CannotDepositBaseToken);
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_depositing_disallowed_mint() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        // The payer is the update authority of the parent NFT.
+        let allowlist_ix = mpl_token_metadata::escrow::set_escrow_allowlist(
+            mpl_token_metadata::id(),
+            find_escrow_allowlist_account(&context.payer.pubkey()).0,
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            vec![Pubkey::new_unique()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[allowlist_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::TokenNotAllowed);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_twice() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&ix),
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // Use a new blockhash so the second transaction isn't deduplicated.
+        let last_blockhash = context
+            .banks_client
+            .get_new_latest_blockhash(&context.last_blockhash)
+            .await
+            .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::AlreadyInitialized);
+    }
+
+    #[tokio::test]
+    async fn close_escrow_account_refunds_rent_recipient() {
+        let mut context = program_test().start_with_context().await;
+        let rent_recipient = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                rent_recipient: Some(rent_recipient.pubkey()),
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: mpl_token_metadata::state::TokenOwnedEscrow =
+            try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.rent_recipient, Some(rent_recipient.pubkey()));
+
+        // Any account other than the recorded recipient is rejected.
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+            Some(Keypair::new().pubkey()),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_custom_error!(error, MetadataError::InvalidRentRecipient);
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+            Some(rent_recipient.pubkey()),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let recipient_account = get_account(&mut context, &rent_recipient.pubkey()).await;
+        assert_eq!(recipient_account.lamports, escrow_account.lamports);
+    }
+
+    #[tokio::test]
+    async fn verify_escrow_authority_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let verify_ix = mpl_token_metadata::escrow::verify_escrow_authority(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, verify_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // A signer that doesn't hold the token is rejected.
+        let other = Keypair::new();
+        let verify_ix = mpl_token_metadata::escrow::verify_escrow_authority(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            other.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[verify_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &other],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::NotEscrowTokenOwner);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_tops_up_existing_lamports() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        // Seed the escrow address with part of its rent before it is created.
+        airdrop(&mut context, &escrow_address.0, 500_000)
+            .await
+            .unwrap();
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(
+            escrow_account.lamports,
+            rent.minimum_balance(escrow_account.data.len())
+        );
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_auto_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        // Move the NFT into the payer's associated token account.
+        let ata = spl_associated_token_account::get_associated_token_address(
+            &context.payer.pubkey(),
+            &test_metadata.mint.pubkey(),
+        );
+        #[allow(deprecated)]
+        let create_ata_ix = spl_associated_token_account::create_associated_token_account(
+            &context.payer.pubkey(),
+            &context.payer.pubkey(),
+            &test_metadata.mint.pubkey(),
+        );
+        let transfer_ix = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &test_metadata.token.pubkey(),
+            &ata,
+            &context.payer.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ata_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_auto(
+            mpl_token_metadata::id(),
+            test_metadata.mint.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+        );
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+        assert_eq!(ix.accounts[0].pubkey, escrow_address.0);
+        assert_eq!(ix.accounts[1].pubkey, test_metadata.pubkey);
+        assert_eq!(ix.accounts[3].pubkey, ata);
+        assert_eq!(ix.accounts[4].pubkey, test_master_edition.pubkey);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: mpl_token_metadata::state::TokenOwnedEscrow =
+            try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.authority, EscrowAuthority::TokenOwner);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_with_supply_of_two() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+
+        context.last_blockhash = context
+            .banks_client
+            .get_new_latest_blockhash(&context.last_blockhash)
+            .await
+            .unwrap();
+        let payer_pubkey = context.payer.pubkey();
+        mint_tokens(
+            &mut context,
+            &test_metadata.mint.pubkey(),
+            &test_metadata.token.pubkey(),
+            1,
+            &payer_pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+        let (edition, _) = find_master_edition_account(&test_metadata.mint.pubkey());
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            edition,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidMintForEscrow);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_with_decimals() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let payer_pubkey = context.payer.pubkey();
+        create_mint(&mut context, &test_metadata.mint, &payer_pubkey, None, 1)
+            .await
+            .unwrap();
+        create_token_account(
+            &mut context,
+            &test_metadata.token,
+            &test_metadata.mint.pubkey(),
+            &payer_pubkey,
+        )
+        .await
+        .unwrap();
+        mint_tokens(
+            &mut context,
+            &test_metadata.mint.pubkey(),
+            &test_metadata.token.pubkey(),
+            1,
+            &payer_pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let create_metadata_ix = mpl_token_metadata::instruction::create_metadata_accounts_v2(
+            mpl_token_metadata::id(),
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            "name".to_string(),
+            "symbol".to_string(),
+            "uri".to_string(),
+            None,
+            0,
+            false,
+            false,
+            None,
+            None,
+        );
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+        let (edition, _) = find_master_edition_account(&test_metadata.mint.pubkey());
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            edition,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                allow_fungible_asset: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_metadata_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidMintForEscrow);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_on_frozen_token() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        // The master edition is the freeze authority once it has been created.
+        let freeze_ix = mpl_token_metadata::instruction::freeze_delegated_account(
+            mpl_token_metadata::id(),
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            test_metadata.mint.pubkey(),
+        );
+        let approve_ix = spl_token::instruction::approve(
+            &spl_token::id(),
+            &test_metadata.token.pubkey(),
+            &context.payer.pubkey(),
+            &context.payer.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[approve_ix, freeze_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::LockedToken);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                allow_locked_token: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_as_full_delegate() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        // The delegate, not the owner's wallet, now controls the NFT.
+        let delegate = Keypair::new();
+        let freeze_ix = mpl_token_metadata::instruction::freeze_delegated_account(
+            mpl_token_metadata::id(),
+            delegate.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            test_metadata.mint.pubkey(),
+        );
+        let approve_ix = spl_token::instruction::approve(
+            &spl_token::id(),
+            &test_metadata.token.pubkey(),
+            &delegate.pubkey(),
+            &context.payer.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[approve_ix, freeze_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &delegate],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(delegate.pubkey()),
+            CreateEscrowAccountArgs {
+                require_known_authority: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &delegate],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.authority, EscrowAuthority::TokenOwner);
+
+        // The delegate that created the escrow can use it as its holder, the payer that
+        // funded it gets the rent back.
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account_to_funder(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            delegate.pubkey(),
+            test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &delegate],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address.0)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    #[tokio::test]
+    async fn list_escrow_contents_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // Give the escrow a token account holding an attribute.
+        let payer_pubkey = context.payer.pubkey();
+        let attribute_mint = Keypair::new();
+        let attribute_account = Keypair::new();
+        create_mint(&mut context, &attribute_mint, &payer_pubkey, None, 0)
+            .await
+            .unwrap();
+        create_token_account(
+            &mut context,
+            &attribute_account,
+            &attribute_mint.pubkey(),
+            &escrow_address.0,
+        )
+        .await
+        .unwrap();
+        mint_tokens(
+            &mut context,
+            &attribute_mint.pubkey(),
+            &attribute_account.pubkey(),
+            5,
+            &payer_pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let list_ix = mpl_token_metadata::escrow::list_escrow_contents(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            &[attribute_account.pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[list_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+
+        assert!(simulation.result.unwrap().is_ok());
+        let logs = simulation.simulation_details.unwrap().logs;
+        assert!(logs.iter().any(|log| log.starts_with("Program data: ")));
+
+        // The base NFT's token account isn't owned by the escrow.
+        let list_ix = mpl_token_metadata::escrow::list_escrow_contents(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            &[test_metadata.token.pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[list_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::IncorrectOwner);
+    }
+
+    #[tokio::test]
+    async fn burn_nft_and_close_escrow_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // Give the escrow a token account holding an attribute.
+        let payer_pubkey = context.payer.pubkey();
+        let attribute_mint = Keypair::new();
+        let attribute_account = Keypair::new();
+        create_mint(&mut context, &attribute_mint, &payer_pubkey, None, 0)
+            .await
+            .unwrap();
+        create_token_account(
+            &mut context,
+            &attribute_account,
+            &attribute_mint.pubkey(),
+            &escrow_address.0,
+        )
+        .await
+        .unwrap();
+        mint_tokens(
+            &mut context,
+            &attribute_mint.pubkey(),
+            &attribute_account.pubkey(),
+            1,
+            &payer_pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let burn_ix = mpl_token_metadata::escrow::burn_nft_and_close_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            context.payer.pubkey(),
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            &[attribute_account.pubkey()],
+            None,
+            None,
+        );
+
+        // The escrow still holds an attribute, so it must be emptied first.
+        let tx = Transaction::new_signed_with_payer(
+            &[burn_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::EscrowNotEmpty);
+
+        let burn_ix = mpl_token_metadata::escrow::burn_nft_and_close_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            context.payer.pubkey(),
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            &[],
+            None,
+            None,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[burn_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        for closed in [
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_master_edition.pubkey,
+        ] {
+            let account = context.banks_client.get_account(closed).await.unwrap();
+            assert!(account.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn transfer_into_escrow_success() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let transfer_ix = mpl_token_metadata::escrow::transfer_into_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            None,
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &escrow_address.0,
+                &attribute_test_metadata.mint.pubkey(),
+            );
+        let attribute_dst_account =
+            get_account(&mut context, &escrow_attribute_token_account).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+
+        assert_eq!(attribute_dst.amount, 1);
+        assert_eq!(attribute_dst.owner, escrow_address.0);
+
+        // The base token can't be deposited into its own escrow.
+        let self_deposit_ix = mpl_token_metadata::escrow::transfer_into_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            None,
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[self_deposit_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::CannotDepositBaseToken);
+    }
+
+    #[tokio::test]
+    async fn find_creator_escrows_success() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // Candidates without an escrow are skipped rather than failing the lookup.
+        let find_ix = mpl_token_metadata::escrow::find_creator_escrows(
+            mpl_token_metadata::id(),
+            test_metadata.mint.pubkey(),
+            &[creator.pubkey(), Keypair::new().pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[find_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+
+        assert!(simulation.result.unwrap().is_ok());
+        let logs = simulation.simulation_details.unwrap().logs;
+        assert!(logs.iter().any(|log| log.starts_with("Program data: ")));
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_without_authority_signature() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+
+        let mut ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs::default(),
+        );
+
+        // Craft the instruction so the authority doesn't have to sign.
+        ix.accounts.last_mut().unwrap().is_signer = false;
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidAuthoritySigner);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_ignores_trailing_accounts() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let mut ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        // Without `has_authority`, a signer following the system program is not read as the
+        // authority, so the payer still gets its Token Owned Escrow.
+        ix.accounts
+            .push(AccountMeta::new_readonly(creator.pubkey(), true));
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.authority, EscrowAuthority::TokenOwner);
+
+        let creator_escrow = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+        assert!(context
+            .banks_client
+            .get_account(creator_escrow.0)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn transfer_out_of_escrow_ignores_trailing_accounts() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let escrow_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &escrow_address.0,
+                &attribute_test_metadata.mint.pubkey(),
+            );
+        let payer_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &context.payer.pubkey(),
+                &attribute_test_metadata.mint.pubkey(),
+            );
+
+        let mut transfer_ix = mpl_token_metadata::escrow::transfer_out_of_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            escrow_attribute_token_account,
+            payer_attribute_token_account,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            None,
+            1,
+        );
+
+        // The instruction data says no authority was passed, so an extra account appended
+        // after the fixed accounts must not be mistaken for one.
+        transfer_ix
+            .accounts
+            .push(solana_program::instruction::AccountMeta::new_readonly(
+                Keypair::new().pubkey(),
+                false,
+            ));
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let attribute_dst_account = get_account(&mut context, &payer_attribute_token_account).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+
+        assert_eq!(attribute_dst.amount, 1);
+    }
+
+    #[tokio::test]
+    async fn transfer_out_of_escrow_with_instruction_encoded_before_authority_flag() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let escrow_attribute_token_account =
+            find_escrow_attribute_ata(&escrow_address.0, &attribute_test_metadata.mint.pubkey());
+        let payer_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &context.payer.pubkey(),
+                &attribute_test_metadata.mint.pubkey(),
+            );
+
+        let mut transfer_ix = mpl_token_metadata::escrow::transfer_out_of_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            escrow_attribute_token_account,
+            payer_attribute_token_account,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            None,
+            1,
+        );
+
+        // Older clients pass an authority as the only trailing account, no metadata, and
+        // end the instruction data after the amount.
+        let authority = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        transfer_ix.accounts.pop();
+        transfer_ix
+            .accounts
+            .push(AccountMeta::new_readonly(authority.pubkey(), true));
+        transfer_ix.data.truncate(1 + 8);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let attribute_dst_account = get_account(&mut context, &payer_attribute_token_account).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+
+        assert_eq!(attribute_dst.amount, 1);
+    }
+
+    #[tokio::test]
+    async fn fail_transfer_out_of_escrow_with_non_canonical_stored_bump() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // The escrow sits at its canonical address but records another bump, which must be
+        // caught before the program signs with it.
+        let mut escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let mut escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        escrow.bump = escrow_address.1.wrapping_sub(1);
+        escrow
+            .serialize(&mut escrow_account.data.as_mut_slice())
+            .unwrap();
+        context.set_account(&escrow_address.0, &AccountSharedData::from(escrow_account));
+
+        let escrow_attribute_token_account =
+            find_escrow_attribute_ata(&escrow_address.0, &attribute_test_metadata.mint.pubkey());
+        let payer_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &context.payer.pubkey(),
+                &attribute_test_metadata.mint.pubkey(),
+            );
+
+        let transfer_ix = mpl_token_metadata::escrow::transfer_out_of_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            escrow_attribute_token_account,
+            payer_attribute_token_account,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            None,
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let err = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(err, MetadataError::InvalidEscrowBumpSeed);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_creator_escrow_with_unknown_authority() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs {
+                require_known_authority: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::UpdateAuthorityIncorrect);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_with_mismatched_mints() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let other_metadata = Metadata::new();
+        other_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        // The mint passed in isn't the mint of the metadata.
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            other_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::MintMismatch);
+
+        // The token account passed in holds a different NFT.
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            other_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidTokenAccountMint);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_in_collection_requires_verified_member() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_collection = Metadata::new();
+        test_collection
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        let collection_master_edition = MasterEditionV2::new(&test_collection);
+        collection_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let test_metadata = Metadata::new();
+        test_metadata
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                true,
+                Some(mpl_token_metadata::state::Collection {
+                    key: test_collection.mint.pubkey(),
+                    verified: false,
+                }),
+                None,
+            )
+            .await
+            .unwrap();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_in_collection(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            test_collection.mint.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&ix),
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::NotVerifiedMemberOfCollection);
+
+        let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        test_metadata
+            .verify_collection(
+                &mut context,
+                test_collection.pubkey,
+                &payer,
+                test_collection.mint.pubkey(),
+                collection_master_edition.pubkey,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let blockhash = context
+            .banks_client
+            .get_new_latest_blockhash(&context.last_blockhash)
+            .await
+            .unwrap();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: mpl_token_metadata::state::TokenOwnedEscrow =
+            try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.base_token, test_metadata.mint.pubkey());
+    }
+
+    #[tokio::test]
+    async fn locked_escrow_rejects_transfers() {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let lock_ix = mpl_token_metadata::escrow::set_escrow_lock(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            true,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, lock_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let transfer_ix = mpl_token_metadata::escrow::transfer_into_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            None,
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&transfer_ix),
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::EscrowLocked);
+
+        // Unlocking lets the same transfer through.
+        let unlock_ix = mpl_token_metadata::escrow::set_escrow_lock(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            false,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[unlock_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_attribute_token_account = mpl_token_metadata::escrow::find_escrow_attribute_ata(
+            &escrow_address.0,
+            &attribute_test_metadata.mint.pubkey(),
+        );
+        let attribute_dst_account =
+            get_account(&mut context, &escrow_attribute_token_account).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+
+        assert_eq!(attribute_dst.amount, 1);
+    }
+
+    async fn create_escrow_with_preallocated_account(
+        size: usize,
+        lamports: u64,
+    ) -> (ProgramTestContext, Pubkey, Result<(), BanksClientError>) {
+        let test_metadata = Metadata::new();
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        // Allocate the escrow address ahead of time as a zeroed, program owned account.
+        let mut program_test = program_test();
+        program_test.add_account(
+            escrow_address.0,
+            Account {
+                lamports,
+                data: vec![0; size],
+                owner: mpl_token_metadata::id(),
+                ..Account::default()
+            },
+        );
+        let mut context = program_test.start_with_context().await;
+
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let result = context.banks_client.process_transaction(tx).await;
+
+        (context, escrow_address.0, result)
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_in_preallocated_account() {
+        let (mut context, escrow_address, result) = create_escrow_with_preallocated_account(
+            TokenOwnedEscrow::size_for(&EscrowAuthority::TokenOwner),
+            10_000_000,
+        )
+        .await;
+        result.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address).await;
+        assert_eq!(
+            escrow_account.data.len(),
+            TokenOwnedEscrow::size_for(&EscrowAuthority::TokenOwner)
+        );
+
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.key, Key::TokenOwnedEscrowV2);
+        assert_eq!(escrow.authority, EscrowAuthority::TokenOwner);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_in_preallocated_account_of_wrong_size() {
+        let (_, _, result) =
+            create_escrow_with_preallocated_account(TokenOwnedEscrow::LEN + 1, 10_000_000).await;
+
+        assert_custom_error!(result.unwrap_err(), MetadataError::InvalidEscrowAccountSize);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_in_underfunded_preallocated_account() {
+        let (_, _, result) = create_escrow_with_preallocated_account(
+            TokenOwnedEscrow::size_for(&EscrowAuthority::TokenOwner),
+            1_000,
+        )
+        .await;
+
+        assert_custom_error!(result.unwrap_err(), MetadataError::NotRentExempt);
+    }
+
+    #[tokio::test]
+    async fn idempotent_create_escrow_account_retry_is_a_no_op() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                idempotent: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&ix),
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+
+        // Use a new blockhash so the retry isn't deduplicated.
+        let last_blockhash = context
+            .banks_client
+            .get_new_latest_blockhash(&context.last_blockhash)
+            .await
+            .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let retried_escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(retried_escrow_account, escrow_account);
+    }
+
+    #[tokio::test]
+    async fn fail_idempotent_create_escrow_account_with_conflicting_authority() {
+        let test_metadata = Metadata::new();
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        // An escrow already sits at the token owner address but records another authority.
+        let conflicting_escrow = TokenOwnedEscrow {
+            key: Key::TokenOwnedEscrow,
+            base_token: test_metadata.mint.pubkey(),
+            authority: EscrowAuthority::Creator(Pubkey::new_unique()),
+            bump: escrow_address.1,
+            rent_recipient: None,
+            version: TOKEN_OWNED_ESCROW_VERSION,
+            locked: false,
+            token_standard: Some(TokenStandard::NonFungible),
+            operator: None,
+            funder: None,
+            tag: 0,
+            update_authority: None,
+        };
+        let mut data = conflicting_escrow.try_to_vec().unwrap();
+        data.resize(TokenOwnedEscrow::LEN, 0);
+
+        let mut program_test = program_test();
+        program_test.add_account(
+            escrow_address.0,
+            Account {
+                lamports: 10_000_000,
+                data,
+                owner: mpl_token_metadata::id(),
+                ..Account::default()
+            },
+        );
+        let mut context = program_test.start_with_context().await;
+
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                idempotent: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::AlreadyInitialized);
+    }
+
+    async fn create_token_owner_escrow(
+        context: &mut ProgramTestContext,
+    ) -> (Metadata, MasterEditionV2, Pubkey) {
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(context).await.unwrap();
+        test_master_edition
+            .create_v3(context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        (test_metadata, test_master_edition, escrow_address.0)
+    }
+
+    #[tokio::test]
+    async fn close_escrow_account_to_treasury_success() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, test_master_edition, escrow_address) =
+            create_token_owner_escrow(&mut context).await;
+
+        let (treasury, _) = find_escrow_treasury_account();
+        let escrow_lamports = get_account(&mut context, &escrow_address).await.lamports;
+        let treasury_lamports = context.banks_client.get_balance(treasury).await.unwrap();
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account_to_treasury(
+            mpl_token_metadata::id(),
+            escrow_address,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+
+        let treasury_account = get_account(&mut context, &treasury).await;
+        assert_eq!(
+            treasury_account.lamports,
+            treasury_lamports + escrow_lamports
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_to_close_escrow_account_to_wrong_treasury() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, test_master_edition, escrow_address) =
+            create_token_owner_escrow(&mut context).await;
+
+        let mut close_ix = mpl_token_metadata::escrow::close_escrow_account_to_treasury(
+            mpl_token_metadata::id(),
+            escrow_address,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+        );
+
+        // Swap the treasury for a wallet the payer controls.
+        let wallet = Keypair::new();
+        airdrop(&mut context, &wallet.pubkey(), 1_000_000)
+            .await
+            .unwrap();
+        close_ix.accounts.last_mut().unwrap().pubkey = wallet.pubkey();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::DerivedKeyInvalid);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_on_native_mint() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let native_mint = spl_token::native_mint::id();
+        let escrow_address = find_escrow_account(&native_mint, &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            native_mint,
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidMintForEscrow);
+    }
+
+    #[tokio::test]
+    async fn holder_and_update_authority_escrow_type() {
+        let mut context = program_test().start_with_context().await;
+
+        // The payer both holds the NFT and is its update authority.
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let payer_pubkey = context.payer.pubkey();
+        let metadata = test_metadata.get_data(&mut context).await;
+        assert_eq!(metadata.update_authority, payer_pubkey);
+
+        for (force_creator, authority) in [
+            (false, EscrowAuthority::TokenOwner),
+            (true, EscrowAuthority::Creator(payer_pubkey)),
+        ] {
+            let escrow_address = find_escrow_account(&test_metadata.mint.pubkey(), &authority);
+
+            let ix = mpl_token_metadata::escrow::create_escrow_account(
+                mpl_token_metadata::id(),
+                escrow_address.0,
+                test_metadata.pubkey,
+                test_metadata.mint.pubkey(),
+                test_metadata.token.pubkey(),
+                test_master_edition.pubkey,
+                payer_pubkey,
+                None,
+                CreateEscrowAccountArgs {
+                    force_creator,
+                    ..Default::default()
+                },
+            );
+
+            let tx = Transaction::new_signed_with_payer(
+                &[ix],
+                Some(&payer_pubkey),
+                &[&context.payer],
+                context.last_blockhash,
+            );
+            context.banks_client.process_transaction(tx).await.unwrap();
+
+            let escrow_account = get_account(&mut context, &escrow_address.0).await;
+            let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+            assert_eq!(escrow.authority, authority);
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_account_with_spoofed_system_program() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let mut ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        // The system program follows the payer.
+        ix.accounts[6].pubkey = Pubkey::new_unique();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidSystemProgram);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_accounts_batch_with_spoofed_system_program() {
+        let mut context = program_test().start_with_context().await;
+        let authority = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let mut ix = mpl_token_metadata::escrow::create_escrow_accounts_batch(
+            mpl_token_metadata::id(),
+            context.payer.pubkey(),
+            authority.pubkey(),
+            &[(
+                test_metadata.mint.pubkey(),
+                test_metadata.pubkey,
+                test_metadata.token.pubkey(),
+                test_master_edition.pubkey,
+                context.payer.pubkey(),
+            )],
+        );
+
+        // The system program follows the payer.
+        ix.accounts[1].pubkey = Pubkey::new_unique();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidSystemProgram);
+    }
+
+    #[tokio::test]
+    async fn simulate_create_escrow_account() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                simulate: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // The checks passed, but no rent was committed to the escrow.
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address.0)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    #[tokio::test]
+    async fn fail_simulate_create_escrow_account_with_wrong_escrow() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        // The payer holds the NFT, so its escrow is the token owner one, not a creator one.
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(context.payer.pubkey()),
+        );
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                simulate: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::DerivedKeyInvalid);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_with_memo() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_with_memo(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            b"created for audit".to_vec(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+
+        assert!(simulation.result.unwrap().is_ok());
+        let logs = simulation.simulation_details.unwrap().logs;
+        assert!(logs
+            .iter()
+            .any(|log| log == "Program log: Memo (len 17): \"created for audit\""));
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_with_long_memo() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_with_memo(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            vec![b'a'; MAX_ESCROW_MEMO_LENGTH + 1],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::EscrowMemoTooLong);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_without_creation_fee() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+        let (treasury, _) = find_escrow_treasury_account();
+        let treasury_lamports = context.banks_client.get_balance(treasury).await.unwrap();
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(escrow_account.owner, mpl_token_metadata::id());
+        // Nothing reaches the treasury while `ESCROW_CREATION_FEE` is zero.
+        assert_eq!(
+            context.banks_client.get_balance(treasury).await.unwrap(),
+            treasury_lamports
+        );
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_records_token_standard() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+
+        // Read the token standard again from the NFT's mint and edition.
+        let mint_key = test_metadata.mint.pubkey();
+        let mut mint_account = get_account(&mut context, &mint_key).await;
+        let mut edition_account = get_account(&mut context, &test_master_edition.pubkey).await;
+        let mint_info = AccountInfo::new(
+            &mint_key,
+            false,
+            false,
+            &mut mint_account.lamports,
+            &mut mint_account.data,
+            &mint_account.owner,
+            false,
+            0,
+        );
+        let edition_info = AccountInfo::new(
+            &test_master_edition.pubkey,
+            false,
+            false,
+            &mut edition_account.lamports,
+            &mut edition_account.data,
+            &edition_account.owner,
+            false,
+            0,
+        );
+
+        assert_eq!(
+            escrow.token_standard,
+            Some(check_token_standard(&mint_info, Some(&edition_info)).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn migrate_escrow_records_token_standard() {
+        let test_metadata = Metadata::new();
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        // A version 2 escrow, created before the token standard was recorded.
+        let mut data = vec![Key::TokenOwnedEscrow as u8];
+        data.extend_from_slice(test_metadata.mint.pubkey().as_ref());
+        data.extend_from_slice(&EscrowAuthority::TokenOwner.try_to_vec().unwrap());
+        data.push(escrow_address.1);
+        data.extend_from_slice(&None::<Pubkey>.try_to_vec().unwrap());
+        data.extend_from_slice(&[2, 0]);
+
+        let mut program_test = program_test();
+        program_test.add_account(
+            escrow_address.0,
+            Account {
+                lamports: 10_000_000,
+                data,
+                owner: mpl_token_metadata::id(),
+                ..Account::default()
+            },
+        );
+        let mut context = program_test.start_with_context().await;
+
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let ix = mpl_token_metadata::escrow::migrate_escrow_with_token_standard(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            context.payer.pubkey(),
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(
+            escrow_account.data.len(),
+            TokenOwnedEscrow::size_for(&EscrowAuthority::TokenOwner)
+        );
+
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.key, Key::TokenOwnedEscrowV2);
+        assert_eq!(escrow.version, TOKEN_OWNED_ESCROW_VERSION);
+        assert_eq!(escrow.token_standard, Some(TokenStandard::NonFungible));
+    }
+
+    #[tokio::test]
+    async fn reclaim_orphaned_escrow_of_burned_nft() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // Burn the NFT without going through the escrow.
+        let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        burn(
+            &mut context,
+            test_metadata.pubkey,
+            &payer,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // The payer funded the escrow for the creator, so it gets the rent back.
+        let funder = context.payer.pubkey();
+        let funder_balance = get_account(&mut context, &funder).await.lamports;
+
+        let reclaim_ix = mpl_token_metadata::escrow::reclaim_orphaned_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.mint.pubkey(),
+            creator.pubkey(),
+            Some(funder),
+            &[],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[reclaim_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address.0)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+
+        // The refund dwarfs the transaction fee the funder paid.
+        let funder_balance_after = get_account(&mut context, &funder).await.lamports;
+        assert!(funder_balance_after > funder_balance);
+        assert!(context
+            .banks_client
+            .get_account(creator.pubkey())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn reclaim_orphaned_token_owner_escrow_of_burned_nft() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.update_authority, Some(context.payer.pubkey()));
+
+        // Burning the NFT through Token Metadata clears its metadata along with the token.
+        let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        burn(
+            &mut context,
+            test_metadata.pubkey,
+            &payer,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let metadata_account = context
+            .banks_client
+            .get_account(test_metadata.pubkey)
+            .await
+            .unwrap();
+        assert!(metadata_account.is_none_or(|account| account.data.iter().all(|b| *b == 0)));
+
+        // The update authority recorded on the escrow can still reclaim it.
+        let reclaim_ix = mpl_token_metadata::escrow::reclaim_orphaned_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.mint.pubkey(),
+            context.payer.pubkey(),
+            None,
+            &[],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[reclaim_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address.0)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    #[tokio::test]
+    async fn fail_reclaim_orphaned_escrow_of_unburned_nft() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let reclaim_ix = mpl_token_metadata::escrow::reclaim_orphaned_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.mint.pubkey(),
+            creator.pubkey(),
+            None,
+            &[],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[reclaim_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::BaseTokenNotBurned);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_requiring_immutable_metadata() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                false,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                require_immutable: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(escrow_account.owner, mpl_token_metadata::id());
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_requiring_immutable_metadata() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                true,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                require_immutable: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::MetadataIsMutable);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_requiring_ata() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        // Move the NFT into the payer's associated token account.
+        let ata = spl_associated_token_account::get_associated_token_address(
+            &context.payer.pubkey(),
+            &test_metadata.mint.pubkey(),
+        );
+        #[allow(deprecated)]
+        let create_ata_ix = spl_associated_token_account::create_associated_token_account(
+            &context.payer.pubkey(),
+            &context.payer.pubkey(),
+            &test_metadata.mint.pubkey(),
+        );
+        let transfer_ix = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &test_metadata.token.pubkey(),
+            &ata,
+            &context.payer.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ata_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            ata,
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                require_ata: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(escrow_account.owner, mpl_token_metadata::id());
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_requiring_ata_with_other_token_account() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        // The NFT sits in a token account that isn't the payer's associated token account.
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs {
+                require_ata: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidTokenAccount);
+    }
+
+    #[tokio::test]
+    async fn create_multisig_escrow_account() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let second = Keypair::new();
+        let members = vec![
+            context.payer.pubkey(),
+            second.pubkey(),
+            Pubkey::new_unique(),
+        ];
+        let authority = EscrowAuthority::Multisig(EscrowMultisig::new(members.clone(), 2).unwrap());
+        let escrow_address = find_escrow_account(&test_metadata.mint.pubkey(), &authority);
+
+        let ix = mpl_token_metadata::escrow::create_multisig_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            members,
+            2,
+            &[context.payer.pubkey(), second.pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &second],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        assert_eq!(
+            escrow_account.data.len(),
+            TokenOwnedEscrow::size_for(&authority)
+        );
+
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.authority, authority);
+    }
+
+    #[tokio::test]
+    async fn fail_create_multisig_escrow_account_below_threshold() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let members = vec![
+            context.payer.pubkey(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let authority = EscrowAuthority::Multisig(EscrowMultisig::new(members.clone(), 2).unwrap());
+        let escrow_address = find_escrow_account(&test_metadata.mint.pubkey(), &authority);
+
+        // Only the payer signs, one short of the threshold.
+        let ix = mpl_token_metadata::escrow::create_multisig_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            members,
+            2,
+            &[context.payer.pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::NotEnoughMultisigSigners);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_v2_with_expected_standard() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_v2(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+            TokenStandard::NonFungible,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.token_standard, Some(TokenStandard::NonFungible));
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_v2_with_unexpected_standard() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account_v2(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+            TokenStandard::FungibleAsset,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidTokenStandard);
+    }
+
+    // With `omit_allowlist`, the depositor leaves the allowlist out of the transfer.
+    async fn transfer_into_escrow_with_allowlist(
+        allow_attribute: bool,
+        omit_allowlist: bool,
+    ) -> (ProgramTestContext, Pubkey, Result<(), BanksClientError>) {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        // The payer is the update authority of the parent NFT.
+        let allowed_mint = if allow_attribute {
+            attribute_test_metadata.mint.pubkey()
+        } else {
+            Pubkey::new_unique()
+        };
+        let allowlist_ix = mpl_token_metadata::escrow::set_escrow_allowlist(
+            mpl_token_metadata::id(),
+            find_escrow_allowlist_account(&context.payer.pubkey()).0,
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            vec![allowed_mint],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, allowlist_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let mut transfer_ix = mpl_token_metadata::escrow::transfer_into_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            None,
+            1,
+        );
+        if omit_allowlist {
+            transfer_ix.accounts.pop();
+        }
+
+        let tx = Transaction::new_signed_with_payer(
+            &[transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let result = context.banks_client.process_transaction(tx).await;
+
+        let attribute_dst =
+            find_escrow_attribute_ata(&escrow_address.0, &attribute_test_metadata.mint.pubkey());
+
+        (context, attribute_dst, result)
+    }
+
+    #[tokio::test]
+    async fn transfer_allowed_mint_into_escrow_with_allowlist() {
+        let (mut context, attribute_dst, result) =
+            transfer_into_escrow_with_allowlist(true, false).await;
+        result.unwrap();
+
+        let attribute_dst_account = get_account(&mut context, &attribute_dst).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+        assert_eq!(attribute_dst.amount, 1);
+    }
+
+    #[tokio::test]
+    async fn fail_transfer_disallowed_mint_into_escrow_with_allowlist() {
+        let (_, _, result) = transfer_into_escrow_with_allowlist(false, false).await;
+
+        assert_custom_error!(result.unwrap_err(), MetadataError::TokenNotAllowed);
+    }
+
+    #[tokio::test]
+    async fn fail_transfer_into_escrow_omitting_allowlist() {
+        let (_, _, result) = transfer_into_escrow_with_allowlist(true, true).await;
+
+        assert!(matches!(
+            result.unwrap_err(),
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::NotEnoughAccountKeys
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn escrows_are_allocated_for_their_authority() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let creator = Keypair::new();
+        let authorities = [
+            (EscrowAuthority::TokenOwner, None),
+            (EscrowAuthority::Creator(creator.pubkey()), Some(&creator)),
+        ];
+
+        for (authority, signer) in authorities {
+            let escrow_address = find_escrow_account(&test_metadata.mint.pubkey(), &authority);
+
+            let ix = mpl_token_metadata::escrow::create_escrow_account(
+                mpl_token_metadata::id(),
+                escrow_address.0,
+                test_metadata.pubkey,
+                test_metadata.mint.pubkey(),
+                test_metadata.token.pubkey(),
+                test_master_edition.pubkey,
+                context.payer.pubkey(),
+                signer.map(|signer| signer.pubkey()),
+                CreateEscrowAccountArgs::default(),
+            );
+
+            let tx = match signer {
+                Some(signer) => Transaction::new_signed_with_payer(
+                    &[ix],
+                    Some(&context.payer.pubkey()),
+                    &[&context.payer, signer],
+                    context.last_blockhash,
+                ),
+                None => Transaction::new_signed_with_payer(
+                    &[ix],
+                    Some(&context.payer.pubkey()),
+                    &[&context.payer],
+                    context.last_blockhash,
+                ),
+            };
+            context.banks_client.process_transaction(tx).await.unwrap();
+
+            let escrow_account = get_account(&mut context, &escrow_address.0).await;
+            assert_eq!(
+                escrow_account.data.len(),
+                TokenOwnedEscrow::size_for(&authority)
+            );
+        }
+    }
+
+    async fn close_escrow_after_withdrawing(
+        withdraw: bool,
+    ) -> (ProgramTestContext, Pubkey, Result<(), BanksClientError>) {
+        let mut context = program_test().start_with_context().await;
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+        let escrow_attribute_ata =
+            find_escrow_attribute_ata(&escrow_address.0, &attribute_test_metadata.mint.pubkey());
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let transfer_in_ix = mpl_token_metadata::escrow::transfer_into_escrow(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            None,
+            1,
+        );
+
+        let mut instructions = vec![create_ix, transfer_in_ix];
+        if withdraw {
+            instructions.push(mpl_token_metadata::escrow::transfer_out_of_escrow(
+                mpl_token_metadata::id(),
+                escrow_address.0,
+                parent_test_metadata.pubkey,
+                context.payer.pubkey(),
+                attribute_test_metadata.mint.pubkey(),
+                escrow_attribute_ata,
+                attribute_test_metadata.token.pubkey(),
+                parent_test_metadata.mint.pubkey(),
+                parent_test_metadata.token.pubkey(),
+                None,
+                1,
+            ));
+        }
+
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_if_empty(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            None,
+            &[attribute_test_metadata.mint.pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let result = context.banks_client.process_transaction(tx).await;
+
+        (context, escrow_address.0, result)
+    }
+
+    #[tokio::test]
+    async fn close_escrow_if_empty_after_withdrawing_everything() {
+        let (mut context, escrow_address, result) = close_escrow_after_withdrawing(true).await;
+        result.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    #[tokio::test]
+    async fn fail_close_escrow_if_empty_with_tokens_left() {
+        let (_, _, result) = close_escrow_after_withdrawing(false).await;
+
+        assert_custom_error!(result.unwrap_err(), MetadataError::EscrowNotEmpty);
+    }
+
+    // Creates a token owner escrow holding one attribute token, with a fresh keypair as its
+    // operator.
+    async fn escrow_with_operator() -> (
+        ProgramTestContext,
+        Metadata,
+        MasterEditionV2,
+        Metadata,
+        Pubkey,
+        Keypair,
+    ) {
+        let mut context = program_test().start_with_context().await;
+        let operator = Keypair::new();
+
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(&mut context)
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &parent_test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account_with_deposit(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            parent_test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            1,
+        );
+
+        let operator_ix = mpl_token_metadata::escrow::set_escrow_operator(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            Some(operator.pubkey()),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, operator_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.operator, Some(operator.pubkey()));
+
+        (
+            context,
+            parent_test_metadata,
+            parent_test_master_edition,
+            attribute_test_metadata,
+            escrow_address.0,
+            operator,
+        )
+    }
+
+    #[tokio::test]
+    async fn operator_can_transfer_out_of_escrow() {
+        let (mut context, parent_test_metadata, _, attribute_test_metadata, escrow, operator) =
+            escrow_with_operator().await;
+
+        let escrow_attribute_token_account =
+            find_escrow_attribute_ata(&escrow, &attribute_test_metadata.mint.pubkey());
+        let payer_attribute_token_account =
+            spl_associated_token_account::get_associated_token_address(
+                &context.payer.pubkey(),
+                &attribute_test_metadata.mint.pubkey(),
+            );
+
+        // The operator signs in place of the token owner.
+        let transfer_ix = mpl_token_metadata::escrow::transfer_out_of_escrow(
+            mpl_token_metadata::id(),
+            escrow,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            escrow_attribute_token_account,
+            payer_attribute_token_account,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            Some(operator.pubkey()),
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &operator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let attribute_dst_account = get_account(&mut context, &payer_attribute_token_account).await;
+        let attribute_dst =
+            spl_token::state::Account::unpack_from_slice(&attribute_dst_account.data).unwrap();
+
+        assert_eq!(attribute_dst.amount, 1);
+    }
+
+    #[tokio::test]
+    async fn fail_operator_transfer_after_operator_removed() {
+        let (mut context, parent_test_metadata, _, attribute_test_metadata, escrow, operator) =
+            escrow_with_operator().await;
+
+        let remove_ix = mpl_token_metadata::escrow::set_escrow_operator(
+            mpl_token_metadata::id(),
+            escrow,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            None,
+        );
+
+        let transfer_ix = mpl_token_metadata::escrow::transfer_out_of_escrow(
+            mpl_token_metadata::id(),
+            escrow,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            find_escrow_attribute_ata(&escrow, &attribute_test_metadata.mint.pubkey()),
+            spl_associated_token_account::get_associated_token_address(
+                &context.payer.pubkey(),
+                &attribute_test_metadata.mint.pubkey(),
+            ),
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            Some(operator.pubkey()),
+            1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[remove_ix, transfer_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &operator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::MustBeEscrowAuthority);
+    }
+
+    #[tokio::test]
+    async fn fail_operator_close_escrow() {
+        let (mut context, parent_test_metadata, parent_test_master_edition, _, escrow, operator) =
+            escrow_with_operator().await;
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account(
+            mpl_token_metadata::id(),
+            escrow,
+            parent_test_metadata.pubkey,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_master_edition.pubkey,
+            operator.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            None,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &operator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidAuthorityType);
+    }
+
+    #[tokio::test]
+    async fn fail_operator_set_escrow_operator() {
+        let (mut context, parent_test_metadata, _, _, escrow, operator) =
+            escrow_with_operator().await;
+
+        // The operator can't hand its role to someone else.
+        let operator_ix = mpl_token_metadata::escrow::set_escrow_operator(
+            mpl_token_metadata::id(),
+            escrow,
+            parent_test_metadata.mint.pubkey(),
+            parent_test_metadata.token.pubkey(),
+            operator.pubkey(),
+            context.payer.pubkey(),
+            Some(Pubkey::new_unique()),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[operator_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &operator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::NotEscrowTokenOwner);
+    }
+
+    #[tokio::test]
+    async fn fail_to_create_escrow_with_truncated_accounts() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let mut ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        // Drop the system program.
+        ix.accounts.truncate(6);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::NotEnoughAccounts);
+    }
+
+    #[tokio::test]
+    async fn log_escrow_bump_success() {
+        let mut context = program_test().start_with_context().await;
+        let mint = Pubkey::new_unique();
+
+        let log_ix = mpl_token_metadata::escrow::log_escrow_bump(
+            mpl_token_metadata::id(),
+            mint,
+            EscrowAuthority::TokenOwner,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&log_ix),
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+
+        assert!(simulation.result.unwrap().is_ok());
+        let logs = simulation.simulation_details.unwrap().logs;
+        assert!(logs.iter().any(|log| log.starts_with("Program data: ")));
+
+        // A writable mint is rejected, so the instruction can't be used to touch state.
+        let mut writable_ix = log_ix;
+        writable_ix.accounts[0].is_writable = true;
+
+        let tx = Transaction::new_signed_with_payer(
+            &[writable_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidArgument
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn close_creator_escrow_refunds_funder() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address = find_escrow_account(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+        );
+
+        // The payer funds an escrow for the creator, with both signing.
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.authority, EscrowAuthority::Creator(creator.pubkey()));
+        let funder = context.payer.pubkey();
+        assert_eq!(escrow.funder, Some(funder));
+
+        // The rent can't be sent anywhere but back to the funder.
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account_to_treasury(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            creator.pubkey(),
+            test_metadata.token.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+        assert_custom_error!(error, MetadataError::InvalidFunder);
+
+        let funder_balance = get_account(&mut context, &funder).await.lamports;
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account_to_funder(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            creator.pubkey(),
+            test_metadata.token.pubkey(),
+            funder,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // The refund dwarfs the transaction fee the funder paid.
+        let funder_balance_after = get_account(&mut context, &funder).await.lamports;
+        assert!(funder_balance_after > funder_balance);
+        assert!(context
+            .banks_client
+            .get_account(creator.pubkey())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn escrow_paid_by_its_authority_records_no_funder() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.funder, None);
+    }
+
+    /// Create a master edition that can print, and its first print, held by the payer.
+    async fn master_and_print_edition(
+        context: &mut ProgramTestContext,
+    ) -> (Metadata, MasterEditionV2, EditionMarker) {
+        let test_metadata = Metadata::new();
+        test_metadata.create_v2_default(context).await.unwrap();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_master_edition
+            .create_v3(context, Some(10))
+            .await
+            .unwrap();
+        let print_edition = EditionMarker::new(&test_metadata, &test_master_edition, 1);
+        print_edition.create(context).await.unwrap();
+
+        (test_metadata, test_master_edition, print_edition)
+    }
+
+    fn create_print_edition_escrow_ix(
+        context: &ProgramTestContext,
+        print_edition: &EditionMarker,
+        args: CreateEscrowAccountArgs,
+    ) -> (Pubkey, solana_program::instruction::Instruction) {
+        let (escrow, _) =
+            find_escrow_account(&print_edition.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow,
+            print_edition.new_metadata_pubkey,
+            print_edition.mint.pubkey(),
+            print_edition.token.pubkey(),
+            print_edition.new_edition_pubkey,
+            context.payer.pubkey(),
+            None,
+            args,
+        );
+
+        (escrow, ix)
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_on_printable_master_edition() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, test_master_edition, _) = master_and_print_edition(&mut context).await;
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            None,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow_address.0).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(escrow.token_standard, Some(TokenStandard::NonFungible));
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_on_print_edition() {
+        let mut context = program_test().start_with_context().await;
+        let (_, _, print_edition) = master_and_print_edition(&mut context).await;
+
+        let (_, ix) = create_print_edition_escrow_ix(
+            &context,
+            &print_edition,
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::NotAMasterEdition);
+    }
+
+    #[tokio::test]
+    async fn create_escrow_account_on_allowed_print_edition() {
+        let mut context = program_test().start_with_context().await;
+        let (_, _, print_edition) = master_and_print_edition(&mut context).await;
+
+        let (escrow, ix) = create_print_edition_escrow_ix(
+            &context,
+            &print_edition,
+            CreateEscrowAccountArgs {
+                allow_print_edition: true,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &escrow).await;
+        let escrow: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(
+            escrow.token_standard,
+            Some(TokenStandard::NonFungibleEdition)
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_create_escrow_account_with_unfunded_payer() {
+        let mut context = program_test().start_with_context().await;
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let escrow_address =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+
+        // The holder is the authority, but the rent is paid by a wallet without lamports.
+        let payer = Keypair::new();
+        let ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow_address.0,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            payer.pubkey(),
+            Some(context.payer.pubkey()),
+            CreateEscrowAccountArgs::default(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InsufficientFunds);
+
+        // Nothing was allocated for the escrow.
+        let escrow_account = context
+            .banks_client
+            .get_account(escrow_address.0)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    struct CreatorEscrowWithAttribute {
+        metadata: Metadata,
+        master_edition: MasterEditionV2,
+        attribute: Metadata,
+        escrow: Pubkey,
+        new_escrow: Pubkey,
+        tag: u8,
+    }
+
+    impl CreatorEscrowWithAttribute {
+        fn escrow_attribute_ata(&self) -> Pubkey {
+            find_escrow_attribute_ata(&self.escrow, &self.attribute.mint.pubkey())
+        }
+
+        fn new_escrow_attribute_ata(&self) -> Pubkey {
+            find_escrow_attribute_ata(&self.new_escrow, &self.attribute.mint.pubkey())
+        }
+
+        fn update_authority_ix(
+            &self,
+            context: &ProgramTestContext,
+            authority: Pubkey,
+            new_authority: Pubkey,
+        ) -> solana_program::instruction::Instruction {
+            mpl_token_metadata::escrow::update_escrow_authority_tagged(
+                mpl_token_metadata::id(),
+                self.metadata.pubkey,
+                self.metadata.mint.pubkey(),
+                self.metadata.token.pubkey(),
+                self.master_edition.pubkey,
+                context.payer.pubkey(),
+                authority,
+                new_authority,
+                // The payer funded the creator escrow, so it gets its rent back.
+                Some(context.payer.pubkey()),
+                &[(self.escrow_attribute_ata(), self.new_escrow_attribute_ata())],
+                self.tag,
+            )
+        }
+    }
+
+    /// Create a creator owned escrow on an NFT held by the payer, holding one attribute
+    /// token, along with the attribute token account of the escrow it would be handed to.
+    async fn creator_escrow_with_attribute(
+        context: &mut ProgramTestContext,
+        creator: &Keypair,
+    ) -> CreatorEscrowWithAttribute {
+        creator_escrow_with_attribute_tagged(context, creator, 0).await
+    }
+
+    /// Same as `creator_escrow_with_attribute`, for the creator escrow created with `tag`.
+    async fn creator_escrow_with_attribute_tagged(
+        context: &mut ProgramTestContext,
+        creator: &Keypair,
+        tag: u8,
+    ) -> CreatorEscrowWithAttribute {
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(context).await.unwrap();
+        test_master_edition
+            .create_v3(context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(context)
+            .await
+            .unwrap();
+
+        let (escrow, _) = find_escrow_account_tagged(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+            tag,
+        );
+        let (new_escrow, _) = find_escrow_account_tagged(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::TokenOwner,
+            tag,
+        );
+
+        let escrow = CreatorEscrowWithAttribute {
+            metadata: test_metadata,
+            master_edition: test_master_edition,
+            attribute: attribute_test_metadata,
+            escrow,
+            new_escrow,
+            tag,
+        };
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            escrow.escrow,
+            escrow.metadata.pubkey,
+            escrow.metadata.mint.pubkey(),
+            escrow.metadata.token.pubkey(),
+            escrow.master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs {
+                tag,
+                ..Default::default()
+            },
+        );
+        let create_ata_ix =
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &context.payer.pubkey(),
+                &escrow.escrow,
+                &escrow.attribute.mint.pubkey(),
+            );
+        let deposit_ix = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &escrow.attribute.token.pubkey(),
+            &escrow.escrow_attribute_ata(),
+            &context.payer.pubkey(),
+            &[],
+            1,
+        )
+        .unwrap();
+        let create_new_ata_ix =
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &context.payer.pubkey(),
+                &escrow.new_escrow,
+                &escrow.attribute.mint.pubkey(),
+            );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix, create_ata_ix, deposit_ix, create_new_ata_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        escrow
+    }
+
+    #[tokio::test]
+    async fn update_escrow_authority_hands_creator_escrow_to_holder() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute(&mut context, &creator).await;
+
+        let holder = context.payer.pubkey();
+        let ix = escrow.update_authority_ix(&context, creator.pubkey(), holder);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&holder),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // The creator escrow and its token account are closed.
+        for closed in [escrow.escrow, escrow.escrow_attribute_ata()] {
+            let account = context.banks_client.get_account(closed).await.unwrap();
+            assert!(account.is_none());
+        }
+
+        let new_escrow_account = get_account(&mut context, &escrow.new_escrow).await;
+        let new_escrow: TokenOwnedEscrow =
+            try_from_slice_unchecked(&new_escrow_account.data).unwrap();
+        assert_eq!(new_escrow.authority, EscrowAuthority::TokenOwner);
+        assert_eq!(new_escrow.base_token, escrow.metadata.mint.pubkey());
+        assert_eq!(new_escrow.funder, Some(context.payer.pubkey()));
+
+        let attribute_account = get_account(&mut context, &escrow.new_escrow_attribute_ata()).await;
+        let attribute =
+            spl_token::state::Account::unpack_from_slice(&attribute_account.data).unwrap();
+        assert_eq!(attribute.owner, escrow.new_escrow);
+        assert_eq!(attribute.amount, 1);
+    }
+
+    #[tokio::test]
+    async fn fail_update_escrow_authority_signed_by_other_creator() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute(&mut context, &creator).await;
+
+        // Point the instruction at the real escrow, signed by someone else.
+        let other = Keypair::new();
+        let mut ix = escrow.update_authority_ix(&context, other.pubkey(), context.payer.pubkey());
+        ix.accounts[0].pubkey = escrow.escrow;
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &other],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::MustBeEscrowAuthority);
+    }
+
+    #[tokio::test]
+    async fn fail_update_escrow_authority_without_funder() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute(&mut context, &creator).await;
+
+        let holder = context.payer.pubkey();
+        let ix = mpl_token_metadata::escrow::update_escrow_authority(
+            mpl_token_metadata::id(),
+            escrow.metadata.pubkey,
+            escrow.metadata.mint.pubkey(),
+            escrow.metadata.token.pubkey(),
+            escrow.master_edition.pubkey,
+            context.payer.pubkey(),
+            creator.pubkey(),
+            holder,
+            None,
+            &[(
+                escrow.escrow_attribute_ata(),
+                escrow.new_escrow_attribute_ata(),
+            )],
+        );
+
+        // The rent of the creator escrow can't be sent anywhere but back to its funder.
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&holder),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidFunder);
+    }
+
+    #[tokio::test]
+    async fn fail_update_escrow_authority_to_non_holder() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute(&mut context, &creator).await;
+
+        let ix = escrow.update_authority_ix(&context, creator.pubkey(), Pubkey::new_unique());
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidOwner);
+    }
+
+    #[tokio::test]
+    async fn fail_update_escrow_authority_of_locked_escrow() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute(&mut context, &creator).await;
+
+        let lock_ix = mpl_token_metadata::escrow::set_escrow_lock(
+            mpl_token_metadata::id(),
+            escrow.escrow,
+            escrow.metadata.mint.pubkey(),
+            escrow.metadata.token.pubkey(),
+            creator.pubkey(),
+            context.payer.pubkey(),
+            true,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[lock_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let ix = escrow.update_authority_ix(&context, creator.pubkey(), context.payer.pubkey());
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::EscrowLocked);
+    }
+
+    #[tokio::test]
+    async fn fail_update_escrow_authority_with_unpaired_token_account() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute(&mut context, &creator).await;
+
+        // Drop the destination of the only pair.
+        let mut ix = escrow.update_authority_ix(&context, creator.pubkey(), context.payer.pubkey());
+        ix.accounts.pop();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            BanksClientError::TransactionError(TransactionError::InstructionError(
+                0,
+                InstructionError::NotEnoughAccountKeys
+            ))
+        ));
+    }
+
+    /// Create the untagged and the `tag` 1 token owner escrows of one NFT and move an
+    /// attribute token through the tagged one, returning the tagged escrow address.
+    async fn tagged_escrow_round_trip(
+        context: &mut ProgramTestContext,
+    ) -> (Metadata, MasterEditionV2, Pubkey) {
+        let parent_test_metadata = Metadata::new();
+        let parent_test_master_edition = MasterEditionV2::new(&parent_test_metadata);
+        parent_test_metadata
+            .create_v2_default(context)
+            .await
+            .unwrap();
+        parent_test_master_edition
+            .create_v3(context, Some(0))
+            .await
+            .unwrap();
+
+        let attribute_test_metadata = Metadata::new();
+        attribute_test_metadata
+            .create_v2_default(context)
+            .await
+            .unwrap();
+
+        let mint = parent_test_metadata.mint.pubkey();
+        let (escrow, _) = find_escrow_account(&mint, &EscrowAuthority::TokenOwner);
+        let (tagged_escrow, _) = find_escrow_account_tagged(&mint, &EscrowAuthority::TokenOwner, 1);
+        assert_ne!(escrow, tagged_escrow);
+        let escrow_attribute_ata =
+            find_escrow_attribute_ata(&tagged_escrow, &attribute_test_metadata.mint.pubkey());
+
+        let create_ixs = [(escrow, 0), (tagged_escrow, 1)].map(|(escrow, tag)| {
+            mpl_token_metadata::escrow::create_escrow_account(
+                mpl_token_metadata::id(),
+                escrow,
+                parent_test_metadata.pubkey,
+                mint,
+                parent_test_metadata.token.pubkey(),
+                parent_test_master_edition.pubkey,
+                context.payer.pubkey(),
+                None,
+                CreateEscrowAccountArgs {
+                    tag,
+                    ..Default::default()
+                },
+            )
+        });
+
+        let transfer_in_ix = mpl_token_metadata::escrow::transfer_into_escrow(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            attribute_test_metadata.token.pubkey(),
+            mint,
+            parent_test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            None,
+            1,
+        );
+
+        let transfer_out_ix = mpl_token_metadata::escrow::transfer_out_of_escrow(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            parent_test_metadata.pubkey,
+            context.payer.pubkey(),
+            attribute_test_metadata.mint.pubkey(),
+            escrow_attribute_ata,
+            attribute_test_metadata.token.pubkey(),
+            mint,
+            parent_test_metadata.token.pubkey(),
+            None,
+            1,
+        );
+
+        let mut instructions = create_ixs.to_vec();
+        instructions.extend([transfer_in_ix, transfer_out_ix]);
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(context, &tagged_escrow).await;
+        let toe: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert_eq!(toe.authority, EscrowAuthority::TokenOwner);
+        assert_eq!(toe.tag, 1);
+
+        (
+            parent_test_metadata,
+            parent_test_master_edition,
+            tagged_escrow,
+        )
+    }
+
+    #[tokio::test]
+    async fn close_tagged_escrow_after_round_trip() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, test_master_edition, tagged_escrow) =
+            tagged_escrow_round_trip(&mut context).await;
+
+        let close_ix = mpl_token_metadata::escrow::close_escrow_account(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            test_metadata.token.pubkey(),
+            None,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[close_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let tagged_escrow_account = context
+            .banks_client
+            .get_account(tagged_escrow)
+            .await
+            .unwrap();
+        assert!(tagged_escrow_account.is_none());
+
+        // The untagged escrow of the same NFT is left alone.
+        let (escrow, _) =
+            find_escrow_account(&test_metadata.mint.pubkey(), &EscrowAuthority::TokenOwner);
+        get_account(&mut context, &escrow).await;
+    }
+
+    #[tokio::test]
+    async fn lock_and_set_operator_on_tagged_escrow() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, _, tagged_escrow) = tagged_escrow_round_trip(&mut context).await;
+        let operator = Keypair::new();
+
+        let lock_ix = mpl_token_metadata::escrow::set_escrow_lock(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            true,
+        );
+        let operator_ix = mpl_token_metadata::escrow::set_escrow_operator(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            Some(operator.pubkey()),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[lock_ix, operator_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = get_account(&mut context, &tagged_escrow).await;
+        let toe: TokenOwnedEscrow = try_from_slice_unchecked(&escrow_account.data).unwrap();
+        assert!(toe.locked);
+        assert_eq!(toe.operator, Some(operator.pubkey()));
+        assert_eq!(toe.tag, 1);
+    }
+
+    #[tokio::test]
+    async fn verify_and_list_tagged_escrow() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, _, tagged_escrow) = tagged_escrow_round_trip(&mut context).await;
+
+        let verify_ix = mpl_token_metadata::escrow::verify_escrow_authority(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            context.payer.pubkey(),
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[verify_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        // Give the tagged escrow a token account to list.
+        let payer_pubkey = context.payer.pubkey();
+        let attribute_mint = Keypair::new();
+        let attribute_account = Keypair::new();
+        create_mint(&mut context, &attribute_mint, &payer_pubkey, None, 0)
+            .await
+            .unwrap();
+        create_token_account(
+            &mut context,
+            &attribute_account,
+            &attribute_mint.pubkey(),
+            &tagged_escrow,
+        )
+        .await
+        .unwrap();
+
+        let list_ix = mpl_token_metadata::escrow::list_escrow_contents(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            &[attribute_account.pubkey()],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[list_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let simulation = context.banks_client.simulate_transaction(tx).await.unwrap();
+
+        assert!(simulation.result.unwrap().is_ok());
+        let logs = simulation.simulation_details.unwrap().logs;
+        assert!(logs.iter().any(|log| log.starts_with("Program data: ")));
+    }
+
+    #[tokio::test]
+    async fn burn_nft_and_close_tagged_escrow() {
+        let mut context = program_test().start_with_context().await;
+        let (test_metadata, test_master_edition, tagged_escrow) =
+            tagged_escrow_round_trip(&mut context).await;
+
+        let burn_ix = mpl_token_metadata::escrow::burn_nft_and_close_escrow(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.pubkey,
+            context.payer.pubkey(),
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            &[],
+            None,
+            None,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[burn_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        for closed in [tagged_escrow, test_metadata.pubkey] {
+            let account = context.banks_client.get_account(closed).await.unwrap();
+            assert!(account.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn reclaim_orphaned_tagged_escrow_of_burned_nft() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+
+        let test_metadata = Metadata::new();
+        let test_master_edition = MasterEditionV2::new(&test_metadata);
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+        test_master_edition
+            .create_v3(&mut context, Some(0))
+            .await
+            .unwrap();
+
+        let (tagged_escrow, _) = find_escrow_account_tagged(
+            &test_metadata.mint.pubkey(),
+            &EscrowAuthority::Creator(creator.pubkey()),
+            1,
+        );
+
+        let create_ix = mpl_token_metadata::escrow::create_escrow_account(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.pubkey,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            context.payer.pubkey(),
+            Some(creator.pubkey()),
+            CreateEscrowAccountArgs {
+                tag: 1,
+                ..Default::default()
+            },
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[create_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let payer = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        burn(
+            &mut context,
+            test_metadata.pubkey,
+            &payer,
+            test_metadata.mint.pubkey(),
+            test_metadata.token.pubkey(),
+            test_master_edition.pubkey,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // The payer funded the escrow for the creator, so it gets the rent back.
+        let reclaim_ix = mpl_token_metadata::escrow::reclaim_orphaned_escrow(
+            mpl_token_metadata::id(),
+            tagged_escrow,
+            test_metadata.mint.pubkey(),
+            creator.pubkey(),
+            Some(context.payer.pubkey()),
+            &[],
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[reclaim_ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let escrow_account = context
+            .banks_client
+            .get_account(tagged_escrow)
+            .await
+            .unwrap();
+        assert!(escrow_account.is_none());
+    }
+
+    #[tokio::test]
+    async fn update_escrow_authority_hands_tagged_creator_escrow_to_holder() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let escrow = creator_escrow_with_attribute_tagged(&mut context, &creator, 1).await;
+
+        let holder = context.payer.pubkey();
+        let ix = escrow.update_authority_ix(&context, creator.pubkey(), holder);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&holder),
+            &[&context.payer, &creator],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let account = context
+            .banks_client
+            .get_account(escrow.escrow)
+            .await
+            .unwrap();
+        assert!(account.is_none());
+
+        // The holder's escrow is the one with the same tag.
+        let new_escrow_account = get_account(&mut context, &escrow.new_escrow).await;
+        let new_escrow: TokenOwnedEscrow =
+            try_from_slice_unchecked(&new_escrow_account.data).unwrap();
+        assert_eq!(new_escrow.authority, EscrowAuthority::TokenOwner);
+        assert_eq!(new_escrow.tag, 1);
+
+        let attribute_account = get_account(&mut context, &escrow.new_escrow_attribute_ata()).await;
+        let attribute =
+            spl_token::state::Account::unpack_from_slice(&attribute_account.data).unwrap();
+        assert_eq!(attribute.amount, 1);
+    }
+
+    // A program data account naming `upgrade_authority`, since the test validator loads the
+    // program without one, and a funded escrow treasury.
+    async fn start_with_funded_treasury(upgrade_authority: &Pubkey) -> ProgramTestContext {
+        let mut data = vec![3, 0, 0, 0];
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(upgrade_authority.as_ref());
+
+        let mut program_test = program_test();
+        program_test.add_account(
+            find_program_data_account(&mpl_token_metadata::id()).0,
+            Account {
+                lamports: 10_000_000,
+                data,
+                owner: bpf_loader_upgradeable::id(),
+                ..Account::default()
+            },
+        );
+        program_test.add_account(
+            find_escrow_treasury_account().0,
+            Account {
+                lamports: 10_000_000,
+                ..Account::default()
+            },
+        );
+        program_test.start_with_context().await
+    }
+
+    #[tokio::test]
+    async fn withdraw_escrow_treasury_success() {
+        let upgrade_authority = Keypair::new();
+        let mut context = start_with_funded_treasury(&upgrade_authority.pubkey()).await;
+
+        let (treasury, _) = find_escrow_treasury_account();
+        let destination = Keypair::new().pubkey();
+
+        let ix = mpl_token_metadata::escrow::withdraw_escrow_treasury(
+            mpl_token_metadata::id(),
+            upgrade_authority.pubkey(),
+            destination,
+            10_000_000,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &upgrade_authority],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        assert_eq!(context.banks_client.get_balance(treasury).await.unwrap(), 0);
+        assert_eq!(
+            context.banks_client.get_balance(destination).await.unwrap(),
+            10_000_000
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_withdraw_escrow_treasury_without_upgrade_authority() {
+        let upgrade_authority = Keypair::new();
+        let mut context = start_with_funded_treasury(&upgrade_authority.pubkey()).await;
+
+        let ix = mpl_token_metadata::escrow::withdraw_escrow_treasury(
+            mpl_token_metadata::id(),
+            context.payer.pubkey(),
+            context.payer.pubkey(),
+            10_000_000,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidTreasuryAuthority);
+    }
+
+    #[tokio::test]
+    async fn fail_withdraw_escrow_treasury_below_rent_exemption() {
+        let upgrade_authority = Keypair::new();
+        let mut context = start_with_funded_treasury(&upgrade_authority.pubkey()).await;
+
+        // Leave a single lamport behind in the treasury.
+        let ix = mpl_token_metadata::escrow::withdraw_escrow_treasury(
+            mpl_token_metadata::id(),
+            upgrade_authority.pubkey(),
+            context.payer.pubkey(),
+            10_000_000 - 1,
+        );
+
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &upgrade_authority],
+            context.last_blockhash,
+        );
+        let error = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(error, MetadataError::InvalidTreasuryWithdrawal);
+    }
 }
@@ -0,0 +1,97 @@
+#![cfg(feature = "test-bpf")]
+pub mod utils;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use mpl_token_metadata::{
+    error::MetadataError,
+    instruction::resize_metadata,
+    state::{Metadata as ProgramMetadata, MAX_METADATA_LEN},
+    ID as PROGRAM_ID,
+};
+use num_traits::FromPrimitive;
+use solana_program_test::*;
+use solana_sdk::{
+    account::AccountSharedData,
+    instruction::InstructionError,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use utils::*;
+
+async fn shrink_to_legacy_size(context: &mut ProgramTestContext, metadata: &Metadata) -> usize {
+    let mut md_account = get_account(context, &metadata.pubkey).await;
+    let md = ProgramMetadata::deserialize(&mut md_account.data.as_slice()).unwrap();
+
+    // Older accounts were allocated without room for the newer optional fields.
+    md_account.data = md.try_to_vec().unwrap();
+    let legacy_len = md_account.data.len();
+
+    let md_account_shared_data: AccountSharedData = md_account.into();
+    context.set_account(&metadata.pubkey, &md_account_shared_data);
+
+    legacy_len
+}
+
+#[tokio::test]
+async fn successfully_resize_legacy_metadata() {
+    let mut context = program_test().start_with_context().await;
+
+    let test_metadata = Metadata::new();
+    test_metadata.create_v2_default(&mut context).await.unwrap();
+    let expected = test_metadata.get_data(&mut context).await;
+
+    let legacy_len = shrink_to_legacy_size(&mut context, &test_metadata).await;
+    assert!(legacy_len < MAX_METADATA_LEN);
+
+    let ix = resize_metadata(
+        PROGRAM_ID,
+        test_metadata.pubkey,
+        context.payer.pubkey(),
+        context.payer.pubkey(),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(tx).await.unwrap();
+
+    let md_account = get_account(&mut context, &test_metadata.pubkey).await;
+    assert_eq!(md_account.data.len(), MAX_METADATA_LEN);
+    assert!(md_account.data[legacy_len..].iter().all(|b| *b == 0));
+
+    let metadata = ProgramMetadata::deserialize(&mut md_account.data.as_slice()).unwrap();
+    assert_eq!(metadata, expected);
+}
+
+#[tokio::test]
+async fn fail_to_resize_metadata_with_wrong_update_authority() {
+    let mut context = program_test().start_with_context().await;
+
+    let test_metadata = Metadata::new();
+    test_metadata.create_v2_default(&mut context).await.unwrap();
+    shrink_to_legacy_size(&mut context, &test_metadata).await;
+
+    let fake_authority = Keypair::new();
+    let ix = resize_metadata(
+        PROGRAM_ID,
+        test_metadata.pubkey,
+        fake_authority.pubkey(),
+        context.payer.pubkey(),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &fake_authority],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .unwrap_err();
+
+    assert_custom_error!(err, MetadataError::UpdateAuthorityIncorrect);
+}
@@ -1,7 +1,12 @@
 #![cfg(feature = "test-bpf")]
 
+use num_traits::FromPrimitive;
 use solana_program_test::*;
-use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
 
 use utils::*;
 
@@ -12,7 +17,7 @@ mod sign_metadata {
     use solana_sdk::transaction::Transaction;
 
     use mpl_token_metadata::instruction::{remove_creator_verification, sign_metadata};
-    use mpl_token_metadata::state::Creator;
+    use mpl_token_metadata::{error::MetadataError, state::Creator};
 
     use super::*;
 
@@ -81,4 +86,128 @@ mod sign_metadata {
         let after_remove = test_meta.get_data(&mut context).await;
         assert!(!after_remove.data.creators.unwrap()[1].verified);
     }
+
+    #[tokio::test]
+    async fn unverify_creator_only_changes_signing_creator() {
+        let mut context = program_test().start_with_context().await;
+        let creator = Keypair::new();
+        let ua_creator = context.payer.pubkey();
+        let test_meta = Metadata::new();
+        test_meta
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                Some(vec![
+                    Creator {
+                        address: ua_creator,
+                        verified: true,
+                        share: 40,
+                    },
+                    Creator {
+                        address: creator.pubkey(),
+                        verified: false,
+                        share: 60,
+                    },
+                ]),
+                10,
+                false,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let sign_ix = sign_metadata(mpl_token_metadata::id(), test_meta.pubkey, creator.pubkey());
+        let remove_ix = remove_creator_verification(
+            mpl_token_metadata::id(),
+            test_meta.pubkey,
+            creator.pubkey(),
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[sign_ix],
+            Some(&context.payer.pubkey()),
+            &[&creator, &context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let before = test_meta.get_data(&mut context).await;
+        let before_len = get_account(&mut context, &test_meta.pubkey)
+            .await
+            .data
+            .len();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[remove_ix],
+            Some(&context.payer.pubkey()),
+            &[&creator, &context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let after = test_meta.get_data(&mut context).await;
+        let after_len = get_account(&mut context, &test_meta.pubkey)
+            .await
+            .data
+            .len();
+
+        assert_eq!(after_len, before_len);
+        assert_eq!(after.update_authority, before.update_authority);
+
+        let creators = after.data.creators.unwrap();
+        let expected = before.data.creators.unwrap();
+        assert!(creators[0].verified);
+        assert!(!creators[1].verified);
+        for (creator, expected) in creators.iter().zip(expected.iter()) {
+            assert_eq!(creator.address, expected.address);
+            assert_eq!(creator.share, expected.share);
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_to_unverify_unlisted_creator() {
+        let mut context = program_test().start_with_context().await;
+        let not_creator = Keypair::new();
+        let payer_pubkey = context.payer.pubkey();
+        let test_meta = Metadata::new();
+        test_meta
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                Some(vec![Creator {
+                    address: payer_pubkey,
+                    verified: true,
+                    share: 100,
+                }]),
+                10,
+                false,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let remove_ix = remove_creator_verification(
+            mpl_token_metadata::id(),
+            test_meta.pubkey,
+            not_creator.pubkey(),
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[remove_ix],
+            Some(&context.payer.pubkey()),
+            &[&not_creator, &context.payer],
+            context.last_blockhash,
+        );
+        let err = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(err, MetadataError::CreatorNotFound);
+    }
 }
@@ -0,0 +1,155 @@
+#![cfg(feature = "test-bpf")]
+pub mod utils;
+
+use mpl_token_metadata::{
+    error::MetadataError,
+    id,
+    instruction::{update_metadata_fields, UpdateMetadataFieldsArgs},
+    state::{Creator, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH},
+    utils::puffed_out_string,
+};
+use num_traits::FromPrimitive;
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use utils::*;
+
+mod update_metadata_fields {
+    use super::*;
+
+    #[tokio::test]
+    async fn success_update_only_provided_fields() {
+        let mut context = program_test().start_with_context().await;
+        let test_metadata = Metadata::new();
+        let creators = vec![Creator {
+            address: context.payer.pubkey(),
+            verified: true,
+            share: 100,
+        }];
+
+        test_metadata
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                Some(creators.clone()),
+                10,
+                true,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let ix = update_metadata_fields(
+            id(),
+            test_metadata.pubkey,
+            context.payer.pubkey(),
+            UpdateMetadataFieldsArgs {
+                uri: Some("new uri".to_string()),
+                seller_fee_basis_points: Some(250),
+                ..Default::default()
+            },
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        context.banks_client.process_transaction(tx).await.unwrap();
+
+        let metadata = test_metadata.get_data(&mut context).await;
+        assert_eq!(
+            metadata.data.name,
+            puffed_out_string("Test", MAX_NAME_LENGTH)
+        );
+        assert_eq!(
+            metadata.data.symbol,
+            puffed_out_string("TST", MAX_SYMBOL_LENGTH)
+        );
+        assert_eq!(
+            metadata.data.uri,
+            puffed_out_string("new uri", MAX_URI_LENGTH)
+        );
+        assert_eq!(metadata.data.seller_fee_basis_points, 250);
+        assert_eq!(metadata.data.creators, Some(creators));
+    }
+
+    #[tokio::test]
+    async fn fail_name_too_long() {
+        let mut context = program_test().start_with_context().await;
+        let test_metadata = Metadata::new();
+        test_metadata
+            .create_v2(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                true,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let ix = update_metadata_fields(
+            id(),
+            test_metadata.pubkey,
+            context.payer.pubkey(),
+            UpdateMetadataFieldsArgs {
+                name: Some("x".repeat(MAX_NAME_LENGTH + 1)),
+                ..Default::default()
+            },
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let err = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(err, MetadataError::NameTooLong);
+    }
+
+    #[tokio::test]
+    async fn fail_immutable_metadata() {
+        let mut context = program_test().start_with_context().await;
+        let test_metadata = Metadata::new();
+        test_metadata.create_v2_default(&mut context).await.unwrap();
+
+        let ix = update_metadata_fields(
+            id(),
+            test_metadata.pubkey,
+            context.payer.pubkey(),
+            UpdateMetadataFieldsArgs {
+                symbol: Some("NEW".to_string()),
+                ..Default::default()
+            },
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let err = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(err, MetadataError::DataIsImmutable);
+    }
+}
@@ -120,4 +120,124 @@ mod update_primary_sale_happened_via_token {
 
         assert_custom_error!(result, MetadataError::MintMismatch);
     }
+
+    #[tokio::test]
+    async fn cannot_flip_primary_sale_back_to_false() {
+        let mut context = program_test().start_with_context().await;
+        let test_metadata = Metadata::new();
+
+        test_metadata
+            .create(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                true,
+                0,
+            )
+            .await
+            .unwrap();
+
+        test_metadata
+            .update_primary_sale_happened_via_token(&mut context)
+            .await
+            .unwrap();
+
+        // Setting the flag again is a no-op, since it can only ever be set to true.
+        let last_blockhash = context
+            .banks_client
+            .get_new_latest_blockhash(&context.last_blockhash)
+            .await
+            .unwrap();
+        context.last_blockhash = last_blockhash;
+
+        test_metadata
+            .update_primary_sale_happened_via_token(&mut context)
+            .await
+            .unwrap();
+
+        let metadata = test_metadata.get_data(&mut context).await;
+        assert!(metadata.primary_sale_happened);
+
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_metadata_accounts_v2(
+                id(),
+                test_metadata.pubkey,
+                context.payer.pubkey(),
+                None,
+                None,
+                Some(false),
+                None,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let result = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(result, MetadataError::PrimarySaleCanOnlyBeFlippedToTrue);
+
+        let metadata = test_metadata.get_data(&mut context).await;
+        assert!(metadata.primary_sale_happened);
+    }
+
+    #[tokio::test]
+    async fn fail_without_token_balance() {
+        let mut context = program_test().start_with_context().await;
+        let test_metadata = Metadata::new();
+
+        test_metadata
+            .create(
+                &mut context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                true,
+                0,
+            )
+            .await
+            .unwrap();
+
+        // An empty token account for the right mint doesn't prove ownership of the token.
+        let empty_token_account = Keypair::new();
+        let payer_pubkey = context.payer.pubkey();
+        create_token_account(
+            &mut context,
+            &empty_token_account,
+            &test_metadata.mint.pubkey(),
+            &payer_pubkey,
+        )
+        .await
+        .unwrap();
+
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::update_primary_sale_happened_via_token(
+                id(),
+                test_metadata.pubkey,
+                context.payer.pubkey(),
+                empty_token_account.pubkey(),
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let result = context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .unwrap_err();
+
+        assert_custom_error!(result, MetadataError::NoBalanceInAccountForAuthorization);
+
+        let metadata = test_metadata.get_data(&mut context).await;
+        assert!(!metadata.primary_sale_happened);
+    }
 }
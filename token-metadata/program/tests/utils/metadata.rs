@@ -556,6 +556,34 @@ impl Metadata {
         context.banks_client.process_transaction(tx).await
     }
 
+    pub async fn verify_collection_item(
+        &self,
+        context: &mut ProgramTestContext,
+        collection: Pubkey,
+        collection_authority: &Keypair,
+        collection_mint: Pubkey,
+        collection_master_edition_account: Pubkey,
+        collection_authority_record: Option<Pubkey>,
+    ) -> Result<(), BanksClientError> {
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction::verify_collection_item(
+                id(),
+                self.pubkey,
+                collection_authority.pubkey(),
+                context.payer.pubkey(),
+                collection_mint,
+                collection,
+                collection_master_edition_account,
+                collection_authority_record,
+            )],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, collection_authority],
+            context.last_blockhash,
+        );
+
+        context.banks_client.process_transaction(tx).await
+    }
+
     pub async fn set_and_verify_collection(
         &self,
         context: &mut ProgramTestContext,
@@ -0,0 +1,221 @@
+#![cfg(feature = "test-bpf")]
+pub mod utils;
+
+use mpl_token_metadata::{
+    error::MetadataError,
+    state::{Collection, CollectionDetails},
+};
+use num_traits::FromPrimitive;
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use utils::*;
+
+mod verify_collection_item {
+    use super::*;
+
+    // Creates a collection NFT with the given details and master edition supply, and an
+    // unverified item pointing at it.
+    async fn collection_with_item(
+        context: &mut ProgramTestContext,
+        collection_details: Option<CollectionDetails>,
+        max_supply: Option<u64>,
+    ) -> (Metadata, MasterEditionV2, Metadata) {
+        let test_collection = Metadata::new();
+        test_collection
+            .create_v3(
+                context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                false,
+                None,
+                None,
+                collection_details,
+            )
+            .await
+            .unwrap();
+        let collection_master_edition_account = MasterEditionV2::new(&test_collection);
+        collection_master_edition_account
+            .create_v3(context, max_supply)
+            .await
+            .unwrap();
+
+        let test_metadata = Metadata::new();
+        test_metadata
+            .create_v2(
+                context,
+                "Test".to_string(),
+                "TST".to_string(),
+                "uri".to_string(),
+                None,
+                10,
+                false,
+                Some(Collection {
+                    key: test_collection.mint.pubkey(),
+                    verified: false,
+                }),
+                None,
+            )
+            .await
+            .unwrap();
+
+        (
+            test_collection,
+            collection_master_edition_account,
+            test_metadata,
+        )
+    }
+
+    #[tokio::test]
+    async fn successfully_verify_sized_collection_item() {
+        let mut context = program_test().start_with_context().await;
+        let (test_collection, collection_master_edition_account, test_metadata) =
+            collection_with_item(&mut context, DEFAULT_COLLECTION_DETAILS, Some(0)).await;
+
+        let kp = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        test_metadata
+            .verify_collection_item(
+                &mut context,
+                test_collection.pubkey,
+                &kp,
+                test_collection.mint.pubkey(),
+                collection_master_edition_account.pubkey,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let metadata_after = test_metadata.get_data(&mut context).await;
+        assert!(metadata_after.collection.unwrap().verified);
+
+        let collection_after = test_collection.get_data(&mut context).await;
+        assert_eq!(
+            collection_after.collection_details,
+            Some(CollectionDetails::V1 { size: 1 })
+        );
+    }
+
+    #[tokio::test]
+    async fn successfully_verify_unsized_collection_item() {
+        let mut context = program_test().start_with_context().await;
+        let (test_collection, collection_master_edition_account, test_metadata) =
+            collection_with_item(&mut context, None, Some(0)).await;
+
+        let kp = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        test_metadata
+            .verify_collection_item(
+                &mut context,
+                test_collection.pubkey,
+                &kp,
+                test_collection.mint.pubkey(),
+                collection_master_edition_account.pubkey,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let metadata_after = test_metadata.get_data(&mut context).await;
+        assert!(metadata_after.collection.unwrap().verified);
+
+        let collection_after = test_collection.get_data(&mut context).await;
+        assert_eq!(collection_after.collection_details, None);
+    }
+
+    #[tokio::test]
+    async fn fail_non_unique_master_edition() {
+        let mut context = program_test().start_with_context().await;
+        let (test_collection, collection_master_edition_account, test_metadata) =
+            collection_with_item(&mut context, DEFAULT_COLLECTION_DETAILS, Some(1)).await;
+
+        let kp = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        let err = test_metadata
+            .verify_collection_item(
+                &mut context,
+                test_collection.pubkey,
+                &kp,
+                test_collection.mint.pubkey(),
+                collection_master_edition_account.pubkey,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_custom_error!(err, MetadataError::CollectionMustBeAUniqueMasterEdition);
+
+        let metadata_after = test_metadata.get_data(&mut context).await;
+        assert!(!metadata_after.collection.unwrap().verified);
+    }
+
+    #[tokio::test]
+    async fn fail_collection_authority_mismatch() {
+        let mut context = program_test().start_with_context().await;
+        let (test_collection, collection_master_edition_account, test_metadata) =
+            collection_with_item(&mut context, DEFAULT_COLLECTION_DETAILS, Some(0)).await;
+
+        let wrong_authority = Keypair::new();
+        let err = test_metadata
+            .verify_collection_item(
+                &mut context,
+                test_collection.pubkey,
+                &wrong_authority,
+                test_collection.mint.pubkey(),
+                collection_master_edition_account.pubkey,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_custom_error!(err, MetadataError::InvalidCollectionUpdateAuthority);
+
+        let collection_after = test_collection.get_data(&mut context).await;
+        assert_eq!(
+            collection_after.collection_details,
+            Some(CollectionDetails::V1 { size: 0 })
+        );
+    }
+
+    #[tokio::test]
+    async fn fail_verify_already_verified() {
+        let mut context = program_test().start_with_context().await;
+        let (test_collection, collection_master_edition_account, test_metadata) =
+            collection_with_item(&mut context, DEFAULT_COLLECTION_DETAILS, Some(0)).await;
+
+        let kp = Keypair::from_bytes(&context.payer.to_bytes()).unwrap();
+        test_metadata
+            .verify_collection_item(
+                &mut context,
+                test_collection.pubkey,
+                &kp,
+                test_collection.mint.pubkey(),
+                collection_master_edition_account.pubkey,
+                None,
+            )
+            .await
+            .unwrap();
+
+        context.warp_to_slot(100).unwrap();
+
+        let err = test_metadata
+            .verify_collection_item(
+                &mut context,
+                test_collection.pubkey,
+                &kp,
+                test_collection.mint.pubkey(),
+                collection_master_edition_account.pubkey,
+                None,
+            )
+            .await
+            .unwrap_err();
+        assert_custom_error!(err, MetadataError::AlreadyVerified);
+
+        let collection_after = test_collection.get_data(&mut context).await;
+        assert_eq!(
+            collection_after.collection_details,
+            Some(CollectionDetails::V1 { size: 1 })
+        );
+    }
+}